@@ -0,0 +1,187 @@
+//! Pointer acceleration scales the relative motions reported by devices before they move the
+//! pointer.
+//!
+//! Motions are computed with subpixel precision. The fractional part of each motion is kept and
+//! added to the next one, so that slow movements are not lost.
+
+use crate::input::DeviceId;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// The default acceleration numerator.
+pub const DEFAULT_ACCELERATION_NUMERATOR: u16 = 2;
+/// The default acceleration denominator.
+pub const DEFAULT_ACCELERATION_DENOMINATOR: u16 = 1;
+/// The default threshold above which acceleration applies.
+pub const DEFAULT_THRESHOLD: u16 = 4;
+
+/// For the adaptive profile, the velocity in units per millisecond below which no acceleration
+/// applies.
+const ADAPTIVE_BASE_VELOCITY: f32 = 0.4;
+/// For the adaptive profile, the increase of the acceleration factor per unit of velocity above
+/// the base velocity.
+const ADAPTIVE_GAIN: f32 = 0.8;
+/// For the adaptive profile, the bounds of the time between two motions in milliseconds. This
+/// avoids huge velocities for events sent in bursts, and null velocities after a pause.
+const ADAPTIVE_TIME_BOUNDS: (f32, f32) = (1., 100.);
+
+/// An acceleration profile.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AccelProfile {
+	/// The model of the X protocol: motions larger than the threshold are multiplied by the
+	/// acceleration fraction.
+	#[default]
+	Classic,
+	/// The acceleration factor grows smoothly with the velocity of the device, up to the
+	/// acceleration fraction.
+	Adaptive,
+	/// Motions are only scaled by the speed of the device.
+	Flat,
+}
+
+/// The acceleration settings, as set by `ChangePointerControl`.
+#[derive(Clone)]
+pub struct PointerControl {
+	/// The numerator of the acceleration fraction.
+	pub numerator: u16,
+	/// The denominator of the acceleration fraction.
+	pub denominator: u16,
+	/// The threshold above which acceleration applies, in units.
+	pub threshold: u16,
+}
+
+impl PointerControl {
+	/// Returns the acceleration fraction as a factor.
+	///
+	/// A numerator of zero disables acceleration instead of stopping fast motions.
+	pub fn get_factor(&self) -> f32 {
+		if self.numerator == 0 {
+			return 1.;
+		}
+		self.numerator as f32 / self.denominator.max(1) as f32
+	}
+}
+
+impl Default for PointerControl {
+	fn default() -> Self {
+		Self {
+			numerator: DEFAULT_ACCELERATION_NUMERATOR,
+			denominator: DEFAULT_ACCELERATION_DENOMINATOR,
+			threshold: DEFAULT_THRESHOLD,
+		}
+	}
+}
+
+/// The acceleration state of a device.
+struct DeviceAcceleration {
+	/// The acceleration profile.
+	profile: AccelProfile,
+	/// The factor applied to every motion of the device.
+	speed: f32,
+
+	/// The fractional parts of the motion not applied yet.
+	remainder: (f32, f32),
+	/// The instant of the last motion.
+	last_motion: Option<Instant>,
+}
+
+impl Default for DeviceAcceleration {
+	fn default() -> Self {
+		Self {
+			profile: AccelProfile::default(),
+			speed: 1.,
+
+			remainder: (0., 0.),
+			last_motion: None,
+		}
+	}
+}
+
+/// Pointer acceleration for every device.
+#[derive(Default)]
+pub struct Acceleration {
+	/// The acceleration settings.
+	control: PointerControl,
+	/// The state of each device. The key is the ID of the device.
+	devices: HashMap<DeviceId, DeviceAcceleration>,
+}
+
+impl Acceleration {
+	/// Returns an immutable reference to the acceleration settings.
+	pub fn get_control(&self) -> &PointerControl {
+		&self.control
+	}
+
+	/// Returns a mutable reference to the acceleration settings.
+	pub fn get_control_mut(&mut self) -> &mut PointerControl {
+		&mut self.control
+	}
+
+	/// Sets the acceleration profile of the given device.
+	pub fn set_profile(&mut self, device: DeviceId, profile: AccelProfile) {
+		self.devices.entry(device).or_default().profile = profile;
+	}
+
+	/// Sets the factor applied to every motion of the given device.
+	pub fn set_speed(&mut self, device: DeviceId, speed: f32) {
+		self.devices.entry(device).or_default().speed = speed;
+	}
+
+	/// Forgets the state of the given device.
+	pub fn remove_device(&mut self, device: DeviceId) {
+		self.devices.remove(&device);
+	}
+
+	/// Returns the acceleration factor for the given motion.
+	///
+	/// Arguments:
+	/// - `profile` is the acceleration profile.
+	/// - `delta` is the motion.
+	/// - `elapsed` is the time since the previous motion in milliseconds, if any.
+	fn get_factor(&self, profile: AccelProfile, delta: (f32, f32), elapsed: Option<f32>) -> f32 {
+		match profile {
+			AccelProfile::Classic => {
+				let threshold = self.control.threshold as f32;
+				if delta.0.abs() + delta.1.abs() > threshold {
+					self.control.get_factor()
+				} else {
+					1.
+				}
+			}
+
+			AccelProfile::Adaptive => {
+				let (min, max) = ADAPTIVE_TIME_BOUNDS;
+				let elapsed = elapsed.unwrap_or(max).clamp(min, max);
+				let velocity = delta.0.hypot(delta.1) / elapsed;
+
+				let factor = 1. + (velocity - ADAPTIVE_BASE_VELOCITY).max(0.) * ADAPTIVE_GAIN;
+				factor.min(self.control.get_factor().max(1.))
+			}
+
+			AccelProfile::Flat => 1.,
+		}
+	}
+
+	/// Applies acceleration to a relative motion of the given device and returns the resulting
+	/// motion in pixels.
+	pub fn apply(&mut self, device: DeviceId, delta_x: i32, delta_y: i32) -> (i32, i32) {
+		let dev = self.devices.entry(device).or_default();
+		let (profile, speed) = (dev.profile, dev.speed);
+
+		let now = Instant::now();
+		let elapsed = dev
+			.last_motion
+			.replace(now)
+			.map(|last| now.duration_since(last).as_secs_f32() * 1000.);
+
+		let delta = (delta_x as f32, delta_y as f32);
+		let factor = self.get_factor(profile, delta, elapsed) * speed;
+
+		let dev = self.devices.get_mut(&device).unwrap();
+		let x = delta.0 * factor + dev.remainder.0;
+		let y = delta.1 * factor + dev.remainder.1;
+		dev.remainder = (x.fract(), y.fract());
+
+		(x.trunc() as _, y.trunc() as _)
+	}
+}
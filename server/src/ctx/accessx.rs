@@ -0,0 +1,428 @@
+//! AccessX features make the keyboard usable by people having difficulties with it:
+//! - StickyKeys: modifiers don't need to be held while pressing another key
+//! - SlowKeys: a key has to be held for some time before its press is accepted
+//! - BounceKeys: presses of a key following its release too closely are ignored
+//! - MouseKeys: the keypad moves the pointer and presses buttons
+//!
+//! Each feature can be toggled from the configuration or with a keyboard gesture:
+//! - StickyKeys: pressing Shift five times in a row
+//! - SlowKeys: holding Shift for eight seconds
+//! - BounceKeys: Shift + Alt + Scroll Lock
+//! - MouseKeys: Shift + Alt + Num Lock
+
+use crate::input::Keycode;
+use crate::protocol::KeyMask;
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The number of consecutive Shift presses toggling StickyKeys.
+const STICKY_KEYS_GESTURE_PRESSES: u8 = 5;
+/// The duration Shift has to be held to toggle SlowKeys.
+const SLOW_KEYS_GESTURE_DURATION: Duration = Duration::from_secs(8);
+
+/// An AccessX feature.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Feature {
+	/// Modifiers stay active after being released, until the next key is pressed.
+	StickyKeys,
+	/// Presses are accepted only after the key has been held for some time.
+	SlowKeys,
+	/// Presses following the release of the same key too closely are ignored.
+	BounceKeys,
+	/// The keypad moves the pointer and presses buttons.
+	MouseKeys,
+}
+
+/// The settings of AccessX features.
+#[derive(Clone, Debug)]
+pub struct AccessXSettings {
+	/// Tells whether StickyKeys is enabled.
+	pub sticky_keys: bool,
+	/// Tells whether SlowKeys is enabled.
+	pub slow_keys: bool,
+	/// The duration a key has to be held for its press to be accepted.
+	pub slow_keys_delay: Duration,
+	/// Tells whether BounceKeys is enabled.
+	pub bounce_keys: bool,
+	/// The duration after the release of a key during which its presses are ignored.
+	pub bounce_keys_delay: Duration,
+	/// Tells whether MouseKeys is enabled.
+	pub mouse_keys: bool,
+	/// The delay before the pointer starts moving continuously when a key is held.
+	pub mouse_keys_delay: Duration,
+	/// The interval between two motions of the pointer when a key is held.
+	pub mouse_keys_interval: Duration,
+	/// The maximum distance in pixels of one motion.
+	pub mouse_keys_max_speed: u16,
+	/// The number of motions before the maximum speed is reached.
+	pub mouse_keys_time_to_max: u16,
+	/// Tells whether keyboard gestures toggle features.
+	pub gestures: bool,
+}
+
+impl Default for AccessXSettings {
+	fn default() -> Self {
+		Self {
+			sticky_keys: false,
+			slow_keys: false,
+			slow_keys_delay: Duration::from_millis(300),
+			bounce_keys: false,
+			bounce_keys_delay: Duration::from_millis(300),
+			mouse_keys: false,
+			mouse_keys_delay: Duration::from_millis(160),
+			mouse_keys_interval: Duration::from_millis(40),
+			mouse_keys_max_speed: 30,
+			mouse_keys_time_to_max: 30,
+			gestures: true,
+		}
+	}
+}
+
+/// The continuous motion of the pointer with MouseKeys.
+struct MouseMotion {
+	/// The instant of the next motion.
+	next: Instant,
+	/// The number of motions since the beginning.
+	steps: u16,
+}
+
+/// The state of AccessX features.
+pub struct AccessX {
+	/// The settings.
+	settings: AccessXSettings,
+
+	/// The mask of latched modifiers.
+	latched: u16,
+	/// The mask of locked modifiers.
+	locked: u16,
+	/// The modifiers of the modifier key being pressed alone, if any.
+	sticky_candidate: Option<u16>,
+
+	/// The number of consecutive Shift presses.
+	shift_presses: u8,
+	/// The instant at which Shift has been pressed, if held.
+	shift_held: Option<Instant>,
+
+	/// The keys waiting for their press to be accepted, with the instant of acceptance.
+	slow_pending: Vec<(Keycode, Instant)>,
+	/// The instant of the last release of each key.
+	last_release: HashMap<Keycode, Instant>,
+
+	/// The held keys moving the pointer, with their direction.
+	mouse_directions: Vec<(Keycode, (i32, i32))>,
+	/// The continuous motion of the pointer, if any.
+	mouse_motion: Option<MouseMotion>,
+	/// The button pressed by MouseKeys.
+	mouse_button: u8,
+}
+
+impl Default for AccessX {
+	fn default() -> Self {
+		Self {
+			settings: AccessXSettings::default(),
+
+			latched: 0,
+			locked: 0,
+			sticky_candidate: None,
+
+			shift_presses: 0,
+			shift_held: None,
+
+			slow_pending: vec![],
+			last_release: HashMap::new(),
+
+			mouse_directions: vec![],
+			mouse_motion: None,
+			mouse_button: 1,
+		}
+	}
+}
+
+impl AccessX {
+	/// Returns the settings.
+	pub fn get_settings(&self) -> &AccessXSettings {
+		&self.settings
+	}
+
+	/// Sets the settings.
+	pub fn set_settings(&mut self, settings: AccessXSettings) {
+		let features = [
+			(Feature::StickyKeys, settings.sticky_keys),
+			(Feature::SlowKeys, settings.slow_keys),
+			(Feature::BounceKeys, settings.bounce_keys),
+			(Feature::MouseKeys, settings.mouse_keys),
+		];
+
+		self.settings = settings;
+		for (feature, enable) in features {
+			self.set_enabled(feature, enable);
+		}
+	}
+
+	/// Tells whether the given feature is enabled.
+	pub fn is_enabled(&self, feature: Feature) -> bool {
+		match feature {
+			Feature::StickyKeys => self.settings.sticky_keys,
+			Feature::SlowKeys => self.settings.slow_keys,
+			Feature::BounceKeys => self.settings.bounce_keys,
+			Feature::MouseKeys => self.settings.mouse_keys,
+		}
+	}
+
+	/// Enables or disables the given feature. Disabling a feature resets its state.
+	pub fn set_enabled(&mut self, feature: Feature, enable: bool) {
+		match feature {
+			Feature::StickyKeys => {
+				self.settings.sticky_keys = enable;
+				if !enable {
+					self.latched = 0;
+					self.locked = 0;
+					self.sticky_candidate = None;
+				}
+			}
+
+			Feature::SlowKeys => {
+				self.settings.slow_keys = enable;
+				if !enable {
+					self.slow_pending.clear();
+				}
+			}
+
+			Feature::BounceKeys => {
+				self.settings.bounce_keys = enable;
+				if !enable {
+					self.last_release.clear();
+				}
+			}
+
+			Feature::MouseKeys => {
+				self.settings.mouse_keys = enable;
+				if !enable {
+					self.mouse_directions.clear();
+					self.mouse_motion = None;
+				}
+			}
+		}
+	}
+
+	/// Returns the mask of modifiers latched or locked by StickyKeys, in the format of
+	/// `KeyMask`.
+	pub fn get_modifiers(&self) -> u16 {
+		self.latched | self.locked
+	}
+
+	/// Updates StickyKeys for the press or release of a key.
+	///
+	/// Pressing and releasing a modifier alone latches it until the next key is released.
+	/// Doing it a second time locks the modifier, and a third time unlocks it.
+	///
+	/// `mods` is the mask of modifiers of the key. If zero, the key is not a modifier.
+	pub fn sticky_key(&mut self, mods: u16, pressed: bool) {
+		if !self.settings.sticky_keys {
+			return;
+		}
+
+		// Lock keys already lock their modifier
+		let mods = mods & !(KeyMask::Lock as u16);
+		if mods == 0 {
+			if pressed {
+				// Modifiers held with another key act normally
+				self.sticky_candidate = None;
+			} else {
+				self.latched = 0;
+			}
+			return;
+		}
+
+		if pressed {
+			self.sticky_candidate = Some(mods);
+		} else if self.sticky_candidate.take() == Some(mods) {
+			if self.locked & mods != 0 {
+				self.locked &= !mods;
+			} else if self.latched & mods != 0 {
+				self.latched &= !mods;
+				self.locked |= mods;
+			} else {
+				self.latched |= mods;
+			}
+		}
+	}
+
+	/// Handles the press or release of a Shift key for gestures.
+	///
+	/// If the press toggles a feature, the function returns it.
+	pub fn shift_gesture(&mut self, pressed: bool) -> Option<Feature> {
+		if !self.settings.gestures {
+			return None;
+		}
+
+		if !pressed {
+			self.shift_held = None;
+			return None;
+		}
+
+		if self.shift_held.is_none() {
+			self.shift_held = Some(Instant::now());
+		}
+		self.shift_presses += 1;
+		if self.shift_presses < STICKY_KEYS_GESTURE_PRESSES {
+			return None;
+		}
+
+		self.shift_presses = 0;
+		Some(Feature::StickyKeys)
+	}
+
+	/// Handles the press of a key other than Shift for gestures.
+	///
+	/// Arguments:
+	/// - `name` is the name of the key's keysym.
+	/// - `state` is the mask of active modifiers.
+	///
+	/// If the press toggles a feature, the function returns it. The key should then not be
+	/// reported to clients.
+	pub fn key_gesture(&mut self, name: Option<&str>, state: u16) -> Option<Feature> {
+		self.shift_presses = 0;
+		self.shift_held = None;
+		if !self.settings.gestures {
+			return None;
+		}
+
+		let combo = KeyMask::Shift as u16 | KeyMask::Mod1 as u16;
+		if state & combo != combo {
+			return None;
+		}
+		match name? {
+			"Num_Lock" => Some(Feature::MouseKeys),
+			"Scroll_Lock" => Some(Feature::BounceKeys),
+
+			_ => None,
+		}
+	}
+
+	/// If Shift has been held long enough to toggle SlowKeys, the function returns the feature.
+	pub fn take_hold_gesture(&mut self) -> Option<Feature> {
+		let since = self.shift_held?;
+		if since.elapsed() < SLOW_KEYS_GESTURE_DURATION {
+			return None;
+		}
+
+		self.shift_held = None;
+		self.shift_presses = 0;
+		Some(Feature::SlowKeys)
+	}
+
+	/// Starts waiting for the press of the given key to be accepted.
+	pub fn add_slow_key(&mut self, keycode: Keycode) {
+		let deadline = Instant::now() + self.settings.slow_keys_delay;
+		self.slow_pending.retain(|(k, _)| *k != keycode);
+		self.slow_pending.push((keycode, deadline));
+	}
+
+	/// Stops waiting for the press of the given key to be accepted.
+	pub fn cancel_slow_key(&mut self, keycode: Keycode) {
+		self.slow_pending.retain(|(k, _)| *k != keycode);
+	}
+
+	/// Returns the keys whose press has been accepted since the last call.
+	pub fn take_slow_keys(&mut self) -> Vec<Keycode> {
+		let now = Instant::now();
+		let (accepted, pending) = self
+			.slow_pending
+			.iter()
+			.partition(|(_, deadline)| *deadline <= now);
+		self.slow_pending = pending;
+
+		accepted.into_iter().map(|(k, _)| k).collect()
+	}
+
+	/// Tells whether the press of the given key bounces and must be ignored.
+	pub fn is_bounce(&self, keycode: Keycode) -> bool {
+		self.settings.bounce_keys
+			&& self
+				.last_release
+				.get(&keycode)
+				.is_some_and(|t| t.elapsed() < self.settings.bounce_keys_delay)
+	}
+
+	/// Records the release of the given key for BounceKeys.
+	pub fn record_release(&mut self, keycode: Keycode) {
+		if self.settings.bounce_keys {
+			self.last_release.insert(keycode, Instant::now());
+		}
+	}
+
+	/// Returns the number of the button pressed by MouseKeys.
+	pub fn get_mouse_button(&self) -> u8 {
+		self.mouse_button
+	}
+
+	/// Sets the number of the button pressed by MouseKeys.
+	pub fn set_mouse_button(&mut self, button: u8) {
+		self.mouse_button = button;
+	}
+
+	/// Handles the press or release of a key moving the pointer in the given direction.
+	///
+	/// On press, the function returns the motion to apply immediately.
+	pub fn mouse_direction(
+		&mut self,
+		keycode: Keycode,
+		direction: (i32, i32),
+		pressed: bool,
+	) -> Option<(i32, i32)> {
+		self.mouse_directions.retain(|(k, _)| *k != keycode);
+		if !pressed {
+			if self.mouse_directions.is_empty() {
+				self.mouse_motion = None;
+			}
+			return None;
+		}
+
+		self.mouse_directions.push((keycode, direction));
+		if self.mouse_motion.is_none() {
+			self.mouse_motion = Some(MouseMotion {
+				next: Instant::now() + self.settings.mouse_keys_delay,
+				steps: 0,
+			});
+		}
+
+		Some(direction)
+	}
+
+	/// If the next continuous motion of the pointer is due, the function schedules the next one
+	/// and returns the motion to apply.
+	pub fn take_mouse_motion(&mut self) -> Option<(i32, i32)> {
+		let now = Instant::now();
+		let motion = self.mouse_motion.as_mut().filter(|m| m.next <= now)?;
+		motion.next = now + self.settings.mouse_keys_interval;
+		motion.steps = motion.steps.saturating_add(1);
+
+		// The speed increases linearly up to the maximum
+		let max = self.settings.mouse_keys_max_speed.max(1) as u32;
+		let time_to_max = self.settings.mouse_keys_time_to_max.max(1) as u32;
+		let speed = 1 + (max - 1) * (motion.steps as u32).min(time_to_max) / time_to_max;
+
+		let (x, y) = self
+			.mouse_directions
+			.iter()
+			.fold((0, 0), |(x, y), (_, (dx, dy))| (x + dx, y + dy));
+		Some((x.signum() * speed as i32, y.signum() * speed as i32))
+	}
+
+	/// Returns the next instant at which a timer of AccessX expires, if any.
+	pub fn get_deadline(&self) -> Option<Instant> {
+		let shift = self
+			.shift_held
+			.filter(|_| self.settings.gestures)
+			.map(|t| t + SLOW_KEYS_GESTURE_DURATION);
+
+		self.slow_pending
+			.iter()
+			.map(|(_, deadline)| *deadline)
+			.chain(self.mouse_motion.as_ref().map(|m| m.next))
+			.chain(shift)
+			.min()
+	}
+}
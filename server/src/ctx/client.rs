@@ -1,13 +1,14 @@
 //! This module implements support for X protocol clients.
 
-use crate::ctx::gc::GC;
+use crate::ctx::image;
+use crate::ctx::keyboard;
 use crate::ctx::Context;
-use crate::ctx::Screen;
 use crate::net::Stream;
 use crate::protocol;
 use crate::protocol::connect::ClientConnect;
 use crate::protocol::connect::ConnectFailed;
 use crate::protocol::connect::ConnectSuccess;
+use crate::protocol::event;
 use crate::protocol::pad;
 use crate::protocol::request::DefaultRequestReader;
 use crate::protocol::request::HandleError;
@@ -16,7 +17,6 @@ use crate::protocol::request::MAX_REQUEST_LEN;
 use crate::protocol::VENDOR_NAME;
 use crate::util;
 use std::cmp::max;
-use std::collections::HashMap;
 use std::error::Error;
 use std::io;
 use std::io::Read;
@@ -61,9 +61,6 @@ pub struct Client {
 	/// The current request reader. Changing this value allows to change the behaviour when
 	/// reading requests.
 	request_reader: Box<dyn RequestReader>,
-
-	/// The list of Graphics Contexts. The key is the ID of the context.
-	gcs: HashMap<u32, GC>,
 }
 
 impl Client {
@@ -87,8 +84,6 @@ impl Client {
 			sequence_number: Wrapping(0),
 
 			request_reader: Box::new(DefaultRequestReader {}),
-
-			gcs: HashMap::new(),
 		}
 	}
 
@@ -166,12 +161,13 @@ impl Client {
 
 	/// Writes a connect success message with the given reason.
 	///
-	/// `screens` is the list of screens.
-	pub fn write_connect_success(&mut self, screens: &[Screen]) -> io::Result<()> {
+	/// `ctx` is the current context.
+	pub fn write_connect_success(&mut self, ctx: &Context) -> io::Result<()> {
 		println!("New client connection succeeded");
 		self.state = ClientState::ConnectSuccess;
 
-		let screens = screens
+		let screens = ctx
+			.get_screens()
 			.iter()
 			.map(|s| s.to_protocol_screen())
 			.collect::<Vec<Vec<u8>>>();
@@ -180,7 +176,7 @@ impl Client {
 		let additional_data_len = 32
 			+ VENDOR_NAME.len()
 			+ pad(VENDOR_NAME.len())
-			+ 1 * size_of::<protocol::Format>() // TODO
+			+ image::FORMATS.len() * size_of::<protocol::Format>()
 			+ screens_len;
 
 		let msg = ConnectSuccess {
@@ -194,33 +190,24 @@ impl Client {
 			release_number: crate::RELEASE_NUMBER,
 			resource_id_base: 0xfffffff, // TODO use
 			resource_id_mask: 0xfffffff, // TODO use
-			motion_buffer_size: 0,       // TODO
+			motion_buffer_size: ctx.get_pointer().get_history().get_size() as _,
 			vendor_length: VENDOR_NAME.len() as _,
 			max_request_length: u16::MAX,
 			roots_screens_number: 1, // TODO
-			pixmap_formats_count: 1, // TODO
-			image_byte_order: 1,     // MSB first
+			pixmap_formats_count: image::FORMATS.len() as _,
+			image_byte_order: image::IMAGE_BYTE_ORDER,
 
-			bitmap_format_bit_order: 0,      // LSB first
-			bitmap_format_scanline_unit: 32, // TODO
-			bitmap_format_scanline_pad: 8,   // TODO
+			bitmap_format_bit_order: image::BITMAP_BIT_ORDER,
+			bitmap_format_scanline_unit: image::BITMAP_SCANLINE_UNIT,
+			bitmap_format_scanline_pad: image::SCANLINE_PAD,
 
-			min_keycode: 8,
-			max_keycode: 255,
+			min_keycode: keyboard::MIN_KEYCODE,
+			max_keycode: keyboard::MAX_KEYCODE,
 
 			/// Padding.
 			_padding1: 0,
 		};
 
-		// TODO Get from screens
-		let format = protocol::Format {
-			depth: 32,
-			bits_per_pixel: 24,
-			scanline_pad: 8,
-
-			_padding: [0; 5],
-		};
-
 		let len = 8 + additional_data_len;
 		let mut buf = vec![0; len];
 
@@ -241,12 +228,21 @@ impl Client {
 			ptr::copy_nonoverlapping::<u8>(vendor_name.as_ptr(), &mut buf[off], vendor_name.len());
 			off += vendor_name.len() + pad(vendor_name.len());
 
-			ptr::copy_nonoverlapping::<u8>(
-				&format as *const _ as *const u8,
-				&mut buf[off],
-				size_of::<protocol::Format>(),
-			);
-			off += size_of::<protocol::Format>();
+			for (depth, bits_per_pixel) in image::FORMATS {
+				let format = protocol::Format {
+					depth: *depth,
+					bits_per_pixel: *bits_per_pixel,
+					scanline_pad: image::SCANLINE_PAD,
+
+					_padding: [0; 5],
+				};
+				ptr::copy_nonoverlapping::<u8>(
+					&format as *const _ as *const u8,
+					&mut buf[off],
+					size_of::<protocol::Format>(),
+				);
+				off += size_of::<protocol::Format>();
+			}
 		}
 
 		for s in screens {
@@ -262,8 +258,8 @@ impl Client {
 
 	/// Handles an incoming connect request, if any.
 	///
-	/// `screens` is the list of screens.
-	fn handle_connect_request(&mut self, screens: &[Screen]) -> io::Result<()> {
+	/// `ctx` is the current context.
+	fn handle_connect_request(&mut self, ctx: &Context) -> io::Result<()> {
 		if self.buff_cursor < size_of::<ClientConnect>() {
 			return Ok(());
 		}
@@ -305,7 +301,7 @@ impl Client {
 		self.buff.rotate_left(required_len);
 		self.buff_cursor -= required_len;
 
-		self.write_connect_success(screens)
+		self.write_connect_success(ctx)
 	}
 
 	/// Handles an incoming request, if any.
@@ -341,6 +337,9 @@ impl Client {
 						// IO error, close connection
 						Err(HandleError::IO(e)) => return Err(Box::new(e)),
 					}
+
+					// Events generated by the request come after its reply or error
+					self.flush_events(ctx)?;
 				}
 
 				// No request to handle, break
@@ -356,20 +355,20 @@ impl Client {
 
 	/// Ticks the client.
 	///
-	/// `ctx` is the current context.
-	pub fn tick(&mut self, ctx: &mut Context) -> Result<(), Box<dyn Error>> {
+	/// Arguments:
+	/// - `ctx` is the current context.
+	/// - `readable` tells whether data is available for reading on the client's stream.
+	pub fn tick(&mut self, ctx: &mut Context, readable: bool) -> Result<(), Box<dyn Error>> {
 		// Reading incoming data
-		if self.buff_cursor < self.buff.len() {
+		if readable && self.buff_cursor < self.buff.len() {
 			let len = self.stream.read(&mut self.buff[self.buff_cursor..])?;
 			self.buff_cursor += len;
 		}
 
-		// TODO Notify client of event if necessary
-
 		// Reading input data
 		match self.state {
 			ClientState::Waiting | ClientState::ConnectFailed => {
-				self.handle_connect_request(&ctx.screens)?;
+				self.handle_connect_request(ctx)?;
 			}
 
 			ClientState::ConnectSuccess => {
@@ -380,13 +379,33 @@ impl Client {
 		Ok(())
 	}
 
+	/// Sends the events waiting for the client, stamped with the sequence number of the last
+	/// handled request.
+	///
+	/// `ctx` is the current context.
+	///
+	/// Events are kept waiting until the connection is established.
+	pub fn flush_events(&mut self, ctx: &mut Context) -> io::Result<()> {
+		if !matches!(self.state, ClientState::ConnectSuccess) {
+			return Ok(());
+		}
+
+		let seq = self.sequence_number.0.to_ne_bytes();
+
+		for mut event in ctx.take_events(self.id) {
+			// `KeymapNotify` has no sequence number
+			if event[0] & 0x7f != event::Event::KeymapNotify as u8 {
+				event[2..4].copy_from_slice(&seq);
+			}
+
+			self.stream.write_all(&event)?;
+		}
+
+		self.stream.flush()
+	}
+
 	/// Sets the request reader for the client.
 	pub fn set_request_reader(&mut self, reader: Box<dyn RequestReader>) {
 		self.request_reader = reader;
 	}
-
-	/// Sets a Graphics Context `gc` with the given ID `cid`.
-	pub fn set_gc(&mut self, cid: u32, gc: GC) {
-		self.gcs.insert(cid, gc);
-	}
 }
@@ -0,0 +1,284 @@
+//! A colormap associates pixel values with the colors displayed for them.
+//!
+//! The colors displayed on a screen are those of its installed colormaps. The server keeps
+//! between `MIN_INSTALLED_MAPS` and `MAX_INSTALLED_MAPS` colormaps installed on each screen:
+//! installing a colormap uninstalls the least recently installed ones when the limit is reached,
+//! and the default colormap of the screen stays installed when uninstalling it would leave too
+//! few.
+//!
+//! Clients using a colormap on a window are notified with `ColormapNotify` events when it is
+//! installed, uninstalled or freed.
+//!
+//! TODO Pixel values are still written to the screen as-is, without going through the installed
+//! colormap.
+
+use super::Context;
+use crate::protocol;
+use crate::protocol::event::ColormapNotifyEvent;
+use crate::protocol::event::Event;
+use crate::protocol::ColormapState;
+use std::num::NonZeroU32;
+
+/// The minimum number of colormaps installed on a screen.
+pub const MIN_INSTALLED_MAPS: usize = 1;
+/// The maximum number of colormaps installed on a screen.
+pub const MAX_INSTALLED_MAPS: usize = 1;
+
+/// A color, each component ranging from `0` to `0xffff`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Color {
+	/// The red component.
+	pub red: u16,
+	/// The green component.
+	pub green: u16,
+	/// The blue component.
+	pub blue: u16,
+}
+
+/// An entry of a colormap.
+#[derive(Clone, Copy, Debug, Default)]
+struct Entry {
+	/// The color of the entry.
+	color: Color,
+	/// The ID of the client that allocated the entry. If None, the entry is free.
+	owner: Option<u32>,
+}
+
+/// A colormap.
+#[derive(Debug)]
+pub struct Colormap {
+	/// The ID of the colormap.
+	id: NonZeroU32,
+	/// The ID of the client that created the colormap. If None, the colormap was created by the
+	/// server.
+	owner: Option<u32>,
+	/// The ID of the root window of the screen the colormap belongs to.
+	root: NonZeroU32,
+	/// The visual the colormap is used with.
+	visual: u32,
+
+	/// The entries of the colormap, indexed by pixel value.
+	entries: Vec<Entry>,
+}
+
+impl Colormap {
+	/// Creates a colormap.
+	///
+	/// Arguments:
+	/// - `id` is the ID of the colormap.
+	/// - `owner` is the ID of the client creating the colormap, or None for the server.
+	/// - `root` is the ID of the root window of the screen the colormap belongs to.
+	/// - `visual` is the visual the colormap is used with.
+	/// - `len` is the number of entries of the colormap.
+	/// - `alloc_all` tells whether every entry is allocated to the owner.
+	pub fn new(
+		id: NonZeroU32,
+		owner: Option<u32>,
+		root: NonZeroU32,
+		visual: u32,
+		len: usize,
+		alloc_all: bool,
+	) -> Self {
+		let entry = Entry {
+			color: Color::default(),
+			owner: owner.filter(|_| alloc_all),
+		};
+
+		Self {
+			id,
+			owner,
+			root,
+			visual,
+
+			entries: vec![entry; len],
+		}
+	}
+
+	/// Returns the ID of the colormap.
+	pub fn get_id(&self) -> NonZeroU32 {
+		self.id
+	}
+
+	/// Returns the ID of the client that created the colormap, or None if created by the server.
+	pub fn get_owner(&self) -> Option<u32> {
+		self.owner
+	}
+
+	/// Returns the ID of the root window of the screen the colormap belongs to.
+	pub fn get_root(&self) -> NonZeroU32 {
+		self.root
+	}
+
+	/// Returns the visual the colormap is used with.
+	pub fn get_visual(&self) -> u32 {
+		self.visual
+	}
+
+	/// Sets the color of the entry for the given pixel value. If the entry does not exist, the
+	/// function does nothing.
+	pub fn set_color(&mut self, pixel: usize, color: Color) {
+		if let Some(e) = self.entries.get_mut(pixel) {
+			e.color = color;
+		}
+	}
+
+	/// Frees the entries allocated by the client with the given ID.
+	pub fn free_entries(&mut self, client: u32) {
+		self.entries
+			.iter_mut()
+			.filter(|e| e.owner == Some(client))
+			.for_each(|e| e.owner = None);
+	}
+
+	/// Creates a colormap with the given ID for the client with the given ID, moving to it the
+	/// entries the client allocated in this colormap.
+	///
+	/// Other entries of the new colormap are free.
+	pub fn copy_and_free(&mut self, id: NonZeroU32, client: u32) -> Self {
+		let mut colormap = Self::new(
+			id,
+			Some(client),
+			self.root,
+			self.visual,
+			self.entries.len(),
+			false,
+		);
+		for (src, dst) in self.entries.iter_mut().zip(colormap.entries.iter_mut()) {
+			if src.owner == Some(client) {
+				*dst = *src;
+				src.owner = None;
+			}
+		}
+
+		colormap
+	}
+}
+
+impl Context<'_> {
+	/// Tells whether the colormap with the given ID is installed on its screen.
+	pub fn is_colormap_installed(&self, id: NonZeroU32) -> bool {
+		self.screens
+			.iter()
+			.any(|s| s.get_installed_colormaps().contains(&id))
+	}
+
+	/// Sends a `ColormapNotify` event to the windows using the colormap with the given ID.
+	///
+	/// Arguments:
+	/// - `new` tells whether the event reports a change of the colormap attribute of the windows.
+	/// - `state` is the state of the colormap.
+	fn notify_colormap(&mut self, id: NonZeroU32, new: bool, state: ColormapState) {
+		let windows: Vec<_> = self
+			.windows
+			.values()
+			.filter(|w| w.attributes.colormap == id.get())
+			.map(|w| w.get_id())
+			.collect();
+		for wid in windows {
+			let event = ColormapNotifyEvent {
+				code: Event::ColormapNotify as _,
+				_padding0: 0,
+				seq_nbr: 0,
+
+				window: wid.get(),
+				colormap: id.get(),
+				new: new as _,
+				state: state as _,
+
+				_padding1: [0; 18],
+			};
+			self.deliver_event(wid, protocol::Event::ColormapChange as _, &event);
+		}
+	}
+
+	/// Installs the colormap with the given ID on its screen.
+	///
+	/// If more than `MAX_INSTALLED_MAPS` colormaps become installed, the least recently installed
+	/// ones are uninstalled.
+	pub fn install_colormap(&mut self, id: NonZeroU32) {
+		let Some(root) = self.get_colormap(id).map(|c| c.get_root()) else {
+			return;
+		};
+		let Some(screen) = self.get_screen_for_root(root) else {
+			return;
+		};
+		let installed = self.screens[screen].get_installed_colormaps_mut();
+		if installed.contains(&id) {
+			return;
+		}
+		installed.push(id);
+		let excess = installed.len().saturating_sub(MAX_INSTALLED_MAPS);
+		let uninstalled: Vec<_> = installed.drain(..excess).collect();
+
+		for c in uninstalled {
+			self.notify_colormap(c, false, ColormapState::Uninstalled);
+		}
+		self.notify_colormap(id, false, ColormapState::Installed);
+	}
+
+	/// Uninstalls the colormap with the given ID from its screen.
+	///
+	/// If fewer than `MIN_INSTALLED_MAPS` colormaps would remain installed, the default colormap
+	/// of the screen is installed instead. The default colormap itself stays installed in that
+	/// case.
+	pub fn uninstall_colormap(&mut self, id: NonZeroU32) {
+		let Some(root) = self.get_colormap(id).map(|c| c.get_root()) else {
+			return;
+		};
+		let Some(screen) = self.get_screen_for_root(root) else {
+			return;
+		};
+		let screen = &mut self.screens[screen];
+		let default = screen.get_default_colormap();
+		let installed = screen.get_installed_colormaps_mut();
+		let Some(i) = installed.iter().position(|c| *c == id) else {
+			return;
+		};
+		if id == default && installed.len() <= MIN_INSTALLED_MAPS {
+			return;
+		}
+		installed.remove(i);
+		let install_default = installed.len() < MIN_INSTALLED_MAPS;
+
+		self.notify_colormap(id, false, ColormapState::Uninstalled);
+		if install_default {
+			self.install_colormap(default);
+		}
+	}
+
+	/// Frees the colormap with the given ID and returns it.
+	///
+	/// The colormap is uninstalled, and windows using it are left without colormap. The default
+	/// colormap of a screen cannot be freed.
+	pub fn free_colormap(&mut self, id: NonZeroU32) -> Option<Colormap> {
+		if self.screens.iter().any(|s| s.get_default_colormap() == id) {
+			return None;
+		}
+
+		self.uninstall_colormap(id);
+		let windows: Vec<_> = self
+			.windows
+			.values()
+			.filter(|w| w.attributes.colormap == id.get())
+			.map(|w| w.get_id())
+			.collect();
+		for wid in windows {
+			self.get_window_mut(wid).unwrap().attributes.colormap = 0;
+			let event = ColormapNotifyEvent {
+				code: Event::ColormapNotify as _,
+				_padding0: 0,
+				seq_nbr: 0,
+
+				window: wid.get(),
+				colormap: 0,
+				new: 1,
+				state: ColormapState::Uninstalled as _,
+
+				_padding1: [0; 18],
+			};
+			self.deliver_event(wid, protocol::Event::ColormapChange as _, &event);
+		}
+
+		self.colormaps.remove(&id)
+	}
+}
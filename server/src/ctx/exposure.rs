@@ -0,0 +1,468 @@
+//! Exposure tracking finds the parts of windows that become visible after the window tree
+//! changes.
+//!
+//! The visible regions of windows are saved before a change and compared to the new ones after
+//! it. Newly visible parts have their background painted and are reported to clients with
+//! `Expose` events, so that they redraw them. Newly visible parts of borders are painted too.
+//! Since the contents of windows are not kept on screen, a window that moved or was resized is
+//! exposed entirely, unless its contents were saved before the change and put back after it.
+//!
+//! Since exposures follow every change of the window tree, the visibility states of windows are
+//! updated at the same time, and reported with `VisibilityNotify` events before `Expose` events.
+//!
+//! Contents may also be kept offscreen. With backing store, a window keeps a copy of its contents,
+//! which is updated by drawing. With save-under, the screen under a window is saved when it is
+//! mapped and put back when it is unmapped, drawing on the windows below going to the saved area
+//! too.
+
+use super::image::Image;
+use super::window::Window;
+use super::Context;
+use super::Drawable;
+use crate::protocol;
+use crate::protocol::event::Event;
+use crate::protocol::event::ExposeEvent;
+use crate::protocol::event::VisibilityNotifyEvent;
+use crate::protocol::BackingStore;
+use crate::protocol::MapState;
+use crate::region::Bounds;
+use crate::region::Region;
+use std::cell::RefCell;
+use std::cell::RefMut;
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+
+/// The visible parts of a window, in absolute coordinates.
+#[derive(Clone)]
+struct WindowVisibility {
+	/// The bounds of the inside of the window.
+	bounds: Bounds,
+	/// The visible region of the inside of the window.
+	inside: Region,
+	/// The visible region of the window's border.
+	border: Region,
+}
+
+/// The contents of a window, saved before it moves.
+struct SavedWindow {
+	/// The absolute position of the window's origin, relative to which the contents are placed.
+	origin: (i32, i32),
+	/// The saved region, in absolute coordinates.
+	region: Region,
+	/// The pixels of the extents of the saved region.
+	image: Image,
+}
+
+/// The contents of the windows of a subtree, saved so that they are kept when the windows move.
+#[derive(Default)]
+pub struct SavedContents {
+	/// The saved contents of each window. The key is the ID of the window.
+	windows: HashMap<NonZeroU32, SavedWindow>,
+}
+
+impl SavedContents {
+	/// Discards the saved contents of the window with the given ID, so that it is exposed.
+	pub fn discard(&mut self, wid: NonZeroU32) {
+		self.windows.remove(&wid);
+	}
+
+	/// Moves the saved contents of the window with the given ID by the given offset, relative to
+	/// the window's origin.
+	pub fn shift(&mut self, wid: NonZeroU32, dx: i32, dy: i32) {
+		if let Some(win) = self.windows.get_mut(&wid) {
+			win.origin = (win.origin.0 - dx, win.origin.1 - dy);
+		}
+	}
+}
+
+/// The visible regions of the windows of a tree, at a given time.
+#[derive(Clone, Default)]
+pub struct Visibility {
+	/// The visible parts of each viewable window. The key is the ID of the window.
+	windows: HashMap<NonZeroU32, WindowVisibility>,
+}
+
+/// The screen under a window with save-under, saved when the window was mapped.
+pub struct SaveUnder {
+	/// The saved area, in absolute coordinates.
+	bounds: Bounds,
+	/// The pixels of the saved area.
+	image: RefCell<Image>,
+	/// The visible regions of the windows when the area was saved.
+	below: Visibility,
+}
+
+impl SaveUnder {
+	/// Returns the part of the saved area showing the given window, relative to the window's
+	/// origin.
+	///
+	/// `ctx` is the current context.
+	pub fn get_region(&self, ctx: &Context, win: &Window) -> Region {
+		let Some(vis) = self.below.windows.get(&win.get_id()) else {
+			return Region::new();
+		};
+		// Once moved, the window does not match the saved area anymore
+		if vis.bounds != win.get_inner_bounds(ctx) {
+			return Region::new();
+		}
+
+		let mut region = vis.inside.intersect(&Region::from_bounds(self.bounds));
+		region.translate(-vis.bounds.x1, -vis.bounds.y1);
+		region
+	}
+
+	/// Returns the absolute position of the saved area.
+	pub fn get_position(&self) -> (i32, i32) {
+		(self.bounds.x1, self.bounds.y1)
+	}
+
+	/// Returns the pixels of the saved area.
+	pub fn get_image_mut(&self) -> RefMut<'_, Image> {
+		self.image.borrow_mut()
+	}
+}
+
+impl<'a> Context<'a> {
+	/// Returns the IDs of the given window and of all its inferiors, each window before its
+	/// children.
+	fn get_tree(&self, wid: NonZeroU32) -> Vec<NonZeroU32> {
+		let mut tree = vec![];
+		let mut stack = vec![wid];
+		while let Some(wid) = stack.pop() {
+			let Some(win) = self.get_window(wid) else {
+				continue;
+			};
+			tree.push(wid);
+			// Reversed so that children are visited bottom-most first
+			stack.extend(win.get_children().iter().rev());
+		}
+
+		tree
+	}
+
+	/// Returns the visible regions of the windows in the tree of the given root window.
+	pub fn get_visibility(&self, root: NonZeroU32) -> Visibility {
+		let windows = self
+			.get_tree(root)
+			.into_iter()
+			.filter_map(|wid| self.get_window(wid))
+			.filter(|win| win.is_viewable() && win.is_output())
+			.map(|win| {
+				let (x, y) = win.get_absolute_position(self);
+				let mut inside = win.get_visible_region(self, false);
+				inside.translate(x, y);
+				let mut border = win.get_visible_border(self);
+				border.translate(x, y);
+
+				let vis = WindowVisibility {
+					bounds: win.get_inner_bounds(self),
+					inside,
+					border,
+				};
+				(win.get_id(), vis)
+			})
+			.collect();
+
+		Visibility {
+			windows,
+		}
+	}
+
+	/// Updates the visibility states of the windows in the tree of the given root window, and
+	/// sends `VisibilityNotify` events for the ones that changed.
+	///
+	/// No event is sent for windows that stop being viewable.
+	fn update_visibility(&mut self, root: NonZeroU32) {
+		let changes: Vec<_> = self
+			.get_tree(root)
+			.into_iter()
+			.filter_map(|wid| self.get_window(wid))
+			.map(|win| (win.get_id(), win.compute_visibility(self)))
+			.filter(|(wid, state)| self.get_window(*wid).unwrap().get_visibility() != *state)
+			.collect();
+
+		for (wid, state) in changes {
+			self.get_window_mut(wid).unwrap().set_visibility(state);
+			let Some(state) = state else {
+				continue;
+			};
+			self.deliver_event(
+				wid,
+				protocol::Event::VisibilityChange as _,
+				&VisibilityNotifyEvent {
+					code: Event::VisibilityNotify as _,
+					_padding0: 0,
+					seq_nbr: 0,
+
+					window: wid.get(),
+					state: state as _,
+
+					_padding1: [0; 23],
+				},
+			);
+		}
+	}
+
+	/// Exposes the parts of the windows in the tree of the given root window that became
+	/// visible since `prev` was taken.
+	pub fn expose(&mut self, root: NonZeroU32, prev: &Visibility) {
+		self.update_visibility(root);
+		let curr = self.get_visibility(root);
+
+		for wid in self.get_tree(root) {
+			let Some(vis) = curr.windows.get(&wid) else {
+				self.update_backing(wid, &Region::new());
+				continue;
+			};
+			let (mut kept, mut exposed, mut border) = match prev.windows.get(&wid) {
+				Some(prev) if prev.bounds == vis.bounds => (
+					vis.inside.intersect(&prev.inside),
+					vis.inside.subtract(&prev.inside),
+					vis.border.subtract(&prev.border),
+				),
+				_ => (Region::new(), vis.inside.clone(), vis.border.clone()),
+			};
+			kept.translate(-vis.bounds.x1, -vis.bounds.y1);
+			exposed.translate(-vis.bounds.x1, -vis.bounds.y1);
+			border.translate(-vis.bounds.x1, -vis.bounds.y1);
+
+			// Borders are painted by the server, without notifying clients
+			if !border.is_empty() {
+				let win = self.get_window(wid).unwrap();
+				win.paint_border(self, &border);
+			}
+			// Contents kept offscreen are put back without notifying clients
+			self.update_backing(wid, &kept);
+			let win = self.get_window(wid).unwrap();
+			let exposed = win.restore_backing(self, &exposed);
+			self.expose_region(wid, &exposed);
+		}
+	}
+
+	/// Creates or frees the offscreen copy of the contents of the given window. See
+	/// `Window::update_backing`.
+	///
+	/// The size of windows is chosen by clients, so the copy may not fit in memory. In that case,
+	/// the window falls back to the `NotUseful` backing store.
+	pub fn update_backing(&mut self, wid: NonZeroU32, kept: &Region) {
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+		if !win.update_backing(self, kept) {
+			self.get_window_mut(wid).unwrap().attributes.backing_store = BackingStore::NotUseful;
+		}
+	}
+
+	/// Saves the visible contents of the given window and of its inferiors.
+	pub fn save_contents(&self, wid: NonZeroU32) -> SavedContents {
+		let windows = self
+			.get_tree(wid)
+			.into_iter()
+			.filter_map(|wid| self.get_window(wid))
+			.filter(|win| win.is_viewable() && win.is_output())
+			.filter_map(|win| {
+				let root = NonZeroU32::new(win.get_root()).unwrap();
+				let screen = &self.get_screens()[self.get_screen_for_root(root)?];
+
+				let origin = win.get_absolute_position(self);
+				let mut region = win.get_visible_region(self, false);
+				region.translate(origin.0, origin.1);
+				let ext = region.get_extents();
+				let image = screen.read_image(
+					ext.x1,
+					ext.y1,
+					(ext.x2 - ext.x1) as _,
+					(ext.y2 - ext.y1) as _,
+					win.get_depth(),
+				)?;
+
+				let saved = SavedWindow {
+					origin,
+					region,
+					image,
+				};
+				Some((win.get_id(), saved))
+			})
+			.collect();
+
+		SavedContents {
+			windows,
+		}
+	}
+
+	/// Puts saved contents back at the new positions of their windows.
+	///
+	/// The restored parts are recorded as visible in `prev`, so that they are not exposed.
+	/// Borders are recorded as hidden, so that they are painted again.
+	pub fn restore_contents(&mut self, saved: &SavedContents, prev: &mut Visibility) {
+		for (wid, s) in &saved.windows {
+			let Some(win) = self.get_window(*wid) else {
+				continue;
+			};
+			if !win.is_viewable() {
+				continue;
+			}
+			let root = NonZeroU32::new(win.get_root()).unwrap();
+			let Some(screen) = self.get_screen_for_root(root) else {
+				continue;
+			};
+
+			let (x, y) = win.get_absolute_position(self);
+			let (dx, dy) = (x - s.origin.0, y - s.origin.1);
+			let mut visible = win.get_visible_region(self, false);
+			visible.translate(x, y);
+			let mut region = s.region.clone();
+			region.translate(dx, dy);
+			let region = region.intersect(&visible);
+
+			let ext = s.region.get_extents();
+			let mut contents = self.get_screens()[screen].get_contents_mut();
+			for r in region.iter() {
+				for y in r.y1..r.y2 {
+					for x in r.x1..r.x2 {
+						let pixel = s.image.get_pixel(x - dx - ext.x1, y - dy - ext.y1);
+						contents.set_pixel(x, y, pixel);
+					}
+				}
+			}
+
+			let vis = WindowVisibility {
+				bounds: win.get_inner_bounds(self),
+				inside: region,
+				border: Region::new(),
+			};
+			prev.windows.insert(*wid, vis);
+		}
+	}
+
+	/// Saves the screen under the given window, if it has the save-under attribute.
+	///
+	/// Arguments:
+	/// - `wid` is the ID of the window, which must not be mapped yet.
+	/// - `below` is the visibility of the windows before the window is mapped.
+	pub fn save_under(&mut self, wid: NonZeroU32, below: &Visibility) {
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+		let unmapped = win.attributes.map_state == MapState::Unmapped;
+		if !win.attributes.save_under || !win.is_output() || !unmapped {
+			return;
+		}
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+		let Some(screen) = self.get_screen_for_root(root) else {
+			return;
+		};
+
+		// Only the part of the window on the screen can be saved
+		let screen = &self.get_screens()[screen];
+		let (width, height) = screen.get_screen_size();
+		let screen_bounds = Bounds::new(0, 0, width as _, height as _);
+		let Some(bounds) = win.get_outer_bounds(self).intersect(&screen_bounds) else {
+			return;
+		};
+		// Without memory for the copy, the window is simply exposed when unmapped
+		let Ok(image) = screen.get_contents().sub_image(
+			bounds.x1,
+			bounds.y1,
+			(bounds.x2 - bounds.x1) as _,
+			(bounds.y2 - bounds.y1) as _,
+		) else {
+			return;
+		};
+		let save_under = SaveUnder {
+			bounds,
+			image: RefCell::new(image),
+			below: below.clone(),
+		};
+		self.save_unders.insert(wid, save_under);
+	}
+
+	/// Returns the areas saved under windows with save-under.
+	pub fn get_save_unders(&self) -> impl Iterator<Item = &SaveUnder> {
+		self.save_unders.values()
+	}
+
+	/// Discards the area saved under the given window, which does not cover it anymore.
+	pub fn discard_save_under(&mut self, wid: NonZeroU32) {
+		self.save_unders.remove(&wid);
+	}
+
+	/// Puts back the screen saved under the given window, after it was unmapped.
+	///
+	/// The restored parts are recorded as visible in `prev`, so that they are not exposed. Parts
+	/// of windows that moved since the area was saved are not restored.
+	pub fn restore_save_under(&mut self, wid: NonZeroU32, prev: &mut Visibility) {
+		let Some(save_under) = self.save_unders.remove(&wid) else {
+			return;
+		};
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+		let Some(screen) = self.get_screen_for_root(root) else {
+			return;
+		};
+
+		let curr = self.get_visibility(root);
+		let area = Region::from_bounds(save_under.bounds);
+		let (x, y) = save_under.get_position();
+		let image = save_under.image.borrow();
+		let mut contents = self.get_screens()[screen].get_contents_mut();
+		for (id, vis) in &curr.windows {
+			let Some(below) = save_under.below.windows.get(id) else {
+				continue;
+			};
+			let entry = prev.windows.get_mut(id);
+			let Some(entry) =
+				entry.filter(|e| e.bounds == vis.bounds && below.bounds == vis.bounds)
+			else {
+				continue;
+			};
+
+			let inside = vis.inside.intersect(&below.inside).intersect(&area);
+			let border = vis.border.intersect(&below.border).intersect(&area);
+			for region in [&inside, &border] {
+				contents.fill_region(region, (0, 0), |px, py| image.get_pixel(px - x, py - y));
+			}
+			entry.inside = entry.inside.union(&inside);
+			entry.border = entry.border.union(&border);
+		}
+	}
+
+	/// Paints the background of the given region of a window and sends `Expose` events for it.
+	///
+	/// Arguments:
+	/// - `wid` is the ID of the window.
+	/// - `region` is the exposed region, relative to the window's origin.
+	pub fn expose_region(&mut self, wid: NonZeroU32, region: &Region) {
+		if region.is_empty() {
+			return;
+		}
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+		win.paint_background(self, region);
+
+		let rects = region.to_rectangles();
+		for (i, r) in rects.iter().enumerate() {
+			self.deliver_event(
+				wid,
+				protocol::Event::Exposure as _,
+				&ExposeEvent {
+					code: Event::Expose as _,
+					_padding0: 0,
+					seq_nbr: 0,
+
+					window: wid.get(),
+					x: r.x as _,
+					y: r.y as _,
+					width: r.width,
+					height: r.height,
+					count: (rects.len() - i - 1) as _,
+
+					_padding1: [0; 14],
+				},
+			);
+		}
+	}
+}
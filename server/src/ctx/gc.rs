@@ -1,9 +1,15 @@
 //! This module implements Graphics Contexts (GC).
 
+use super::pixmap::Pixmap;
+use super::Drawable;
 use crate::protocol::error::Error;
+use crate::protocol::Rectangle;
+use crate::region::Region;
+use std::num::NonZeroU32;
+use std::rc::Rc;
 
 /// TODO doc
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Function {
 	/// TODO doc
 	Clear,
@@ -66,8 +72,32 @@ impl TryFrom<u8> for Function {
 	}
 }
 
+impl Function {
+	/// Combines the source pixel `src` with the destination pixel `dst` and returns the result.
+	pub fn apply(self, src: u32, dst: u32) -> u32 {
+		match self {
+			Self::Clear => 0,
+			Self::And => src & dst,
+			Self::AndReverse => src & !dst,
+			Self::Copy => src,
+			Self::AndInverted => !src & dst,
+			Self::NoOp => dst,
+			Self::Xor => src ^ dst,
+			Self::Or => src | dst,
+			Self::Nor => !(src | dst),
+			Self::Equiv => !src ^ dst,
+			Self::Invert => !dst,
+			Self::OrReverse => src | !dst,
+			Self::CopyInverted => !src,
+			Self::OrInverted => !src | dst,
+			Self::Nand => !(src & dst),
+			Self::Set => u32::MAX,
+		}
+	}
+}
+
 /// TODO doc
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LineStyle {
 	/// TODO doc
 	Solid,
@@ -92,7 +122,7 @@ impl TryFrom<u8> for LineStyle {
 }
 
 /// TODO doc
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CapStyle {
 	/// TODO doc
 	NotLast,
@@ -120,14 +150,14 @@ impl TryFrom<u8> for CapStyle {
 }
 
 /// TODO doc
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum JoinStyle {
 	/// TODO doc
-	Solid,
+	Miter,
 	/// TODO doc
-	OnOffDash,
+	Round,
 	/// TODO doc
-	DoubleDash,
+	Bevel,
 }
 
 impl TryFrom<u8> for JoinStyle {
@@ -135,9 +165,9 @@ impl TryFrom<u8> for JoinStyle {
 
 	fn try_from(v: u8) -> Result<Self, Self::Error> {
 		match v {
-			0 => Ok(Self::Solid),
-			1 => Ok(Self::OnOffDash),
-			2 => Ok(Self::DoubleDash),
+			0 => Ok(Self::Miter),
+			1 => Ok(Self::Round),
+			2 => Ok(Self::Bevel),
 
 			_ => Err(Error::Value(v as _)),
 		}
@@ -145,7 +175,7 @@ impl TryFrom<u8> for JoinStyle {
 }
 
 /// TODO doc
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum FillStyle {
 	/// TODO doc
 	Solid,
@@ -173,7 +203,7 @@ impl TryFrom<u8> for FillStyle {
 }
 
 /// TODO doc
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum FillRule {
 	/// TODO doc
 	EvenOdd,
@@ -195,7 +225,7 @@ impl TryFrom<u8> for FillRule {
 }
 
 /// TODO doc
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum SubWindowMode {
 	/// TODO doc
 	ClipByChildren,
@@ -217,7 +247,7 @@ impl TryFrom<u8> for SubWindowMode {
 }
 
 /// TODO doc
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ArcMode {
 	/// TODO doc
 	Chord,
@@ -239,7 +269,7 @@ impl TryFrom<u8> for ArcMode {
 }
 
 /// Value of a graphics context.
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum Value {
 	/// TODO doc
 	Function(Function),
@@ -289,11 +319,313 @@ pub enum Value {
 	ArcMode(ArcMode),
 }
 
+/// The ordering of clip rectangles, as specified by the client.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ordering {
+	/// No particular order.
+	UnSorted,
+	/// Rectangles are sorted by their top edge.
+	YSorted,
+	/// Rectangles are sorted by their top edge, then by their left edge.
+	YXSorted,
+	/// Like `YXSorted`, and every rectangle of a band has the same top and bottom edges.
+	YXBanded,
+}
+
+impl TryFrom<u8> for Ordering {
+	type Error = Error;
+
+	fn try_from(v: u8) -> Result<Self, Self::Error> {
+		match v {
+			0 => Ok(Self::UnSorted),
+			1 => Ok(Self::YSorted),
+			2 => Ok(Self::YXSorted),
+			3 => Ok(Self::YXBanded),
+
+			_ => Err(Error::Value(v as _)),
+		}
+	}
+}
+
+impl Ordering {
+	/// Tells whether the given list of rectangles respects the ordering.
+	pub fn check(&self, rects: &[Rectangle]) -> bool {
+		let sorted =
+			|f: fn(&Rectangle, &Rectangle) -> bool| rects.windows(2).all(|w| f(&w[0], &w[1]));
+
+		match self {
+			Self::UnSorted => true,
+			Self::YSorted => sorted(|a, b| a.y <= b.y),
+			Self::YXSorted => sorted(|a, b| a.y < b.y || (a.y == b.y && a.x <= b.x)),
+			Self::YXBanded => sorted(|a, b| {
+				(a.y == b.y && a.height == b.height && a.x <= b.x)
+					|| a.y as i32 + a.height as i32 <= b.y as i32
+			}),
+		}
+	}
+}
+
+/// The clip of a graphics context, restricting the pixels affected by drawing operations.
+#[derive(Clone, Debug, Default)]
+pub enum Clip {
+	/// Every pixel is affected.
+	#[default]
+	None,
+	/// Only pixels for which the bitmap has a bit set are affected.
+	Mask(Rc<Pixmap>),
+	/// Only pixels inside of the union of a list of rectangles are affected.
+	Rectangles(Region),
+}
+
+/// Bit of a component in the mask of values of a graphics context.
+pub mod component {
+	/// The function.
+	pub const FUNCTION: u32 = 1 << 0;
+	/// The plane mask.
+	pub const PLANE_MASK: u32 = 1 << 1;
+	/// The foreground pixel.
+	pub const FOREGROUND: u32 = 1 << 2;
+	/// The background pixel.
+	pub const BACKGROUND: u32 = 1 << 3;
+	/// The line width.
+	pub const LINE_WIDTH: u32 = 1 << 4;
+	/// The line style.
+	pub const LINE_STYLE: u32 = 1 << 5;
+	/// The cap style.
+	pub const CAP_STYLE: u32 = 1 << 6;
+	/// The join style.
+	pub const JOIN_STYLE: u32 = 1 << 7;
+	/// The fill style.
+	pub const FILL_STYLE: u32 = 1 << 8;
+	/// The fill rule.
+	pub const FILL_RULE: u32 = 1 << 9;
+	/// The tile.
+	pub const TILE: u32 = 1 << 10;
+	/// The stipple.
+	pub const STIPPLE: u32 = 1 << 11;
+	/// The X origin of the tile and stipple.
+	pub const TILE_STIPPLE_X_ORIGIN: u32 = 1 << 12;
+	/// The Y origin of the tile and stipple.
+	pub const TILE_STIPPLE_Y_ORIGIN: u32 = 1 << 13;
+	/// The font.
+	pub const FONT: u32 = 1 << 14;
+	/// The subwindow mode.
+	pub const SUBWINDOW_MODE: u32 = 1 << 15;
+	/// Whether `GraphicsExposure` events are generated.
+	pub const GRAPHICS_EXPOSURES: u32 = 1 << 16;
+	/// The X origin of the clip.
+	pub const CLIP_X_ORIGIN: u32 = 1 << 17;
+	/// The Y origin of the clip.
+	pub const CLIP_Y_ORIGIN: u32 = 1 << 18;
+	/// The clip mask.
+	pub const CLIP_MASK: u32 = 1 << 19;
+	/// The dash offset.
+	pub const DASH_OFFSET: u32 = 1 << 20;
+	/// The dash list.
+	pub const DASHES: u32 = 1 << 21;
+	/// The arc mode.
+	pub const ARC_MODE: u32 = 1 << 22;
+}
+
 /// Structure representing a graphics context.
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct GC {
-	/// The ID of the drawable.
-	pub drawable: u32,
-	/// The list of values.
-	pub values: Vec<Value>,
+	/// The ID of the graphics context.
+	id: NonZeroU32,
+	/// The ID of the client that created the graphics context.
+	owner: u32,
+	/// The root of the drawables the graphics context can be used with.
+	root: u32,
+	/// The depth of the drawables the graphics context can be used with.
+	depth: u8,
+
+	/// The function combining source and destination pixels.
+	pub function: Function,
+	/// The mask of planes affected by drawing operations.
+	pub plane_mask: u32,
+	/// The foreground pixel.
+	pub foreground: u32,
+	/// The background pixel.
+	pub background: u32,
+	/// The width of lines. Zero means thin lines.
+	pub line_width: u16,
+	/// The style of lines.
+	pub line_style: LineStyle,
+	/// The style of the ends of lines.
+	pub cap_style: CapStyle,
+	/// The style of joints between lines.
+	pub join_style: JoinStyle,
+	/// The way shapes are filled.
+	pub fill_style: FillStyle,
+	/// The rule deciding which pixels are inside of a polygon.
+	pub fill_rule: FillRule,
+	/// The pixmap used by tiled fills. If None, the tile is filled with the foreground pixel.
+	pub tile: Option<Rc<Pixmap>>,
+	/// The bitmap used by stippled fills. If None, every bit of the stipple is set.
+	pub stipple: Option<Rc<Pixmap>>,
+	/// The X origin of the tile and stipple.
+	pub tile_stipple_x_origin: i16,
+	/// The Y origin of the tile and stipple.
+	pub tile_stipple_y_origin: i16,
+	/// The ID of the font.
+	pub font: u32,
+	/// Tells whether drawing on a window is clipped by its children.
+	pub subwindow_mode: SubWindowMode,
+	/// Tells whether `GraphicsExposure` events are generated.
+	pub graphics_exposures: bool,
+	/// The X origin of the clip.
+	pub clip_x_origin: i16,
+	/// The Y origin of the clip.
+	pub clip_y_origin: i16,
+	/// The clip.
+	pub clip: Clip,
+	/// The offset in the dash pattern at which lines start.
+	pub dash_offset: u16,
+	/// The lengths of dashes, alternating between even and odd dashes.
+	pub dashes: Vec<u8>,
+	/// The way arcs are filled.
+	pub arc_mode: ArcMode,
+}
+
+impl GC {
+	/// Creates a graphics context with default values.
+	///
+	/// Arguments:
+	/// - `id` is the ID of the graphics context.
+	/// - `owner` is the ID of the client creating the graphics context.
+	/// - `drawable` is the drawable the graphics context is created for.
+	pub fn new(id: NonZeroU32, owner: u32, drawable: &dyn Drawable) -> Self {
+		Self {
+			id,
+			owner,
+			root: drawable.get_root(),
+			depth: drawable.get_depth(),
+
+			function: Function::Copy,
+			plane_mask: u32::MAX,
+			foreground: 0,
+			background: 1,
+			line_width: 0,
+			line_style: LineStyle::Solid,
+			cap_style: CapStyle::Butt,
+			join_style: JoinStyle::Miter,
+			fill_style: FillStyle::Solid,
+			fill_rule: FillRule::EvenOdd,
+			tile: None,
+			stipple: None,
+			tile_stipple_x_origin: 0,
+			tile_stipple_y_origin: 0,
+			font: 0,
+			subwindow_mode: SubWindowMode::ClipByChildren,
+			graphics_exposures: true,
+			clip_x_origin: 0,
+			clip_y_origin: 0,
+			clip: Clip::None,
+			dash_offset: 0,
+			dashes: vec![4, 4],
+			arc_mode: ArcMode::PieSlice,
+		}
+	}
+
+	/// Returns the ID of the graphics context.
+	pub fn get_id(&self) -> NonZeroU32 {
+		self.id
+	}
+
+	/// Returns the ID of the client that created the graphics context.
+	pub fn get_owner(&self) -> u32 {
+		self.owner
+	}
+
+	/// Returns the root of the drawables the graphics context can be used with.
+	pub fn get_root(&self) -> u32 {
+		self.root
+	}
+
+	/// Returns the depth of the drawables the graphics context can be used with.
+	pub fn get_depth(&self) -> u8 {
+		self.depth
+	}
+
+	/// Tells whether the graphics context can be used to draw on the given drawable.
+	pub fn is_compatible(&self, drawable: &dyn Drawable) -> bool {
+		drawable.get_root() == self.root && drawable.get_depth() == self.depth
+	}
+
+	/// Copies the components selected by `mask` from `src`.
+	///
+	/// See the `component` module for the bits of the mask.
+	pub fn copy_components(&mut self, src: &GC, mask: u32) {
+		let copy = |bit: u32| mask & bit != 0;
+
+		if copy(component::FUNCTION) {
+			self.function = src.function;
+		}
+		if copy(component::PLANE_MASK) {
+			self.plane_mask = src.plane_mask;
+		}
+		if copy(component::FOREGROUND) {
+			self.foreground = src.foreground;
+		}
+		if copy(component::BACKGROUND) {
+			self.background = src.background;
+		}
+		if copy(component::LINE_WIDTH) {
+			self.line_width = src.line_width;
+		}
+		if copy(component::LINE_STYLE) {
+			self.line_style = src.line_style;
+		}
+		if copy(component::CAP_STYLE) {
+			self.cap_style = src.cap_style;
+		}
+		if copy(component::JOIN_STYLE) {
+			self.join_style = src.join_style;
+		}
+		if copy(component::FILL_STYLE) {
+			self.fill_style = src.fill_style;
+		}
+		if copy(component::FILL_RULE) {
+			self.fill_rule = src.fill_rule;
+		}
+		if copy(component::TILE) {
+			self.tile = src.tile.clone();
+		}
+		if copy(component::STIPPLE) {
+			self.stipple = src.stipple.clone();
+		}
+		if copy(component::TILE_STIPPLE_X_ORIGIN) {
+			self.tile_stipple_x_origin = src.tile_stipple_x_origin;
+		}
+		if copy(component::TILE_STIPPLE_Y_ORIGIN) {
+			self.tile_stipple_y_origin = src.tile_stipple_y_origin;
+		}
+		if copy(component::FONT) {
+			self.font = src.font;
+		}
+		if copy(component::SUBWINDOW_MODE) {
+			self.subwindow_mode = src.subwindow_mode;
+		}
+		if copy(component::GRAPHICS_EXPOSURES) {
+			self.graphics_exposures = src.graphics_exposures;
+		}
+		if copy(component::CLIP_X_ORIGIN) {
+			self.clip_x_origin = src.clip_x_origin;
+		}
+		if copy(component::CLIP_Y_ORIGIN) {
+			self.clip_y_origin = src.clip_y_origin;
+		}
+		if copy(component::CLIP_MASK) {
+			self.clip = src.clip.clone();
+		}
+		if copy(component::DASH_OFFSET) {
+			self.dash_offset = src.dash_offset;
+		}
+		if copy(component::DASHES) {
+			self.dashes = src.dashes.clone();
+		}
+		if copy(component::ARC_MODE) {
+			self.arc_mode = src.arc_mode;
+		}
+	}
 }
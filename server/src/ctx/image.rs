@@ -0,0 +1,391 @@
+//! An image is a rectangle of pixels stored in memory, such as the contents of a pixmap.
+//!
+//! Pixels are stored in the `ZPixmap` format advertised to clients for the image's depth: each
+//! scanline is padded to `SCANLINE_PAD` bits, pixels of depth 1 are packed in bitmap scanline
+//! units and other pixels are stored in native byte order.
+//!
+//! Images can also be transferred in the `XYPixmap` format, where each bit plane is sent as a
+//! separate bitmap, starting from the most significant.
+
+use crate::protocol::error::Error;
+use crate::region::Region;
+
+/// The number of bits each scanline is padded to.
+pub const SCANLINE_PAD: u8 = 32;
+/// The byte order of images: `0` for least significant byte first, `1` for most significant byte
+/// first. This is the native byte order, so that pixels can be transferred without conversion.
+pub const IMAGE_BYTE_ORDER: u8 = if cfg!(target_endian = "big") { 1 } else { 0 };
+/// The bit order of bitmaps: `0` for least significant bit first, `1` for most significant bit
+/// first.
+pub const BITMAP_BIT_ORDER: u8 = 0;
+/// The number of bits in a unit of a bitmap scanline.
+pub const BITMAP_SCANLINE_UNIT: u8 = 32;
+
+/// The list of supported depths, along with the number of bits used to store a pixel.
+pub const FORMATS: &[(u8, u8)] = &[(1, 1), (8, 8), (15, 16), (16, 16), (24, 32), (32, 32)];
+
+/// A format in which images are transferred with clients.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+	/// A single bitmap, drawn with the foreground and background of a graphics context.
+	XYBitmap,
+	/// One bitmap per bit plane, from the most significant.
+	XYPixmap,
+	/// Pixels are stored one after the other.
+	ZPixmap,
+}
+
+impl TryFrom<u8> for Format {
+	type Error = Error;
+
+	fn try_from(v: u8) -> Result<Self, Self::Error> {
+		match v {
+			0 => Ok(Self::XYBitmap),
+			1 => Ok(Self::XYPixmap),
+			2 => Ok(Self::ZPixmap),
+
+			_ => Err(Error::Value(v as _)),
+		}
+	}
+}
+
+/// Returns the number of bits used to store a pixel of the given depth.
+///
+/// If the depth is not supported, the function returns None.
+pub fn get_bits_per_pixel(depth: u8) -> Option<u8> {
+	FORMATS
+		.iter()
+		.find(|(d, _)| *d == depth)
+		.map(|(_, bpp)| *bpp)
+}
+
+/// Returns the mask of bits that are significant in a pixel of the given depth.
+pub fn get_pixel_mask(depth: u8) -> u32 {
+	match depth {
+		32 => u32::MAX,
+		d => (1 << d) - 1,
+	}
+}
+
+/// Returns the number of bytes of a scanline of `width` pixels of `bpp` bits each.
+pub fn get_stride(width: u16, bpp: u8) -> usize {
+	pad_scanline(width as usize * bpp as usize)
+}
+
+/// Returns the number of bytes of a scanline of `bits` bits, once padded.
+fn pad_scanline(bits: usize) -> usize {
+	let pad = SCANLINE_PAD as usize;
+	bits.div_ceil(pad) * pad / 8
+}
+
+/// Returns the offset of the byte and the shift of the bit storing the bit at position `x` in a
+/// bitmap scanline.
+fn get_bit_position(x: usize) -> (usize, usize) {
+	let unit_bits = BITMAP_SCANLINE_UNIT as usize;
+	let (unit, bit) = (x / unit_bits, x % unit_bits);
+	// The position of the bit in the unit, from the least significant
+	let bit = if BITMAP_BIT_ORDER == 0 {
+		bit
+	} else {
+		unit_bits - 1 - bit
+	};
+	let byte = if IMAGE_BYTE_ORDER == 0 {
+		bit / 8
+	} else {
+		unit_bits / 8 - 1 - bit / 8
+	};
+
+	(unit * unit_bits / 8 + byte, bit % 8)
+}
+
+/// Returns the bit at position `x` in the given bitmap scanline.
+fn get_bit(scanline: &[u8], x: usize) -> bool {
+	let (byte, shift) = get_bit_position(x);
+	(scanline[byte] >> shift) & 1 != 0
+}
+
+/// Sets the bit at position `x` in the given bitmap scanline.
+fn set_bit(scanline: &mut [u8], x: usize, value: bool) {
+	let (byte, shift) = get_bit_position(x);
+	if value {
+		scanline[byte] |= 1 << shift;
+	} else {
+		scanline[byte] &= !(1 << shift);
+	}
+}
+
+/// A rectangle of pixels stored in memory.
+#[derive(Clone, Debug)]
+pub struct Image {
+	/// The depth of pixels.
+	depth: u8,
+	/// The number of bits used to store a pixel.
+	bpp: u8,
+	/// The width in pixels.
+	width: u16,
+	/// The height in pixels.
+	height: u16,
+	/// The number of bytes of a scanline.
+	stride: usize,
+
+	/// The pixels.
+	data: Vec<u8>,
+}
+
+impl Image {
+	/// Creates an image with every pixel set to zero.
+	///
+	/// If the depth is not supported, the function returns `Value`. If the pixels cannot be
+	/// allocated, the function returns `Alloc`.
+	pub fn new(depth: u8, width: u16, height: u16) -> Result<Self, Error> {
+		let bpp = get_bits_per_pixel(depth).ok_or(Error::Value(depth as _))?;
+		let stride = get_stride(width, bpp);
+
+		// The size is chosen by clients, so running out of memory must not abort the server
+		let len = stride * height as usize;
+		let mut data = Vec::new();
+		data.try_reserve_exact(len).map_err(|_| Error::Alloc)?;
+		data.resize(len, 0);
+
+		Ok(Self {
+			depth,
+			bpp,
+			width,
+			height,
+			stride,
+
+			data,
+		})
+	}
+
+	/// Returns the depth of pixels.
+	pub fn get_depth(&self) -> u8 {
+		self.depth
+	}
+
+	/// Returns the number of bits used to store a pixel.
+	pub fn get_bits_per_pixel(&self) -> u8 {
+		self.bpp
+	}
+
+	/// Returns the width and height of the image.
+	pub fn get_size(&self) -> (u16, u16) {
+		(self.width, self.height)
+	}
+
+	/// Returns the number of bytes of a scanline.
+	pub fn get_stride(&self) -> usize {
+		self.stride
+	}
+
+	/// Returns the raw pixel data.
+	pub fn get_data(&self) -> &[u8] {
+		&self.data
+	}
+
+	/// Returns the raw pixel data, mutably.
+	pub fn get_data_mut(&mut self) -> &mut [u8] {
+		&mut self.data
+	}
+
+	/// Returns the mask of bits that are significant in a pixel.
+	pub fn get_pixel_mask(&self) -> u32 {
+		get_pixel_mask(self.depth)
+	}
+
+	/// Tells whether the given position is inside of the image.
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		(0..self.width as i32).contains(&x) && (0..self.height as i32).contains(&y)
+	}
+
+	/// Returns the value of the pixel at the given position.
+	///
+	/// If the position is outside of the image, the function returns zero.
+	pub fn get_pixel(&self, x: i32, y: i32) -> u32 {
+		if !self.contains(x, y) {
+			return 0;
+		}
+
+		let row = &self.data[(y as usize * self.stride)..];
+		let x = x as usize;
+		match self.bpp {
+			1 => get_bit(row, x) as u32,
+			8 => row[x] as u32,
+			16 => u16::from_ne_bytes([row[x * 2], row[x * 2 + 1]]) as u32,
+			_ => u32::from_ne_bytes(row[(x * 4)..(x * 4 + 4)].try_into().unwrap()),
+		}
+	}
+
+	/// Sets the value of the pixel at the given position. Bits that are not significant for the
+	/// image's depth are ignored.
+	///
+	/// If the position is outside of the image, the function does nothing.
+	pub fn set_pixel(&mut self, x: i32, y: i32, value: u32) {
+		if !self.contains(x, y) {
+			return;
+		}
+
+		let value = value & self.get_pixel_mask();
+		let row = &mut self.data[(y as usize * self.stride)..];
+		let x = x as usize;
+		match self.bpp {
+			1 => set_bit(row, x, value != 0),
+			8 => row[x] = value as u8,
+			16 => row[(x * 2)..(x * 2 + 2)].copy_from_slice(&(value as u16).to_ne_bytes()),
+			_ => row[(x * 4)..(x * 4 + 4)].copy_from_slice(&value.to_ne_bytes()),
+		}
+	}
+
+	/// Creates an image from data in the `ZPixmap` format.
+	///
+	/// If the data is too short, the function returns `Length` without allocating the image.
+	/// Trailing data is ignored. Other errors are those of `new`.
+	pub fn from_z_pixmap(depth: u8, width: u16, height: u16, data: &[u8]) -> Result<Self, Error> {
+		let bpp = get_bits_per_pixel(depth).ok_or(Error::Value(depth as _))?;
+		let len = get_stride(width, bpp) * height as usize;
+		let data = data.get(..len).ok_or(Error::Length)?;
+
+		let mut img = Self::new(depth, width, height)?;
+		img.data.copy_from_slice(data);
+
+		Ok(img)
+	}
+
+	/// Returns the number of bytes of an image in the `XYPixmap` format.
+	///
+	/// Arguments:
+	/// - `planes` is the number of bit planes.
+	/// - `width` and `height` are the size of the image.
+	/// - `left_pad` is the number of bits to skip at the beginning of each scanline.
+	pub fn get_xy_pixmap_len(planes: u8, width: u16, height: u16, left_pad: u8) -> usize {
+		let stride = pad_scanline(left_pad as usize + width as usize);
+		planes as usize * height as usize * stride
+	}
+
+	/// Creates an image from data in the `XYPixmap` format, with one bit plane per bit of the
+	/// depth. An image of depth 1 can be created from data in the `XYBitmap` format this way.
+	///
+	/// Arguments:
+	/// - `depth` is the depth of the image.
+	/// - `width` and `height` are the size of the image.
+	/// - `left_pad` is the number of bits to skip at the beginning of each scanline.
+	/// - `data` is the image's data.
+	///
+	/// If the data is too short, the function returns `Length` without allocating the image.
+	/// Trailing data is ignored. Other errors are those of `new`.
+	pub fn from_xy_pixmap(
+		depth: u8,
+		width: u16,
+		height: u16,
+		left_pad: u8,
+		data: &[u8],
+	) -> Result<Self, Error> {
+		let data = data
+			.get(..Self::get_xy_pixmap_len(depth, width, height, left_pad))
+			.ok_or(Error::Length)?;
+		let mut img = Self::new(depth, width, height)?;
+		let stride = pad_scanline(left_pad as usize + width as usize);
+
+		for (i, plane) in data.chunks_exact(stride * height as usize).enumerate() {
+			// Planes are sent from the most significant
+			let bit = 1 << (depth as usize - 1 - i);
+			for (y, scanline) in plane.chunks_exact(stride).enumerate() {
+				for x in 0..width as usize {
+					if get_bit(scanline, left_pad as usize + x) {
+						let pixel = img.get_pixel(x as _, y as _);
+						img.set_pixel(x as _, y as _, pixel | bit);
+					}
+				}
+			}
+		}
+
+		Ok(img)
+	}
+
+	/// Returns the pixels of the image in the `ZPixmap` format. Bits of pixels that are not in
+	/// `plane_mask` are set to zero.
+	pub fn to_z_pixmap(&self, plane_mask: u32) -> Vec<u8> {
+		let mask = self.get_pixel_mask();
+		if plane_mask & mask == mask {
+			return self.data.clone();
+		}
+
+		let mut img = self.clone();
+		for y in 0..self.height as i32 {
+			for x in 0..self.width as i32 {
+				img.set_pixel(x, y, self.get_pixel(x, y) & plane_mask);
+			}
+		}
+
+		img.data
+	}
+
+	/// Returns the pixels of the image in the `XYPixmap` format. Only the bit planes in
+	/// `plane_mask` are returned.
+	pub fn to_xy_pixmap(&self, plane_mask: u32) -> Vec<u8> {
+		let stride = pad_scanline(self.width as usize);
+		let plane_len = stride * self.height as usize;
+
+		let planes = (0..self.depth)
+			.rev()
+			.filter(|bit| plane_mask & (1 << bit) != 0)
+			.collect::<Vec<_>>();
+		let mut data = vec![0; planes.len() * plane_len];
+		for (plane, bit) in data.chunks_exact_mut(plane_len).zip(planes) {
+			for (y, scanline) in plane.chunks_exact_mut(stride).enumerate() {
+				for x in 0..self.width as usize {
+					let pixel = self.get_pixel(x as _, y as _);
+					set_bit(scanline, x, pixel & (1 << bit) != 0);
+				}
+			}
+		}
+
+		data
+	}
+
+	/// Returns a copy of the given rectangle of the image.
+	///
+	/// Pixels outside of the image are set to zero.
+	///
+	/// If the copy cannot be allocated, the function returns `Alloc`.
+	pub fn sub_image(&self, x: i32, y: i32, width: u16, height: u16) -> Result<Self, Error> {
+		let mut img = Self::new(self.depth, width, height)?;
+		for dy in 0..height as i32 {
+			for dx in 0..width as i32 {
+				img.set_pixel(dx, dy, self.get_pixel(x + dx, y + dy));
+			}
+		}
+
+		Ok(img)
+	}
+
+	/// Sets every pixel of the image to the given value.
+	pub fn fill(&mut self, value: u32) {
+		for y in 0..self.height as i32 {
+			for x in 0..self.width as i32 {
+				self.set_pixel(x, y, value);
+			}
+		}
+	}
+
+	/// Sets the pixels of the given region.
+	///
+	/// Arguments:
+	/// - `region` is the region to set.
+	/// - `dx` and `dy` are the offset from the region's coordinates to the image's.
+	/// - `get_pixel` returns the pixel at the given position, in the region's coordinates.
+	pub fn fill_region<F: Fn(i32, i32) -> u32>(
+		&mut self,
+		region: &Region,
+		(dx, dy): (i32, i32),
+		get_pixel: F,
+	) {
+		for r in region.iter() {
+			for y in r.y1..r.y2 {
+				for x in r.x1..r.x2 {
+					self.set_pixel(x + dx, y + dy, get_pixel(x, y));
+				}
+			}
+		}
+	}
+}
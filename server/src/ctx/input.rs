@@ -0,0 +1,696 @@
+//! Input routing turns inputs from devices into pointer movements and into events sent to
+//! clients.
+
+use super::accessx::Feature;
+use super::Context;
+use super::Drawable;
+use crate::input::DeviceChange;
+use crate::input::DeviceId;
+use crate::input::Feedback;
+use crate::input::Input;
+use crate::input::Keycode;
+use crate::input::MouseButton;
+use crate::protocol;
+use crate::protocol::event;
+use crate::protocol::event::CrossingEvent;
+use crate::protocol::event::InputEvent;
+use crate::protocol::event::KeymapNotifyEvent;
+use crate::protocol::ButMask;
+use crate::protocol::KeyMask;
+use std::num::NonZeroU32;
+use std::time::Instant;
+
+impl MouseButton {
+	/// Returns the button with the given number, if it exists.
+	pub fn from_number(n: u8) -> Option<Self> {
+		match n {
+			1 => Some(Self::Button1),
+			2 => Some(Self::Button2),
+			3 => Some(Self::Button3),
+			4 => Some(Self::Button4),
+			5 => Some(Self::Button5),
+
+			_ => None,
+		}
+	}
+
+	/// Returns the number of the physical button, before the pointer mapping is applied.
+	pub fn get_number(&self) -> u8 {
+		match self {
+			Self::Button1 => 1,
+			Self::Button2 => 2,
+			Self::Button3 => 3,
+			Self::Button4 => 4,
+			Self::Button5 => 5,
+		}
+	}
+}
+
+impl<'a> Context<'a> {
+	/// Handles the given input from a device.
+	///
+	/// `device` is the ID of the device the input comes from.
+	pub fn handle_input(&mut self, device: DeviceId, input: Input) {
+		match input {
+			Input::RelativeMove {
+				delta_x,
+				delta_y,
+			} => {
+				let (delta_x, delta_y) = self.acceleration.apply(device, delta_x, delta_y);
+				if (delta_x, delta_y) == (0, 0) {
+					return;
+				}
+
+				let (x, y) = self.get_pointer_global_position();
+				self.move_pointer(x + delta_x, y + delta_y);
+			}
+
+			Input::AbsoluteMove {
+				x,
+				y,
+			} => {
+				let (min_x, min_y, max_x, max_y) = self.get_desktop_bounds();
+				let x = min_x + ((max_x - min_x - 1) as f32 * x).round() as i32;
+				let y = min_y + ((max_y - min_y - 1) as f32 * y).round() as i32;
+				self.move_pointer(x, y);
+			}
+
+			Input::ButtonPress(button) => self.press_button(button, true),
+			Input::ButtonRelease(button) => self.press_button(button, false),
+
+			Input::KeyPress(keycode) => self.handle_key(keycode, true),
+			Input::KeyRelease(keycode) => self.handle_key(keycode, false),
+		}
+	}
+
+	/// Sets up or forgets a device according to the given change.
+	pub fn handle_device_change(&mut self, change: DeviceChange) {
+		match change {
+			DeviceChange::Added {
+				id,
+				settings,
+			} => {
+				if let Some(profile) = settings.accel_profile {
+					self.acceleration.set_profile(id, profile);
+				}
+				if let Some(speed) = settings.accel_speed {
+					self.acceleration.set_speed(id, speed);
+				}
+			}
+
+			DeviceChange::Removed(id) => self.acceleration.remove_device(id),
+		}
+	}
+
+	/// Returns the bounding box of the virtual desktop, as `(min_x, min_y, max_x, max_y)`, the
+	/// maximums being exclusive.
+	fn get_desktop_bounds(&self) -> (i32, i32, i32, i32) {
+		self.screens
+			.iter()
+			.map(|s| {
+				let (x, y) = s.get_position();
+				let (width, height) = s.get_screen_size();
+				(
+					x as i32,
+					y as i32,
+					x as i32 + width as i32,
+					y as i32 + height as i32,
+				)
+			})
+			.reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+			.unwrap_or((0, 0, 1, 1))
+	}
+
+	/// Returns the current state of buttons and modifiers, as reported in events.
+	pub fn get_input_state(&self) -> u16 {
+		self.pointer.get_buttons()
+			| self.keyboard.get_modifiers_state()
+			| self.accessx.get_modifiers()
+	}
+
+	/// Returns the position of the pointer on the virtual desktop.
+	pub fn get_pointer_global_position(&self) -> (i32, i32) {
+		let (x, y) = self.pointer.get_position();
+
+		match self.screens.get(self.pointer.get_screen()) {
+			Some(screen) => {
+				let (screen_x, screen_y) = screen.get_position();
+				(screen_x as i32 + x as i32, screen_y as i32 + y as i32)
+			}
+
+			None => (x as _, y as _),
+		}
+	}
+
+	/// Returns the ID of the root window of the screen on which the pointer is located.
+	pub fn get_pointer_root(&self) -> Option<NonZeroU32> {
+		self.screens
+			.get(self.pointer.get_screen())
+			.map(|s| s.get_root_window_id())
+	}
+
+	/// Moves the pointer to the given position on the virtual desktop, generating motion and
+	/// crossing events.
+	///
+	/// If no screen contains the given position, the pointer stays on its current screen and the
+	/// position is clamped to its edges.
+	pub fn move_pointer(&mut self, x: i32, y: i32) {
+		let curr = self.pointer.get_screen();
+		let Some(screen) = self
+			.screens
+			.iter()
+			.position(|s| s.contains(x, y))
+			.or_else(|| (curr < self.screens.len()).then_some(curr))
+		else {
+			return;
+		};
+
+		let (screen_x, screen_y) = self.screens[screen].get_position();
+		let (width, height) = self.screens[screen].get_screen_size();
+		let x = (x - screen_x as i32).clamp(0, width as i32 - 1) as i16;
+		let y = (y - screen_y as i32).clamp(0, height as i32 - 1) as i16;
+
+		if screen == curr && (x, y) == self.pointer.get_position() {
+			return;
+		}
+		let time = self.get_timestamp();
+		self.pointer.set_position(screen, x, y, time);
+
+		self.update_pointer_window();
+
+		if let Some(win) = self.pointer.get_window() {
+			let mut mask = protocol::Event::PointerMotion as u32;
+			let buttons = self.pointer.get_buttons();
+			if buttons != 0 {
+				mask |= protocol::Event::ButtonMotion as u32;
+			}
+			// ButtonNMotion masks follow the order of button masks
+			for i in 0..5 {
+				if buttons & (ButMask::Button1 as u16) << i != 0 {
+					mask |= (protocol::Event::Button1Motion as u32) << i;
+				}
+			}
+
+			self.deliver_device_event(win, mask, event::Event::MotionNotify as u8, 0);
+		}
+	}
+
+	/// Recomputes the window containing the pointer, generating crossing events if it changed.
+	///
+	/// This function must be called when the window hierarchy changes under the pointer.
+	pub fn update_pointer_window(&mut self) {
+		let window = self.get_pointer_root().map(|root| {
+			let (x, y) = self.pointer.get_position();
+			self.window_at(root, x as _, y as _)
+		});
+
+		let old = self.pointer.get_window();
+		if old != window {
+			self.pointer.set_window(window);
+			self.send_crossing_events(old, window);
+		}
+	}
+
+	/// Presses or releases the given button, generating the associated event.
+	fn press_button(&mut self, button: MouseButton, pressed: bool) {
+		let (mask, code) = if pressed {
+			(protocol::Event::ButtonPress, event::Event::ButtonPress)
+		} else {
+			(protocol::Event::ButtonRelease, event::Event::ButtonRelease)
+		};
+
+		let Some(logical) = self.pointer.map_button(button.get_number()) else {
+			return;
+		};
+
+		if let Some(win) = self.pointer.get_window() {
+			self.deliver_device_event(win, mask as _, code as _, logical);
+		}
+
+		if let Some(mask) = ButMask::from_button(logical) {
+			self.pointer.set_buttons(mask as _, pressed);
+		}
+	}
+
+	/// Enables or disables the given AccessX feature, ringing the bell to notify the user.
+	fn toggle_accessx(&mut self, feature: Feature) {
+		let enable = !self.accessx.is_enabled(feature);
+		self.accessx.set_enabled(feature, enable);
+
+		// Higher volume when enabling
+		self.keyboard.bell(if enable { 50 } else { -50 });
+	}
+
+	/// Handles the press or release of a key from a device, applying AccessX features.
+	fn handle_key(&mut self, keycode: Keycode, pressed: bool) {
+		let name = self.keyboard.get_keysym_name(keycode);
+		let mods = self.keyboard.get_modifier_map().get_modifiers_of(keycode);
+
+		// Gestures
+		let gesture = if mods == KeyMask::Shift as u16 {
+			self.accessx.shift_gesture(pressed)
+		} else if pressed {
+			let state = self.get_input_state();
+			let feature = self.accessx.key_gesture(name, state);
+			if let Some(feature) = feature {
+				// The key is not reported
+				self.toggle_accessx(feature);
+				return;
+			}
+			None
+		} else {
+			None
+		};
+		if let Some(feature) = gesture {
+			self.toggle_accessx(feature);
+		}
+
+		if self.accessx.is_enabled(Feature::MouseKeys)
+			&& self.handle_mouse_key(keycode, name, pressed)
+		{
+			return;
+		}
+
+		if !pressed {
+			// The press may have been discarded by SlowKeys or BounceKeys
+			if !self.keyboard.is_pressed(keycode) {
+				self.accessx.cancel_slow_key(keycode);
+				return;
+			}
+			self.accessx.record_release(keycode);
+		} else if !self.keyboard.is_pressed(keycode) {
+			if self.accessx.is_bounce(keycode) {
+				return;
+			}
+			if self.accessx.is_enabled(Feature::SlowKeys) {
+				self.accessx.add_slow_key(keycode);
+				return;
+			}
+		}
+
+		self.press_key(keycode, pressed);
+	}
+
+	/// Handles the press or release of a key for MouseKeys.
+	///
+	/// Keys of the keypad are used only when Num Lock is off.
+	///
+	/// If the key is used by MouseKeys, the function returns `true` and the key must not be
+	/// reported to clients.
+	fn handle_mouse_key(&mut self, keycode: Keycode, name: Option<&str>, pressed: bool) -> bool {
+		// Num Lock is bound to Mod2. The LEDs cannot be trusted since clients can change them
+		if self.keyboard.get_locked_modifiers() & KeyMask::Mod2 as u16 != 0 {
+			return false;
+		}
+
+		let direction = match name {
+			Some("KP_Home") => (-1, -1),
+			Some("KP_Up") => (0, -1),
+			Some("KP_Prior") => (1, -1),
+			Some("KP_Left") => (-1, 0),
+			Some("KP_Right") => (1, 0),
+			Some("KP_End") => (-1, 1),
+			Some("KP_Down") => (0, 1),
+			Some("KP_Next") => (1, 1),
+
+			Some(
+				"KP_Begin" | "KP_Add" | "KP_Insert" | "KP_Delete" | "KP_Divide" | "KP_Multiply"
+				| "KP_Subtract",
+			) => {
+				if pressed {
+					self.mouse_key_button(name.unwrap());
+				}
+				return true;
+			}
+
+			_ => return false,
+		};
+
+		if let Some((delta_x, delta_y)) = self.accessx.mouse_direction(keycode, direction, pressed)
+		{
+			let (x, y) = self.get_pointer_global_position();
+			self.move_pointer(x + delta_x, y + delta_y);
+		}
+		true
+	}
+
+	/// Performs the button action of the MouseKeys key with the given keysym name.
+	fn mouse_key_button(&mut self, name: &str) {
+		let Some(button) = MouseButton::from_number(self.accessx.get_mouse_button()) else {
+			return;
+		};
+
+		match name {
+			"KP_Begin" => {
+				self.press_button(button, true);
+				self.press_button(button, false);
+			}
+			"KP_Add" => {
+				for _ in 0..2 {
+					self.press_button(button, true);
+					self.press_button(button, false);
+				}
+			}
+			"KP_Insert" => self.press_button(button, true),
+			"KP_Delete" => self.press_button(button, false),
+
+			"KP_Divide" => self.accessx.set_mouse_button(1),
+			"KP_Multiply" => self.accessx.set_mouse_button(2),
+			"KP_Subtract" => self.accessx.set_mouse_button(3),
+
+			_ => {}
+		}
+	}
+
+	/// Presses or releases the given key, generating the associated event.
+	fn press_key(&mut self, keycode: Keycode, pressed: bool) {
+		self.send_key_event(keycode, pressed);
+		self.keyboard.set_pressed(keycode, pressed);
+
+		if !self.keyboard.is_locking(keycode) {
+			let mods = self.keyboard.get_modifier_map().get_modifiers_of(keycode);
+			self.accessx.sticky_key(mods, pressed);
+		}
+	}
+
+	/// Sends the event for a press or release of the given key, without changing the state of
+	/// the keyboard.
+	fn send_key_event(&mut self, keycode: Keycode, pressed: bool) {
+		let (mask, code) = if pressed {
+			(protocol::Event::KeyPress, event::Event::KeyPress)
+		} else {
+			(protocol::Event::KeyRelease, event::Event::KeyRelease)
+		};
+
+		// TODO Use the focus window once input focus is implemented
+		if let Some(win) = self.pointer.get_window() {
+			self.deliver_device_event(win, mask as _, code as _, keycode);
+		}
+	}
+
+	/// Returns the next instant at which timers have to be handled with `tick_timers`, if any.
+	pub fn get_next_deadline(&self) -> Option<Instant> {
+		[
+			self.keyboard.get_repeat_deadline(),
+			self.accessx.get_deadline(),
+		]
+		.into_iter()
+		.flatten()
+		.min()
+	}
+
+	/// Handles the timers that are due, such as key autorepeat.
+	pub fn tick_timers(&mut self) {
+		// Repetitions appear to clients as a release followed by a press
+		if let Some(keycode) = self.keyboard.take_repeat() {
+			self.send_key_event(keycode, false);
+			self.send_key_event(keycode, true);
+		}
+
+		// AccessX
+		if let Some(feature) = self.accessx.take_hold_gesture() {
+			self.toggle_accessx(feature);
+		}
+		for keycode in self.accessx.take_slow_keys() {
+			self.press_key(keycode, true);
+		}
+		if let Some((delta_x, delta_y)) = self.accessx.take_mouse_motion() {
+			let (x, y) = self.get_pointer_global_position();
+			self.move_pointer(x + delta_x, y + delta_y);
+		}
+	}
+
+	/// Takes the feedback waiting to be given to the user through input devices.
+	pub fn take_feedback(&mut self) -> Vec<Feedback> {
+		self.keyboard.take_feedback()
+	}
+
+	/// Returns the ID of the deepest viewable window containing the given position, relative to
+	/// the given root window.
+	pub fn window_at(&self, root: NonZeroU32, mut x: i32, mut y: i32) -> NonZeroU32 {
+		let mut wid = root;
+
+		while let Some(child) = self.get_window(wid).and_then(|w| w.child_at(self, x, y)) {
+			let win = self.get_window(child).unwrap();
+			let rect = win.get_rectangle();
+			let bw = win.get_border_width() as i32;
+
+			x -= rect.x as i32 + bw;
+			y -= rect.y as i32 + bw;
+			wid = child;
+		}
+
+		wid
+	}
+
+	/// Returns the list of IDs of the given window and its ancestors, starting with the window
+	/// itself and ending with the root.
+	fn get_ancestors(&self, wid: Option<NonZeroU32>) -> Vec<NonZeroU32> {
+		let mut list = vec![];
+
+		let mut curr = wid;
+		while let Some(w) = curr {
+			list.push(w);
+			curr = self.get_window(w).and_then(|w| w.get_parent());
+		}
+
+		list
+	}
+
+	/// Builds a device event for the current state of the pointer, reported relative to the
+	/// given window.
+	///
+	/// Arguments:
+	/// - `code` is the event code.
+	/// - `detail` is the detail of the event.
+	/// - `wid` is the ID of the window the event is reported to.
+	/// - `child` is the ID of the child of the window on the path to the source window.
+	fn build_input_event(&self, code: u8, detail: u8, wid: NonZeroU32, child: u32) -> InputEvent {
+		let (root_x, root_y) = self.pointer.get_position();
+		let root = self.get_pointer_root().map(|r| r.get()).unwrap_or(0);
+
+		let win = self.get_window(wid);
+		let same_screen = win.map(|w| w.get_root() == root).unwrap_or(false);
+		let (event_x, event_y) = match win {
+			Some(win) if same_screen => {
+				let (x, y) = win.get_absolute_position(self);
+				(root_x - x as i16, root_y - y as i16)
+			}
+
+			_ => (0, 0),
+		};
+
+		InputEvent {
+			code,
+			detail,
+			seq_nbr: 0,
+
+			time: self.get_timestamp(),
+			root,
+			event: wid.get(),
+			child,
+
+			root_x,
+			root_y,
+			event_x,
+			event_y,
+
+			state: self.get_input_state(),
+			same_screen: same_screen as _,
+
+			_padding: 0,
+		}
+	}
+
+	/// Delivers a device event originating from the given source window.
+	///
+	/// The event is propagated to the ancestors of the source window until a client selecting
+	/// it is found, unless propagation is prevented by a window's do-not-propagate mask.
+	///
+	/// Arguments:
+	/// - `source` is the ID of the window in which the event originates.
+	/// - `mask` is the mask of events under which the event may be selected.
+	/// - `code` is the event code.
+	/// - `detail` is the detail of the event.
+	pub fn deliver_device_event(&mut self, source: NonZeroU32, mask: u32, code: u8, detail: u8) {
+		let mut wid = source;
+		let mut child = 0;
+
+		while let Some(win) = self.get_window(wid) {
+			if win.get_listeners(mask).next().is_some() {
+				let event = self.build_input_event(code, detail, wid, child);
+				self.deliver_event(wid, mask, &event);
+				return;
+			}
+
+			if win.attributes.do_not_propagate_mask & mask != 0 {
+				return;
+			}
+			let Some(parent) = win.get_parent() else {
+				return;
+			};
+
+			child = wid.get();
+			wid = parent;
+		}
+	}
+
+	/// Sends a crossing event to the given window.
+	///
+	/// Arguments:
+	/// - `code` is the event code.
+	/// - `wid` is the ID of the window the event is reported to.
+	/// - `detail` is the crossing detail.
+	/// - `child` is the ID of the child of the window on the path to the pointer, if any.
+	fn send_crossing_event(
+		&mut self,
+		code: event::Event,
+		wid: NonZeroU32,
+		detail: u8,
+		child: u32,
+	) {
+		let mask = match code {
+			event::Event::EnterNotify => protocol::Event::EnterWindow,
+			_ => protocol::Event::LeaveWindow,
+		};
+		let e = self.build_input_event(code as _, detail, wid, child);
+
+		let event = CrossingEvent {
+			code: code as _,
+			detail,
+			seq_nbr: 0,
+
+			time: e.time,
+			root: e.root,
+			event: e.event,
+			child: e.child,
+
+			root_x: e.root_x,
+			root_y: e.root_y,
+			event_x: e.event_x,
+			event_y: e.event_y,
+
+			state: e.state,
+			mode: event::NOTIFY_NORMAL,
+			same_screen_focus: e.same_screen << 1,
+		};
+		self.deliver_event(wid, mask as _, &event);
+
+		// `KeymapNotify` follows every `EnterNotify`
+		if matches!(code, event::Event::EnterNotify) {
+			let mut keys = [0; 31];
+			keys.copy_from_slice(&self.keyboard.get_keys()[1..]);
+
+			let event = KeymapNotifyEvent {
+				code: event::Event::KeymapNotify as _,
+				keys,
+			};
+			self.deliver_event(wid, protocol::Event::KeymapState as _, &event);
+		}
+	}
+
+	/// Sends `LeaveNotify` and `EnterNotify` events for the pointer moving from window `old` to
+	/// window `new`.
+	fn send_crossing_events(&mut self, old: Option<NonZeroU32>, new: Option<NonZeroU32>) {
+		let old_chain = self.get_ancestors(old);
+		let new_chain = self.get_ancestors(new);
+
+		// Child of the given chain's element at index `i`, toward the chain's first element
+		let child_of = |chain: &[NonZeroU32], i: usize| chain[i - 1].get();
+
+		if let Some(i) = new.and_then(|new| old_chain.iter().position(|w| *w == new)) {
+			// `new` is an ancestor of `old`
+			self.send_crossing_event(
+				event::Event::LeaveNotify,
+				old_chain[0],
+				event::NOTIFY_ANCESTOR,
+				0,
+			);
+			for j in 1..i {
+				let child = child_of(&old_chain, j);
+				self.send_crossing_event(
+					event::Event::LeaveNotify,
+					old_chain[j],
+					event::NOTIFY_VIRTUAL,
+					child,
+				);
+			}
+			self.send_crossing_event(
+				event::Event::EnterNotify,
+				new_chain[0],
+				event::NOTIFY_INFERIOR,
+				child_of(&old_chain, i),
+			);
+		} else if let Some(i) = old.and_then(|old| new_chain.iter().position(|w| *w == old)) {
+			// `new` is an inferior of `old`
+			self.send_crossing_event(
+				event::Event::LeaveNotify,
+				old_chain[0],
+				event::NOTIFY_INFERIOR,
+				child_of(&new_chain, i),
+			);
+			for j in (1..i).rev() {
+				let child = child_of(&new_chain, j);
+				self.send_crossing_event(
+					event::Event::EnterNotify,
+					new_chain[j],
+					event::NOTIFY_VIRTUAL,
+					child,
+				);
+			}
+			self.send_crossing_event(
+				event::Event::EnterNotify,
+				new_chain[0],
+				event::NOTIFY_ANCESTOR,
+				0,
+			);
+		} else {
+			// Windows are not in a line. The common ancestor does not receive events. If the
+			// windows are on different screens, there is no common ancestor
+			let common = old_chain.iter().find(|w| new_chain.contains(w)).cloned();
+			let old_end = old_chain
+				.iter()
+				.position(|w| Some(*w) == common)
+				.unwrap_or(old_chain.len());
+			let new_end = new_chain
+				.iter()
+				.position(|w| Some(*w) == common)
+				.unwrap_or(new_chain.len());
+
+			if !old_chain.is_empty() {
+				self.send_crossing_event(
+					event::Event::LeaveNotify,
+					old_chain[0],
+					event::NOTIFY_NONLINEAR,
+					0,
+				);
+			}
+			for j in 1..old_end {
+				let child = child_of(&old_chain, j);
+				self.send_crossing_event(
+					event::Event::LeaveNotify,
+					old_chain[j],
+					event::NOTIFY_NONLINEAR_VIRTUAL,
+					child,
+				);
+			}
+			for j in (1..new_end).rev() {
+				let child = child_of(&new_chain, j);
+				self.send_crossing_event(
+					event::Event::EnterNotify,
+					new_chain[j],
+					event::NOTIFY_NONLINEAR_VIRTUAL,
+					child,
+				);
+			}
+			if !new_chain.is_empty() {
+				self.send_crossing_event(
+					event::Event::EnterNotify,
+					new_chain[0],
+					event::NOTIFY_NONLINEAR,
+					0,
+				);
+			}
+		}
+	}
+}
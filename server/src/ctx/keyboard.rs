@@ -0,0 +1,672 @@
+//! The keyboard translates the keycodes of its keys into keysyms through a keymap.
+
+use crate::input::device::*;
+use crate::input::Feedback;
+use crate::input::Keycode;
+use crate::input::KEYCODE_OFFSET;
+use crate::protocol::keysym;
+use crate::protocol::keysym::Keysym;
+use crate::protocol::keysym::NO_SYMBOL;
+use crate::protocol::KeyMask;
+use std::mem;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The minimum keycode reported by the server.
+pub const MIN_KEYCODE: Keycode = KEYCODE_OFFSET as _;
+/// The maximum keycode reported by the server.
+pub const MAX_KEYCODE: Keycode = Keycode::MAX;
+
+/// The default US layout. Each entry associates an evdev key with the names of the keysyms
+/// it produces, without and with Shift.
+const US_LAYOUT: &[(u16, [&str; 2])] = &[
+	(KEY_ESC, ["Escape", "NoSymbol"]),
+	(KEY_1, ["1", "exclam"]),
+	(KEY_2, ["2", "at"]),
+	(KEY_3, ["3", "numbersign"]),
+	(KEY_4, ["4", "dollar"]),
+	(KEY_5, ["5", "percent"]),
+	(KEY_6, ["6", "asciicircum"]),
+	(KEY_7, ["7", "ampersand"]),
+	(KEY_8, ["8", "asterisk"]),
+	(KEY_9, ["9", "parenleft"]),
+	(KEY_0, ["0", "parenright"]),
+	(KEY_MINUS, ["minus", "underscore"]),
+	(KEY_EQUAL, ["equal", "plus"]),
+	(KEY_BACKSPACE, ["BackSpace", "NoSymbol"]),
+	(KEY_TAB, ["Tab", "ISO_Left_Tab"]),
+	(KEY_Q, ["q", "Q"]),
+	(KEY_W, ["w", "W"]),
+	(KEY_E, ["e", "E"]),
+	(KEY_R, ["r", "R"]),
+	(KEY_T, ["t", "T"]),
+	(KEY_Y, ["y", "Y"]),
+	(KEY_U, ["u", "U"]),
+	(KEY_I, ["i", "I"]),
+	(KEY_O, ["o", "O"]),
+	(KEY_P, ["p", "P"]),
+	(KEY_LEFTBRACE, ["bracketleft", "braceleft"]),
+	(KEY_RIGHTBRACE, ["bracketright", "braceright"]),
+	(KEY_ENTER, ["Return", "NoSymbol"]),
+	(KEY_LEFTCTRL, ["Control_L", "NoSymbol"]),
+	(KEY_A, ["a", "A"]),
+	(KEY_S, ["s", "S"]),
+	(KEY_D, ["d", "D"]),
+	(KEY_F, ["f", "F"]),
+	(KEY_G, ["g", "G"]),
+	(KEY_H, ["h", "H"]),
+	(KEY_J, ["j", "J"]),
+	(KEY_K, ["k", "K"]),
+	(KEY_L, ["l", "L"]),
+	(KEY_SEMICOLON, ["semicolon", "colon"]),
+	(KEY_APOSTROPHE, ["apostrophe", "quotedbl"]),
+	(KEY_GRAVE, ["grave", "asciitilde"]),
+	(KEY_LEFTSHIFT, ["Shift_L", "NoSymbol"]),
+	(KEY_BACKSLASH, ["backslash", "bar"]),
+	(KEY_Z, ["z", "Z"]),
+	(KEY_X, ["x", "X"]),
+	(KEY_C, ["c", "C"]),
+	(KEY_V, ["v", "V"]),
+	(KEY_B, ["b", "B"]),
+	(KEY_N, ["n", "N"]),
+	(KEY_M, ["m", "M"]),
+	(KEY_COMMA, ["comma", "less"]),
+	(KEY_DOT, ["period", "greater"]),
+	(KEY_SLASH, ["slash", "question"]),
+	(KEY_RIGHTSHIFT, ["Shift_R", "NoSymbol"]),
+	(KEY_KPASTERISK, ["KP_Multiply", "NoSymbol"]),
+	(KEY_LEFTALT, ["Alt_L", "Meta_L"]),
+	(KEY_SPACE, ["space", "NoSymbol"]),
+	(KEY_CAPSLOCK, ["Caps_Lock", "NoSymbol"]),
+	(KEY_F1, ["F1", "NoSymbol"]),
+	(KEY_F2, ["F2", "NoSymbol"]),
+	(KEY_F3, ["F3", "NoSymbol"]),
+	(KEY_F4, ["F4", "NoSymbol"]),
+	(KEY_F5, ["F5", "NoSymbol"]),
+	(KEY_F6, ["F6", "NoSymbol"]),
+	(KEY_F7, ["F7", "NoSymbol"]),
+	(KEY_F8, ["F8", "NoSymbol"]),
+	(KEY_F9, ["F9", "NoSymbol"]),
+	(KEY_F10, ["F10", "NoSymbol"]),
+	(KEY_NUMLOCK, ["Num_Lock", "NoSymbol"]),
+	(KEY_SCROLLLOCK, ["Scroll_Lock", "NoSymbol"]),
+	(KEY_KP7, ["KP_Home", "KP_7"]),
+	(KEY_KP8, ["KP_Up", "KP_8"]),
+	(KEY_KP9, ["KP_Prior", "KP_9"]),
+	(KEY_KPMINUS, ["KP_Subtract", "NoSymbol"]),
+	(KEY_KP4, ["KP_Left", "KP_4"]),
+	(KEY_KP5, ["KP_Begin", "KP_5"]),
+	(KEY_KP6, ["KP_Right", "KP_6"]),
+	(KEY_KPPLUS, ["KP_Add", "NoSymbol"]),
+	(KEY_KP1, ["KP_End", "KP_1"]),
+	(KEY_KP2, ["KP_Down", "KP_2"]),
+	(KEY_KP3, ["KP_Next", "KP_3"]),
+	(KEY_KP0, ["KP_Insert", "KP_0"]),
+	(KEY_KPDOT, ["KP_Delete", "KP_Decimal"]),
+	(KEY_102ND, ["less", "greater"]),
+	(KEY_F11, ["F11", "NoSymbol"]),
+	(KEY_F12, ["F12", "NoSymbol"]),
+	(KEY_KPENTER, ["KP_Enter", "NoSymbol"]),
+	(KEY_RIGHTCTRL, ["Control_R", "NoSymbol"]),
+	(KEY_KPSLASH, ["KP_Divide", "NoSymbol"]),
+	(KEY_SYSRQ, ["Print", "Sys_Req"]),
+	(KEY_RIGHTALT, ["Alt_R", "Meta_R"]),
+	(KEY_LINEFEED, ["Linefeed", "NoSymbol"]),
+	(KEY_HOME, ["Home", "NoSymbol"]),
+	(KEY_UP, ["Up", "NoSymbol"]),
+	(KEY_PAGEUP, ["Prior", "NoSymbol"]),
+	(KEY_LEFT, ["Left", "NoSymbol"]),
+	(KEY_RIGHT, ["Right", "NoSymbol"]),
+	(KEY_END, ["End", "NoSymbol"]),
+	(KEY_DOWN, ["Down", "NoSymbol"]),
+	(KEY_PAGEDOWN, ["Next", "NoSymbol"]),
+	(KEY_INSERT, ["Insert", "NoSymbol"]),
+	(KEY_DELETE, ["Delete", "NoSymbol"]),
+	(KEY_MUTE, ["XF86AudioMute", "NoSymbol"]),
+	(KEY_VOLUMEDOWN, ["XF86AudioLowerVolume", "NoSymbol"]),
+	(KEY_VOLUMEUP, ["XF86AudioRaiseVolume", "NoSymbol"]),
+	(KEY_POWER, ["XF86PowerOff", "NoSymbol"]),
+	(KEY_KPEQUAL, ["KP_Equal", "NoSymbol"]),
+	(KEY_PAUSE, ["Pause", "Break"]),
+	(KEY_LEFTMETA, ["Super_L", "NoSymbol"]),
+	(KEY_RIGHTMETA, ["Super_R", "NoSymbol"]),
+	(KEY_COMPOSE, ["Menu", "NoSymbol"]),
+	(KEY_UNDO, ["Undo", "NoSymbol"]),
+	(KEY_COPY, ["XF86Copy", "NoSymbol"]),
+	(KEY_PASTE, ["XF86Paste", "NoSymbol"]),
+	(KEY_FIND, ["Find", "NoSymbol"]),
+	(KEY_CUT, ["XF86Cut", "NoSymbol"]),
+	(KEY_HELP, ["Help", "NoSymbol"]),
+	(KEY_CALC, ["XF86Calculator", "NoSymbol"]),
+	(KEY_SLEEP, ["XF86Sleep", "NoSymbol"]),
+	(KEY_WAKEUP, ["XF86WakeUp", "NoSymbol"]),
+	(KEY_WWW, ["XF86WWW", "NoSymbol"]),
+];
+
+/// Table associating each keycode with a list of keysyms.
+///
+/// Every keycode has the same number of keysyms. Unused entries are set to `NoSymbol`.
+pub struct Keymap {
+	/// The number of keysyms associated with each keycode.
+	keysyms_per_keycode: u8,
+	/// The keysyms, starting with the ones of `MIN_KEYCODE`.
+	keysyms: Vec<Keysym>,
+}
+
+impl Keymap {
+	/// Creates a keymap with no keysym assigned.
+	///
+	/// `keysyms_per_keycode` is the number of keysyms associated with each keycode.
+	pub fn new(keysyms_per_keycode: u8) -> Self {
+		let count = (MAX_KEYCODE - MIN_KEYCODE) as usize + 1;
+
+		Self {
+			keysyms_per_keycode,
+			keysyms: vec![NO_SYMBOL; count * keysyms_per_keycode as usize],
+		}
+	}
+
+	/// Creates the default US keymap.
+	pub fn us() -> Self {
+		let mut keymap = Self::new(2);
+
+		for (key, names) in US_LAYOUT {
+			let keycode = (key + KEYCODE_OFFSET) as Keycode;
+			// Names in the layout are known to the database
+			let syms = names.map(|n| keysym::from_name(n).unwrap());
+			keymap.set(keycode, 2, &syms);
+		}
+
+		keymap
+	}
+
+	/// Returns the number of keysyms associated with each keycode.
+	pub fn get_keysyms_per_keycode(&self) -> u8 {
+		self.keysyms_per_keycode
+	}
+
+	/// Returns the keysyms associated with the given keycode.
+	///
+	/// If the keycode is below `MIN_KEYCODE`, the function returns an empty slice.
+	pub fn get(&self, keycode: Keycode) -> &[Keysym] {
+		if keycode < MIN_KEYCODE {
+			return &[];
+		}
+
+		let per = self.keysyms_per_keycode as usize;
+		let off = (keycode - MIN_KEYCODE) as usize * per;
+		&self.keysyms[off..(off + per)]
+	}
+
+	/// Sets the keysyms of consecutive keycodes.
+	///
+	/// If `keysyms_per_keycode` is greater than the current number of keysyms per keycode, the
+	/// table is widened and missing entries are set to `NoSymbol`.
+	///
+	/// Arguments:
+	/// - `first` is the first keycode to modify.
+	/// - `keysyms_per_keycode` is the number of keysyms given for each keycode.
+	/// - `keysyms` is the list of keysyms, `keysyms_per_keycode` for each keycode.
+	///
+	/// Keycodes must be in the range of the keymap.
+	pub fn set(&mut self, first: Keycode, keysyms_per_keycode: u8, keysyms: &[Keysym]) {
+		if keysyms_per_keycode > self.keysyms_per_keycode {
+			self.widen(keysyms_per_keycode);
+		}
+
+		let per = self.keysyms_per_keycode as usize;
+		let new_per = keysyms_per_keycode as usize;
+		if new_per == 0 {
+			return;
+		}
+
+		for (i, syms) in keysyms.chunks(new_per).enumerate() {
+			let off = (first - MIN_KEYCODE) as usize * per + i * per;
+			let entry = &mut self.keysyms[off..(off + per)];
+
+			entry[..new_per].copy_from_slice(syms);
+			entry[new_per..].fill(NO_SYMBOL);
+		}
+	}
+
+	/// Increases the number of keysyms per keycode, keeping the current ones.
+	fn widen(&mut self, keysyms_per_keycode: u8) {
+		let mut keymap = Self::new(keysyms_per_keycode);
+
+		let per = self.keysyms_per_keycode as usize;
+		let new_per = keysyms_per_keycode as usize;
+		for (i, syms) in self.keysyms.chunks(per).enumerate() {
+			keymap.keysyms[(i * new_per)..(i * new_per + per)].copy_from_slice(syms);
+		}
+
+		*self = keymap;
+	}
+}
+
+/// The number of modifiers.
+pub const MODIFIERS_COUNT: usize = 8;
+
+/// The keysyms of keys that lock their modifiers when pressed, until pressed again. Each keysym
+/// is associated with the index of the LED showing the lock, starting from zero.
+const LOCKING_KEYSYMS: &[(&str, u32)] = &[
+	("Caps_Lock", 0),
+	("Shift_Lock", 0),
+	("Num_Lock", 1),
+	("Scroll_Lock", 2),
+];
+
+/// The keysyms of the keys assigned to each modifier by default, in the order of `KeyMask`.
+const DEFAULT_MODIFIERS: [&[&str]; MODIFIERS_COUNT] = [
+	&["Shift_L", "Shift_R"],
+	&["Caps_Lock"],
+	&["Control_L", "Control_R"],
+	&["Alt_L", "Alt_R", "Meta_L", "Meta_R"],
+	&["Num_Lock"],
+	&[],
+	&["Super_L", "Super_R", "Hyper_L", "Hyper_R"],
+	&["ISO_Level3_Shift", "Mode_switch"],
+];
+
+/// Table associating each modifier with the keycodes of the keys setting it.
+///
+/// Every modifier has the same number of keycodes. Unused entries are set to zero.
+#[derive(Clone, PartialEq)]
+pub struct ModifierMap {
+	/// The number of keycodes associated with each modifier.
+	keycodes_per_modifier: u8,
+	/// The keycodes, in the order of `KeyMask`.
+	keycodes: Vec<Keycode>,
+}
+
+impl ModifierMap {
+	/// Creates a modifier map.
+	///
+	/// Arguments:
+	/// - `keycodes_per_modifier` is the number of keycodes associated with each modifier.
+	/// - `keycodes` is the list of keycodes, `keycodes_per_modifier` for each modifier.
+	pub fn new(keycodes_per_modifier: u8, keycodes: Vec<Keycode>) -> Self {
+		Self {
+			keycodes_per_modifier,
+			keycodes,
+		}
+	}
+
+	/// Creates the default modifier map for the given keymap.
+	pub fn from_keymap(keymap: &Keymap) -> Self {
+		let modifiers = DEFAULT_MODIFIERS.map(|names| {
+			let syms: Vec<Keysym> = names.iter().filter_map(|n| keysym::from_name(n)).collect();
+
+			(MIN_KEYCODE..=MAX_KEYCODE)
+				.filter(|k| keymap.get(*k).first().is_some_and(|s| syms.contains(s)))
+				.collect::<Vec<_>>()
+		});
+
+		let per = modifiers.iter().map(Vec::len).max().unwrap_or(0);
+		let mut keycodes = vec![0; per * MODIFIERS_COUNT];
+		for (i, m) in modifiers.iter().enumerate() {
+			keycodes[(i * per)..(i * per + m.len())].copy_from_slice(m);
+		}
+
+		Self::new(per as _, keycodes)
+	}
+
+	/// Returns the number of keycodes associated with each modifier.
+	pub fn get_keycodes_per_modifier(&self) -> u8 {
+		self.keycodes_per_modifier
+	}
+
+	/// Returns the list of keycodes, `keycodes_per_modifier` for each modifier.
+	pub fn get_keycodes(&self) -> &[Keycode] {
+		&self.keycodes
+	}
+
+	/// Returns the keycodes associated with the modifier at the given index, including unused
+	/// entries.
+	pub fn get_modifier(&self, modifier: usize) -> &[Keycode] {
+		let per = self.keycodes_per_modifier as usize;
+		&self.keycodes[(modifier * per)..((modifier + 1) * per)]
+	}
+
+	/// Returns the mask of the modifiers the given keycode is associated with.
+	pub fn get_modifiers_of(&self, keycode: Keycode) -> u16 {
+		(0..MODIFIERS_COUNT)
+			.filter(|i| self.get_modifier(*i).contains(&keycode))
+			.fold(0, |mask, i| mask | KeyMask::from_index(i).unwrap() as u16)
+	}
+
+	/// Tells whether a keycode is associated with several modifiers.
+	pub fn has_shared_keycodes(&self) -> bool {
+		self.keycodes
+			.iter()
+			.filter(|k| **k != 0)
+			.any(|k| self.get_modifiers_of(*k).count_ones() > 1)
+	}
+}
+
+/// The default volume of key clicks in percent.
+pub const DEFAULT_KEY_CLICK_PERCENT: u8 = 0;
+/// The default volume of the bell in percent.
+pub const DEFAULT_BELL_PERCENT: u8 = 50;
+/// The default pitch of the bell in Hz.
+pub const DEFAULT_BELL_PITCH: u16 = 400;
+/// The default duration of the bell in milliseconds.
+pub const DEFAULT_BELL_DURATION: u16 = 100;
+
+/// The default delay before a held key starts repeating.
+pub const DEFAULT_REPEAT_DELAY: Duration = Duration::from_millis(660);
+/// The default interval between two repetitions of a held key.
+pub const DEFAULT_REPEAT_INTERVAL: Duration = Duration::from_millis(40);
+
+/// The settings of the keyboard, as set by `ChangeKeyboardControl`.
+#[derive(Clone)]
+pub struct KeyboardControl {
+	/// The volume of key clicks in percent.
+	pub key_click_percent: u8,
+	/// The volume of the bell in percent.
+	pub bell_percent: u8,
+	/// The pitch of the bell in Hz.
+	pub bell_pitch: u16,
+	/// The duration of the bell in milliseconds.
+	pub bell_duration: u16,
+
+	/// Tells whether keys repeat when held.
+	pub global_auto_repeat: bool,
+	/// Bitmap of keys allowed to repeat when held, indexed by keycode.
+	pub auto_repeats: [u8; 32],
+}
+
+impl KeyboardControl {
+	/// Tells whether the key with the given keycode is allowed to repeat.
+	pub fn is_auto_repeat(&self, keycode: Keycode) -> bool {
+		self.auto_repeats[keycode as usize / 8] & (1 << (keycode % 8)) != 0
+	}
+
+	/// Sets whether the key with the given keycode is allowed to repeat.
+	pub fn set_auto_repeat(&mut self, keycode: Keycode, enable: bool) {
+		let bit = 1 << (keycode % 8);
+
+		if enable {
+			self.auto_repeats[keycode as usize / 8] |= bit;
+		} else {
+			self.auto_repeats[keycode as usize / 8] &= !bit;
+		}
+	}
+}
+
+impl Default for KeyboardControl {
+	fn default() -> Self {
+		Self {
+			key_click_percent: DEFAULT_KEY_CLICK_PERCENT,
+			bell_percent: DEFAULT_BELL_PERCENT,
+			bell_pitch: DEFAULT_BELL_PITCH,
+			bell_duration: DEFAULT_BELL_DURATION,
+
+			global_auto_repeat: true,
+			auto_repeats: [0xff; 32],
+		}
+	}
+}
+
+/// The state of the keyboard.
+pub struct Keyboard {
+	/// The keymap.
+	keymap: Keymap,
+	/// The modifier map.
+	modifier_map: ModifierMap,
+	/// The settings.
+	control: KeyboardControl,
+
+	/// Bitmap of the keys currently pressed, indexed by keycode.
+	keys: [u8; 32],
+	/// The mask of modifiers currently locked.
+	locked_modifiers: u16,
+	/// The mask of LEDs currently lit. Bit `n` is LED `n + 1` in the protocol.
+	leds: u32,
+
+	/// The delay before a held key starts repeating.
+	repeat_delay: Duration,
+	/// The interval between two repetitions of a held key.
+	repeat_interval: Duration,
+	/// The key currently repeating, with the instant of its next repetition.
+	repeat: Option<(Keycode, Instant)>,
+
+	/// Feedback waiting to be given to the user through devices.
+	feedback: Vec<Feedback>,
+}
+
+impl Keyboard {
+	/// Returns an immutable reference to the keymap.
+	pub fn get_keymap(&self) -> &Keymap {
+		&self.keymap
+	}
+
+	/// Returns a mutable reference to the keymap.
+	pub fn get_keymap_mut(&mut self) -> &mut Keymap {
+		&mut self.keymap
+	}
+
+	/// Returns an immutable reference to the modifier map.
+	pub fn get_modifier_map(&self) -> &ModifierMap {
+		&self.modifier_map
+	}
+
+	/// Sets the modifier map.
+	///
+	/// If a modifier whose keycodes change has one of its current or new keys pressed, the map
+	/// is not changed and the function returns `false`.
+	pub fn set_modifier_map(&mut self, map: ModifierMap) -> bool {
+		let busy = (0..MODIFIERS_COUNT).any(|i| {
+			let old = self.modifier_map.get_modifier(i);
+			let new = map.get_modifier(i);
+
+			let mut old_keys: Vec<_> = old.iter().filter(|k| **k != 0).collect();
+			let mut new_keys: Vec<_> = new.iter().filter(|k| **k != 0).collect();
+			old_keys.sort();
+			new_keys.sort();
+
+			old_keys != new_keys
+				&& old_keys
+					.iter()
+					.chain(new_keys.iter())
+					.any(|k| self.is_pressed(**k))
+		});
+		if busy {
+			return false;
+		}
+
+		self.modifier_map = map;
+		true
+	}
+
+	/// Returns an immutable reference to the settings.
+	pub fn get_control(&self) -> &KeyboardControl {
+		&self.control
+	}
+
+	/// Returns a mutable reference to the settings.
+	pub fn get_control_mut(&mut self) -> &mut KeyboardControl {
+		&mut self.control
+	}
+
+	/// Returns the bitmap of the keys currently pressed, indexed by keycode.
+	pub fn get_keys(&self) -> &[u8; 32] {
+		&self.keys
+	}
+
+	/// Tells whether the key with the given keycode is pressed.
+	pub fn is_pressed(&self, keycode: Keycode) -> bool {
+		self.keys[keycode as usize / 8] & (1 << (keycode % 8)) != 0
+	}
+
+	/// If the key with the given keycode locks its modifiers when pressed, the function returns
+	/// the index of the LED showing the lock.
+	fn get_lock_led(&self, keycode: Keycode) -> Option<u32> {
+		let name = self.get_keysym_name(keycode)?;
+
+		LOCKING_KEYSYMS
+			.iter()
+			.find(|(n, _)| *n == name)
+			.map(|(_, led)| *led)
+	}
+
+	/// Tells whether the key with the given keycode locks its modifiers when pressed.
+	pub fn is_locking(&self, keycode: Keycode) -> bool {
+		self.get_lock_led(keycode).is_some()
+	}
+
+	/// Returns the name of the first keysym of the key with the given keycode, if any.
+	pub fn get_keysym_name(&self, keycode: Keycode) -> Option<&'static str> {
+		self.keymap
+			.get(keycode)
+			.first()
+			.and_then(|s| keysym::get_name(*s))
+	}
+
+	/// Tells whether the key with the given keycode repeats when held.
+	///
+	/// Keys used as modifiers never repeat.
+	fn is_repeating(&self, keycode: Keycode) -> bool {
+		self.control.global_auto_repeat
+			&& self.control.is_auto_repeat(keycode)
+			&& self.modifier_map.get_modifiers_of(keycode) == 0
+	}
+
+	/// Sets whether the key with the given keycode is pressed.
+	///
+	/// Pressing a locking key toggles the lock of its modifiers and its LED. Pressing a key
+	/// allowed to repeat starts its repetition.
+	pub fn set_pressed(&mut self, keycode: Keycode, pressed: bool) {
+		let bit = 1 << (keycode % 8);
+
+		if pressed {
+			if !self.is_pressed(keycode) {
+				if let Some(led) = self.get_lock_led(keycode) {
+					self.locked_modifiers ^= self.modifier_map.get_modifiers_of(keycode);
+					self.set_leds(self.leds ^ (1 << led));
+				}
+			}
+
+			if self.is_repeating(keycode) {
+				self.repeat = Some((keycode, Instant::now() + self.repeat_delay));
+			}
+			self.feedback
+				.push(Feedback::Click(self.control.key_click_percent));
+
+			self.keys[keycode as usize / 8] |= bit;
+		} else {
+			if matches!(self.repeat, Some((k, _)) if k == keycode) {
+				self.repeat = None;
+			}
+
+			self.keys[keycode as usize / 8] &= !bit;
+		}
+	}
+
+	/// Returns the mask of modifiers currently active, in the format of `KeyMask`.
+	pub fn get_modifiers_state(&self) -> u16 {
+		// Locking keys only act through their lock
+		let pressed = (0..MODIFIERS_COUNT)
+			.filter(|i| {
+				self.modifier_map
+					.get_modifier(*i)
+					.iter()
+					.any(|k| *k != 0 && self.is_pressed(*k) && self.get_lock_led(*k).is_none())
+			})
+			.fold(0, |mask, i| mask | KeyMask::from_index(i).unwrap() as u16);
+
+		pressed | self.locked_modifiers
+	}
+
+	/// Returns the mask of modifiers currently locked, in the format of `KeyMask`.
+	pub fn get_locked_modifiers(&self) -> u16 {
+		self.locked_modifiers
+	}
+
+	/// Returns the mask of LEDs currently lit. Bit `n` is LED `n + 1` in the protocol.
+	pub fn get_leds(&self) -> u32 {
+		self.leds
+	}
+
+	/// Sets the mask of LEDs currently lit.
+	pub fn set_leds(&mut self, leds: u32) {
+		if leds != self.leds {
+			self.leds = leds;
+			self.feedback.push(Feedback::Leds(leds));
+		}
+	}
+
+	/// Sets the delay before a held key starts repeating and the interval between two
+	/// repetitions.
+	pub fn set_repeat_rate(&mut self, delay: Duration, interval: Duration) {
+		self.repeat_delay = delay;
+		self.repeat_interval = interval;
+	}
+
+	/// Returns the instant of the next repetition of the held key, if any.
+	pub fn get_repeat_deadline(&self) -> Option<Instant> {
+		self.repeat.map(|(_, deadline)| deadline)
+	}
+
+	/// If the repetition of the held key is due, the function schedules the next one and returns
+	/// the keycode of the key.
+	pub fn take_repeat(&mut self) -> Option<Keycode> {
+		let now = Instant::now();
+
+		match &mut self.repeat {
+			Some((keycode, deadline)) if *deadline <= now => {
+				*deadline = now + self.repeat_interval;
+				Some(*keycode)
+			}
+
+			_ => None,
+		}
+	}
+
+	/// Rings the bell.
+	///
+	/// `percent` is the volume relative to the base volume of the bell, in the range
+	/// `-100..=100`.
+	pub fn bell(&mut self, percent: i8) {
+		let base = self.control.bell_percent as i32;
+		let percent = percent as i32;
+		let volume = if percent >= 0 {
+			base - (base * percent) / 100 + percent
+		} else {
+			base + (base * percent) / 100
+		};
+
+		self.feedback.push(Feedback::Bell {
+			volume: volume as _,
+			pitch: self.control.bell_pitch,
+			duration: self.control.bell_duration,
+		});
+	}
+
+	/// Takes the feedback waiting to be given to the user through devices.
+	pub fn take_feedback(&mut self) -> Vec<Feedback> {
+		mem::take(&mut self.feedback)
+	}
+}
+
+impl Default for Keyboard {
+	fn default() -> Self {
+		let keymap = Keymap::us();
+		let modifier_map = ModifierMap::from_keymap(&keymap);
+
+		Self {
+			keymap,
+			modifier_map,
+			control: KeyboardControl::default(),
+
+			keys: [0; 32],
+			locked_modifiers: 0,
+			leds: 0,
+
+			repeat_delay: DEFAULT_REPEAT_DELAY,
+			repeat_interval: DEFAULT_REPEAT_INTERVAL,
+			repeat: None,
+
+			feedback: vec![],
+		}
+	}
+}
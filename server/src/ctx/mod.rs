@@ -1,7 +1,15 @@
 //! TODO doc
 
+pub mod acceleration;
+pub mod accessx;
 pub mod client;
+pub mod colormap;
+pub mod exposure;
 pub mod gc;
+pub mod image;
+pub mod input;
+pub mod keyboard;
+pub mod pixmap;
 pub mod pointer;
 pub mod screen;
 pub mod window;
@@ -9,16 +17,39 @@ pub mod window;
 use crate::output::card::DRICard;
 use crate::output::connector::DRIConnector;
 use crate::poll::PollHandler;
+use crate::protocol;
+use crate::protocol::event::Event;
+use crate::protocol::event::MapNotifyEvent;
+use crate::protocol::event::ReparentNotifyEvent;
+use crate::protocol::event::UnmapNotifyEvent;
 use crate::protocol::request::RequestReadFn;
+use crate::protocol::Class;
+use crate::protocol::MapState;
 use crate::protocol::Rectangle;
 use crate::screens_layout::ScreensLayout;
+use acceleration::Acceleration;
+use accessx::AccessX;
 use client::Client;
+use colormap::Color;
+use colormap::Colormap;
+use exposure::SaveUnder;
+use exposure::Visibility;
+use gc::GC;
+use keyboard::Keyboard;
+use pixmap::Pixmap;
 use pointer::Pointer;
 use screen::Screen;
 use std::cell::UnsafeCell;
 use std::collections::HashMap;
 use std::collections::LinkedList;
+use std::error::Error;
+use std::mem;
+use std::mem::size_of;
 use std::num::NonZeroU32;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::rc::Rc;
+use std::time::Instant;
 use window::Window;
 
 // TODO Move in its own module?
@@ -59,6 +90,14 @@ pub struct Context<'a> {
 	screens: Vec<Screen<'a>>,
 	/// The list of windows.
 	windows: HashMap<NonZeroU32, Window>,
+	/// The areas saved under mapped windows with save-under. The key is the ID of the window.
+	save_unders: HashMap<NonZeroU32, SaveUnder>,
+	/// The list of pixmaps. The key is the ID of the pixmap.
+	pixmaps: HashMap<NonZeroU32, Rc<Pixmap>>,
+	/// The list of graphics contexts. The key is the ID of the graphics context.
+	gcs: HashMap<NonZeroU32, GC>,
+	/// The list of colormaps. The key is the ID of the colormap.
+	colormaps: HashMap<NonZeroU32, Colormap>,
 
 	/// The list of atoms on the server. The key is the ID of the atom.
 	atoms: HashMap<u32, String>,
@@ -70,6 +109,9 @@ pub struct Context<'a> {
 	clients: UnsafeCell<LinkedList<Client>>,
 	/// The client currently grabbing the server.
 	grabbing_client: Option<u32>,
+	/// Events waiting to be sent. The key is the ID of the client. Every connected client has an
+	/// entry.
+	pending_events: HashMap<u32, Vec<[u8; 32]>>,
 
 	/// Requests handlers registered by extensions.
 	/// The key is the major opcode and the value is the handler.
@@ -77,6 +119,15 @@ pub struct Context<'a> {
 
 	/// The pointer, controller by user inputs.
 	pointer: Pointer,
+	/// The acceleration applied to relative motions of the pointer.
+	acceleration: Acceleration,
+	/// The keyboard.
+	keyboard: Keyboard,
+	/// The state of keyboard accessibility features.
+	accessx: AccessX,
+
+	/// The instant at which the server started, used to compute timestamps.
+	start_time: Instant,
 }
 
 impl<'a> Context<'a> {
@@ -85,6 +136,10 @@ impl<'a> Context<'a> {
 		Self {
 			screens: Vec::new(),
 			windows: HashMap::new(),
+			save_unders: HashMap::new(),
+			pixmaps: HashMap::new(),
+			gcs: HashMap::new(),
+			colormaps: HashMap::new(),
 
 			atoms: HashMap::from([
 				(1, "PRIMARY".to_owned()),
@@ -160,10 +215,16 @@ impl<'a> Context<'a> {
 
 			clients: UnsafeCell::new(LinkedList::new()),
 			grabbing_client: None,
+			pending_events: HashMap::new(),
 
 			custom_requests: HashMap::new(),
 
 			pointer: Pointer::default(),
+			acceleration: Acceleration::default(),
+			keyboard: Keyboard::default(),
+			accessx: AccessX::default(),
+
+			start_time: Instant::now(),
 		}
 	}
 
@@ -176,6 +237,9 @@ impl<'a> Context<'a> {
 	pub fn init_screens(&mut self, cards: &'a [DRICard], screens_layout: Option<ScreensLayout>) {
 		self.screens.clear();
 
+		// The absolute virtual X position of the next screen, when placing screens side by side
+		let mut next_x = 0;
+
 		for dev in cards {
 			for conn in DRIConnector::scan(dev) {
 				// Selecting the screen's mode
@@ -209,11 +273,47 @@ impl<'a> Context<'a> {
 					width: mode.hdisplay,
 					height: mode.vdisplay,
 				};
-				let root = Window::new(self, None, root_rect);
+				// IDs of root windows, root visuals and default colormaps are allocated by the
+				// server
+				let base = self.screens.len() as u32 * 3;
+				let root_id = NonZeroU32::new(base + 1).unwrap();
+				let visual = base + 2;
+				let colormap_id = NonZeroU32::new(base + 3).unwrap();
+
+				// The default colormap maps each component of pixels to itself
+				let mut colormap = Colormap::new(
+					colormap_id,
+					None,
+					root_id,
+					visual,
+					screen::ROOT_VISUAL_ENTRIES as _,
+					false,
+				);
+				for i in 0..screen::ROOT_VISUAL_ENTRIES {
+					let val = i * 0x101;
+					let color = Color {
+						red: val,
+						green: val,
+						blue: val,
+					};
+					colormap.set_color(i as _, color);
+				}
+
+				let root = Window::new(self, root_id, None, root_rect);
+				root.attributes.class = Class::InputOutput;
+				root.attributes.map_state = MapState::Viewable;
+				root.attributes.background_pixel = Some(0xffffff);
+				root.attributes.visual = visual;
+				root.attributes.colormap = colormap_id.get();
 
-				// TODO Screen coords
-				let screen = Screen::new(dev, conn, mode, 0, 0, root.get_id());
+				let x = next_x;
+				next_x += mode.hdisplay as u32;
+
+				let screen = Screen::new(dev, conn, mode, x, 0, root_id, &colormap);
 				self.screens.push(screen);
+				self.add_colormap(colormap);
+				self.install_colormap(colormap_id);
+				self.expose(root_id, &Visibility::default());
 			}
 		}
 	}
@@ -228,11 +328,79 @@ impl<'a> Context<'a> {
 		&mut self.screens
 	}
 
+	/// Returns the index of the screen whose root window has the given ID.
+	pub fn get_screen_for_root(&self, root: NonZeroU32) -> Option<usize> {
+		self.screens
+			.iter()
+			.position(|s| s.get_root_window_id() == root)
+	}
+
 	/// Returns the drawable with the given ID.
 	pub fn get_drawable(&self, id: NonZeroU32) -> Option<&dyn Drawable> {
-		// TODO Handle pixmaps
+		self.get_window(id)
+			.map(|d| d as &dyn Drawable)
+			.or_else(|| self.get_pixmap(id).map(|p| &**p as &dyn Drawable))
+	}
+
+	/// Tells whether the given ID is used by a resource.
+	pub fn is_id_used(&self, id: NonZeroU32) -> bool {
+		self.windows.contains_key(&id)
+			|| self.pixmaps.contains_key(&id)
+			|| self.gcs.contains_key(&id)
+			|| self.colormaps.contains_key(&id)
+	}
+
+	/// Returns the pixmap with the given ID.
+	pub fn get_pixmap(&self, id: NonZeroU32) -> Option<&Rc<Pixmap>> {
+		self.pixmaps.get(&id)
+	}
+
+	/// Adds the given pixmap.
+	pub fn add_pixmap(&mut self, pixmap: Pixmap) {
+		self.pixmaps.insert(pixmap.get_id(), Rc::new(pixmap));
+	}
 
-		self.get_window(id).map(|d| d as &dyn Drawable)
+	/// Removes the association between the pixmap and the given ID, and returns the pixmap.
+	///
+	/// The pixmap is freed once every other reference to it is dropped.
+	pub fn free_pixmap(&mut self, id: NonZeroU32) -> Option<Rc<Pixmap>> {
+		self.pixmaps.remove(&id)
+	}
+
+	/// Returns the graphics context with the given ID.
+	pub fn get_gc(&self, id: NonZeroU32) -> Option<&GC> {
+		self.gcs.get(&id)
+	}
+
+	/// Returns the graphics context with the given ID, mutably.
+	pub fn get_gc_mut(&mut self, id: NonZeroU32) -> Option<&mut GC> {
+		self.gcs.get_mut(&id)
+	}
+
+	/// Adds the given graphics context. If a graphics context with the same ID already exists, it
+	/// is replaced.
+	pub fn add_gc(&mut self, gc: GC) {
+		self.gcs.insert(gc.get_id(), gc);
+	}
+
+	/// Removes the graphics context with the given ID and returns it.
+	pub fn free_gc(&mut self, id: NonZeroU32) -> Option<GC> {
+		self.gcs.remove(&id)
+	}
+
+	/// Returns the colormap with the given ID.
+	pub fn get_colormap(&self, id: NonZeroU32) -> Option<&Colormap> {
+		self.colormaps.get(&id)
+	}
+
+	/// Returns the colormap with the given ID, mutably.
+	pub fn get_colormap_mut(&mut self, id: NonZeroU32) -> Option<&mut Colormap> {
+		self.colormaps.get_mut(&id)
+	}
+
+	/// Adds the given colormap. If a colormap with the same ID already exists, it is replaced.
+	pub fn add_colormap(&mut self, colormap: Colormap) {
+		self.colormaps.insert(colormap.get_id(), colormap);
 	}
 
 	/// Returns an immutable reference to the window with the given ID.
@@ -288,6 +456,7 @@ impl<'a> Context<'a> {
 	/// `poll_handler` is the poll handler on which the stream is to be registered.
 	pub fn add_client(&mut self, client: Client, poll_handler: &mut PollHandler) {
 		poll_handler.add_fd(client.get_stream());
+		self.pending_events.insert(client.get_id(), vec![]);
 
 		unsafe {
 			(*self.clients.get()).push_back(client);
@@ -296,13 +465,35 @@ impl<'a> Context<'a> {
 
 	/// Ticks every connected client.
 	///
-	/// `poll_handler` is the poll handler on which the stream is to be registered.
-	pub fn tick_clients(&mut self, poll_handler: &mut PollHandler) {
+	/// Arguments:
+	/// - `poll_handler` is the poll handler on which the stream is to be registered.
+	/// - `ready` is the list of file descriptors ready for reading.
+	pub fn tick_clients(&mut self, poll_handler: &mut PollHandler, ready: &[i32]) {
+		self.for_each_client(poll_handler, |ctx, client| {
+			let readable = ready.contains(&client.get_stream().as_raw_fd());
+			client.tick(ctx, readable)
+		});
+	}
+
+	/// Sends the events waiting for every connected client.
+	///
+	/// `poll_handler` is the poll handler on which the streams are registered.
+	pub fn flush_clients(&mut self, poll_handler: &mut PollHandler) {
+		self.for_each_client(poll_handler, |ctx, client| Ok(client.flush_events(ctx)?));
+	}
+
+	/// Calls `f` on every connected client, disconnecting clients for which it fails.
+	///
+	/// `poll_handler` is the poll handler on which the streams are registered.
+	fn for_each_client<F>(&mut self, poll_handler: &mut PollHandler, mut f: F)
+	where
+		F: FnMut(&mut Self, &mut Client) -> Result<(), Box<dyn Error>>,
+	{
 		let mut cursor = unsafe { (*self.clients.get()).cursor_front_mut() };
 
 		while let Some(client) = cursor.current() {
 			// On error, remove client
-			if let Err(e) = client.tick(self) {
+			if let Err(e) = f(self, client) {
 				println!("Client disconnect: {}", e);
 
 				// If the client is grabbing the server, ungrab
@@ -311,16 +502,313 @@ impl<'a> Context<'a> {
 						self.grabbing_client = None;
 					}
 				}
+				self.forget_client(client.get_id());
 
+				// Removing moves the cursor to the next client
 				if let Some(removed) = cursor.remove_current() {
 					poll_handler.remove_fd(removed.get_stream());
 				}
+				continue;
 			}
 
 			cursor.move_next();
 		}
 	}
 
+	/// Removes every state associated with the client with the given ID.
+	fn forget_client(&mut self, client: u32) {
+		self.pending_events.remove(&client);
+
+		for win in self.windows.values_mut() {
+			win.set_event_mask(client, 0);
+		}
+		self.pixmaps.retain(|_, p| p.get_owner() != client);
+		self.gcs.retain(|_, gc| gc.get_owner() != client);
+
+		let colormaps: Vec<_> = self
+			.colormaps
+			.values()
+			.filter(|c| c.get_owner() == Some(client))
+			.map(|c| c.get_id())
+			.collect();
+		for id in colormaps {
+			self.free_colormap(id);
+		}
+		for colormap in self.colormaps.values_mut() {
+			colormap.free_entries(client);
+		}
+	}
+
+	/// Returns the current server time in milliseconds.
+	///
+	/// The value wraps around after about 49.7 days.
+	pub fn get_timestamp(&self) -> u32 {
+		self.start_time.elapsed().as_millis() as u32
+	}
+
+	/// Queues the given event to be sent to the client with the given ID.
+	///
+	/// The event must be a structure of at most 32 bytes, in the protocol's format. The sequence
+	/// number is filled when the event is sent.
+	///
+	/// If the client is not connected, the event is discarded.
+	pub fn send_event<E>(&mut self, client: u32, event: &E) {
+		let mut data = [0; 32];
+		unsafe {
+			ptr::copy_nonoverlapping::<u8>(
+				event as *const _ as *const u8,
+				data.as_mut_ptr(),
+				size_of::<E>().min(data.len()),
+			);
+		}
+
+		if let Some(queue) = self.pending_events.get_mut(&client) {
+			queue.push(data);
+		}
+	}
+
+	/// Queues the given event to be sent to every connected client.
+	pub fn broadcast_event<E>(&mut self, event: &E) {
+		let clients: Vec<u32> = self.pending_events.keys().cloned().collect();
+
+		for c in clients {
+			self.send_event(c, event);
+		}
+	}
+
+	/// Queues the given event for every client that selected at least one event of `mask` on the
+	/// window with the given ID.
+	pub fn deliver_event<E>(&mut self, wid: NonZeroU32, mask: u32, event: &E) {
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+		let clients: Vec<u32> = win.get_listeners(mask).collect();
+
+		for c in clients {
+			self.send_event(c, event);
+		}
+	}
+
+	/// Queues the given event on a window for clients that selected `StructureNotify`, and on its
+	/// parent for clients that selected `SubstructureNotify`.
+	///
+	/// `event` builds the event from the ID of the window it is reported on.
+	pub fn deliver_structure_event<E, F: Fn(u32) -> E>(&mut self, wid: NonZeroU32, event: F) {
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+		let parent = win.get_parent();
+
+		self.deliver_event(
+			wid,
+			protocol::Event::StructureNotify as _,
+			&event(wid.get()),
+		);
+		if let Some(parent) = parent {
+			self.deliver_event(
+				parent,
+				protocol::Event::SubstructureNotify as _,
+				&event(parent.get()),
+			);
+		}
+	}
+
+	/// Updates the map state of the given window and of its inferiors.
+	///
+	/// Mapped windows are viewable if all their ancestors are mapped, and unviewable otherwise.
+	pub fn update_map_state(&mut self, wid: NonZeroU32) {
+		let Some(win) = self.get_window(wid) else {
+			return;
+		};
+		let parent_viewable = win
+			.get_parent()
+			.and_then(|p| self.get_window(p))
+			.is_none_or(|p| p.is_viewable());
+
+		let mut stack = vec![(wid, parent_viewable)];
+		while let Some((wid, parent_viewable)) = stack.pop() {
+			let Some(win) = self.get_window_mut(wid) else {
+				continue;
+			};
+			if win.attributes.map_state != MapState::Unmapped {
+				win.attributes.map_state = if parent_viewable {
+					MapState::Viewable
+				} else {
+					MapState::Unviewable
+				};
+			}
+
+			let viewable = win.is_viewable();
+			stack.extend(win.get_children().iter().map(|c| (*c, viewable)));
+		}
+	}
+
+	/// Maps the window with the given ID and sends `MapNotify` events.
+	///
+	/// If the window is already mapped, the function does nothing.
+	///
+	/// Parts of windows that become visible are not exposed by this function.
+	pub fn map_window(&mut self, wid: NonZeroU32) {
+		let Some(win) = self.get_window_mut(wid) else {
+			return;
+		};
+		if win.attributes.map_state != MapState::Unmapped {
+			return;
+		}
+		// TODO Send `MapRequest` instead if a client selected `SubstructureRedirect` on the parent
+		win.attributes.map_state = MapState::Unviewable;
+		let override_redirect = win.attributes.override_redirect;
+		self.update_map_state(wid);
+
+		self.deliver_structure_event(wid, |event| MapNotifyEvent {
+			code: Event::MapNotify as _,
+			_padding0: 0,
+			seq_nbr: 0,
+
+			event,
+			window: wid.get(),
+			override_redirect: override_redirect as _,
+
+			_padding1: [0; 19],
+		});
+	}
+
+	/// Unmaps the window with the given ID and sends `UnmapNotify` events.
+	///
+	/// `from_configure` tells whether the window is unmapped because its parent was resized.
+	///
+	/// If the window is already unmapped or is root, the function does nothing.
+	///
+	/// Parts of windows that become visible are not exposed by this function.
+	pub fn unmap_window(&mut self, wid: NonZeroU32, from_configure: bool) {
+		let Some(win) = self.get_window_mut(wid) else {
+			return;
+		};
+		if win.is_root() || win.attributes.map_state == MapState::Unmapped {
+			return;
+		}
+		win.attributes.map_state = MapState::Unmapped;
+		self.update_map_state(wid);
+
+		self.deliver_structure_event(wid, |event| UnmapNotifyEvent {
+			code: Event::UnmapNotify as _,
+			_padding0: 0,
+			seq_nbr: 0,
+
+			event,
+			window: wid.get(),
+			from_configure: from_configure as _,
+
+			_padding1: [0; 19],
+		});
+	}
+
+	/// Moves the window with the given ID to the top of the children of `parent`, at the given
+	/// position relative to it, and sends `ReparentNotify` events.
+	///
+	/// A mapped window is unmapped first and mapped again afterward.
+	///
+	/// Both windows must exist and be on the same screen, `wid` must not be a root window and
+	/// `parent` must not be one of its inferiors.
+	///
+	/// Parts of windows that become visible are not exposed by this function.
+	pub fn reparent_window(&mut self, wid: NonZeroU32, parent: NonZeroU32, x: i16, y: i16) {
+		let win = self.get_window(wid).unwrap();
+		let mapped = win.attributes.map_state != MapState::Unmapped;
+		let prev_parent = win.get_parent().unwrap();
+		if mapped {
+			self.unmap_window(wid, false);
+		}
+
+		Window::set_parent(self, wid, parent);
+		let win = self.get_window_mut(wid).unwrap();
+		let mut rect = win.get_rectangle();
+		rect.x = x;
+		rect.y = y;
+		win.set_rectangle(rect);
+		let override_redirect = win.attributes.override_redirect;
+		self.update_map_state(wid);
+
+		let event = |event: u32| ReparentNotifyEvent {
+			code: Event::ReparentNotify as _,
+			_padding0: 0,
+			seq_nbr: 0,
+
+			event,
+			window: wid.get(),
+			parent: parent.get(),
+			x,
+			y,
+			override_redirect: override_redirect as _,
+
+			_padding1: [0; 11],
+		};
+		// The previous parent is notified too
+		self.deliver_event(
+			prev_parent,
+			protocol::Event::SubstructureNotify as _,
+			&event(prev_parent.get()),
+		);
+		self.deliver_structure_event(wid, event);
+
+		if mapped {
+			self.map_window(wid);
+		}
+	}
+
+	/// Takes the events waiting to be sent to the client with the given ID.
+	pub fn take_events(&mut self, client: u32) -> Vec<[u8; 32]> {
+		self.pending_events
+			.get_mut(&client)
+			.map(mem::take)
+			.unwrap_or_default()
+	}
+
+	/// Returns an immutable reference to the pointer.
+	pub fn get_pointer(&self) -> &Pointer {
+		&self.pointer
+	}
+
+	/// Returns a mutable reference to the pointer.
+	pub fn get_pointer_mut(&mut self) -> &mut Pointer {
+		&mut self.pointer
+	}
+
+	/// Returns an immutable reference to the pointer acceleration.
+	pub fn get_acceleration(&self) -> &Acceleration {
+		&self.acceleration
+	}
+
+	/// Returns a mutable reference to the pointer acceleration.
+	pub fn get_acceleration_mut(&mut self) -> &mut Acceleration {
+		&mut self.acceleration
+	}
+
+	/// Returns an immutable reference to the keyboard.
+	pub fn get_keyboard(&self) -> &Keyboard {
+		&self.keyboard
+	}
+
+	/// Returns a mutable reference to the keyboard.
+	pub fn get_keyboard_mut(&mut self) -> &mut Keyboard {
+		&mut self.keyboard
+	}
+
+	/// Returns an immutable reference to the state of keyboard accessibility features.
+	pub fn get_accessx(&self) -> &AccessX {
+		&self.accessx
+	}
+
+	/// Returns a mutable reference to the state of keyboard accessibility features.
+	pub fn get_accessx_mut(&mut self) -> &mut AccessX {
+		&mut self.accessx
+	}
+
+	/// Sets the number of entries kept in the pointer's motion history.
+	pub fn set_motion_buffer_size(&mut self, size: usize) {
+		self.pointer.set_history_size(size);
+	}
+
 	/// Returns an immutable reference to the list of custom requests.
 	pub fn get_custom_requests(&self) -> &HashMap<u8, Box<RequestReadFn>> {
 		&self.custom_requests
@@ -342,20 +830,13 @@ impl<'a> Context<'a> {
 	}
 
 	/// Renders to the screen.
+	///
+	/// Windows are drawn onto the contents of their screen as requests are handled. Only screens
+	/// whose contents changed are displayed again.
 	pub fn render(&mut self) {
-		// TODO Avoid rendering the whole screen: only render parts that changed
-
-		// Render recursively starting from root windows
-		for s in &self.screens {
-			let root_id = s.get_root_window_id();
-
-			if let Some(root) = self.get_window(root_id) {
-				root.render_full(self, s);
-			}
-		}
-
+		// TODO Avoid copying the whole screen: only copy parts that changed
 		for s in &mut self.screens {
-			s.swap_buffers();
+			s.present();
 		}
 	}
 }
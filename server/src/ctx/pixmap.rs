@@ -0,0 +1,103 @@
+//! A pixmap is an offscreen drawable.
+//!
+//! Pixmaps are shared through reference counting: a pixmap used as the tile of a graphics
+//! context or as the background of a window survives `FreePixmap` until it is not used anymore.
+
+use super::image::Image;
+use super::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::Rectangle;
+use std::cell::Ref;
+use std::cell::RefCell;
+use std::cell::RefMut;
+use std::num::NonZeroU32;
+
+/// An offscreen drawable.
+#[derive(Debug)]
+pub struct Pixmap {
+	/// The ID of the pixmap.
+	id: NonZeroU32,
+	/// The ID of the client that created the pixmap.
+	owner: u32,
+	/// The ID of the root window of the screen the pixmap belongs to.
+	root: NonZeroU32,
+
+	/// The contents of the pixmap.
+	image: RefCell<Image>,
+}
+
+impl Pixmap {
+	/// Creates a pixmap with undefined contents.
+	///
+	/// Arguments:
+	/// - `id` is the ID of the pixmap.
+	/// - `owner` is the ID of the client creating the pixmap.
+	/// - `root` is the ID of the root window of the screen the pixmap belongs to.
+	/// - `depth` is the depth of the pixmap.
+	/// - `width` and `height` are the dimensions of the pixmap.
+	///
+	/// If the depth is not supported, the function returns `Value`. If the contents cannot be
+	/// allocated, the function returns `Alloc`.
+	pub fn new(
+		id: NonZeroU32,
+		owner: u32,
+		root: NonZeroU32,
+		depth: u8,
+		width: u16,
+		height: u16,
+	) -> Result<Self, Error> {
+		Ok(Self {
+			id,
+			owner,
+			root,
+
+			image: RefCell::new(Image::new(depth, width, height)?),
+		})
+	}
+
+	/// Returns the ID of the pixmap.
+	pub fn get_id(&self) -> NonZeroU32 {
+		self.id
+	}
+
+	/// Returns the ID of the client that created the pixmap.
+	pub fn get_owner(&self) -> u32 {
+		self.owner
+	}
+
+	/// Returns the contents of the pixmap.
+	pub fn get_image(&self) -> Ref<'_, Image> {
+		self.image.borrow()
+	}
+
+	/// Returns the contents of the pixmap, mutably.
+	pub fn get_image_mut(&self) -> RefMut<'_, Image> {
+		self.image.borrow_mut()
+	}
+}
+
+impl Drawable for Pixmap {
+	fn get_depth(&self) -> u8 {
+		self.image.borrow().get_depth()
+	}
+
+	fn get_root(&self) -> u32 {
+		self.root.get()
+	}
+
+	fn get_rectangle(&self) -> Rectangle {
+		let (width, height) = self.image.borrow().get_size();
+
+		Rectangle {
+			x: 0,
+			y: 0,
+
+			width,
+			height,
+		}
+	}
+
+	fn get_border_width(&self) -> u16 {
+		0
+	}
+}
@@ -1,16 +1,223 @@
-//! TODO doc
+//! The pointer is controlled by the user through input devices and moves across screens.
+
+use crate::protocol::ButMask;
+use std::collections::VecDeque;
+use std::num::NonZeroU32;
+
+/// The number of physical buttons of the pointer.
+pub const BUTTONS_COUNT: usize = 5;
+
+/// The default number of entries in the motion history.
+pub const DEFAULT_MOTION_BUFFER_SIZE: usize = 256;
+
+/// An entry of the motion history.
+#[derive(Clone)]
+pub struct TimeCoord {
+	/// The time at which the pointer reached the position in milliseconds.
+	pub time: u32,
+	/// The index of the screen the pointer was located on.
+	pub screen: usize,
+	/// The X position of the pointer relative to the screen's top-left corner.
+	pub x: i16,
+	/// The Y position of the pointer relative to the screen's top-left corner.
+	pub y: i16,
+}
+
+/// A bounded history of the positions of the pointer, from oldest to newest.
+pub struct MotionHistory {
+	/// The maximum number of entries.
+	size: usize,
+	/// The entries.
+	entries: VecDeque<TimeCoord>,
+}
+
+impl MotionHistory {
+	/// Creates a new instance with the given maximum number of entries.
+	pub fn new(size: usize) -> Self {
+		Self {
+			size,
+			entries: VecDeque::with_capacity(size),
+		}
+	}
+
+	/// Returns the maximum number of entries.
+	pub fn get_size(&self) -> usize {
+		self.size
+	}
+
+	/// Records a new entry, discarding the oldest one if the history is full.
+	pub fn push(&mut self, entry: TimeCoord) {
+		if self.size == 0 {
+			return;
+		}
+		if self.entries.len() >= self.size {
+			self.entries.pop_front();
+		}
+
+		self.entries.push_back(entry);
+	}
+
+	/// Returns an iterator over the entries recorded between the times `start` and `stop`
+	/// (inclusive).
+	pub fn range(&self, start: u32, stop: u32) -> impl Iterator<Item = &TimeCoord> {
+		self.entries
+			.iter()
+			.filter(move |e| (start..=stop).contains(&e.time))
+	}
+}
+
+impl Default for MotionHistory {
+	fn default() -> Self {
+		Self::new(DEFAULT_MOTION_BUFFER_SIZE)
+	}
+}
 
 /// A pointer displayed on a screen.
-#[derive(Default)]
 pub struct Pointer {
-	/// The ID of the screen the pointer is located on.
-	screen: u32,
+	/// The index of the screen the pointer is located on.
+	screen: usize,
 
 	/// The X position of the pointer relative to the screen's top-left corner.
 	x: i16,
 	/// The Y position of the pointer relative to the screen's top-left corner.
 	y: i16,
 
+	/// The mask of logical buttons currently pressed.
+	buttons: u16,
+	/// The logical button of each physical button. Zero means the button is disabled.
+	mapping: [u8; BUTTONS_COUNT],
+
+	/// The ID of the deepest viewable window containing the pointer.
+	window: Option<NonZeroU32>,
+
 	/// The ID of the cursor associated with the pointer.
 	cursor: u32,
+
+	/// The history of the pointer's positions.
+	history: MotionHistory,
+}
+
+impl Pointer {
+	/// Returns the index of the screen the pointer is located on.
+	pub fn get_screen(&self) -> usize {
+		self.screen
+	}
+
+	/// Returns the position of the pointer relative to its screen's top-left corner.
+	pub fn get_position(&self) -> (i16, i16) {
+		(self.x, self.y)
+	}
+
+	/// Sets the screen and the position of the pointer on it.
+	///
+	/// `time` is the current time in milliseconds, recorded in the motion history.
+	pub fn set_position(&mut self, screen: usize, x: i16, y: i16, time: u32) {
+		self.screen = screen;
+		self.x = x;
+		self.y = y;
+
+		self.history.push(TimeCoord {
+			time,
+			screen,
+			x,
+			y,
+		});
+	}
+
+	/// Returns an immutable reference to the motion history.
+	pub fn get_history(&self) -> &MotionHistory {
+		&self.history
+	}
+
+	/// Sets the maximum number of entries of the motion history, clearing it.
+	pub fn set_history_size(&mut self, size: usize) {
+		self.history = MotionHistory::new(size);
+	}
+
+	/// Returns the mask of logical buttons currently pressed.
+	pub fn get_buttons(&self) -> u16 {
+		self.buttons
+	}
+
+	/// Returns the logical button of each physical button.
+	pub fn get_mapping(&self) -> &[u8] {
+		&self.mapping
+	}
+
+	/// Returns the logical button associated with the physical button with the given number.
+	///
+	/// If the button is disabled or doesn't exist, the function returns None.
+	pub fn map_button(&self, button: u8) -> Option<u8> {
+		let logical = *self.mapping.get((button as usize).checked_sub(1)?)?;
+		(logical != 0).then_some(logical)
+	}
+
+	/// Sets the logical button of each physical button.
+	///
+	/// If a physical button whose mapping changes is pressed, the mapping is not changed and
+	/// the function returns `false`.
+	pub fn set_mapping(&mut self, mapping: [u8; BUTTONS_COUNT]) -> bool {
+		let busy = self
+			.mapping
+			.iter()
+			.zip(mapping.iter())
+			.filter(|(old, new)| old != new)
+			.filter_map(|(old, _)| ButMask::from_button(*old))
+			.any(|mask| self.buttons & mask as u16 != 0);
+		if busy {
+			return false;
+		}
+
+		self.mapping = mapping;
+		true
+	}
+
+	/// Sets whether the buttons in `mask` are pressed.
+	pub fn set_buttons(&mut self, mask: u16, pressed: bool) {
+		if pressed {
+			self.buttons |= mask;
+		} else {
+			self.buttons &= !mask;
+		}
+	}
+
+	/// Returns the ID of the deepest viewable window containing the pointer.
+	pub fn get_window(&self) -> Option<NonZeroU32> {
+		self.window
+	}
+
+	/// Sets the ID of the deepest viewable window containing the pointer.
+	pub fn set_window(&mut self, window: Option<NonZeroU32>) {
+		self.window = window;
+	}
+
+	/// Returns the ID of the cursor associated with the pointer.
+	pub fn get_cursor(&self) -> u32 {
+		self.cursor
+	}
+}
+
+impl Default for Pointer {
+	fn default() -> Self {
+		let mut mapping = [0; BUTTONS_COUNT];
+		for (i, b) in mapping.iter_mut().enumerate() {
+			*b = i as u8 + 1;
+		}
+
+		Self {
+			screen: 0,
+
+			x: 0,
+			y: 0,
+
+			buttons: 0,
+			mapping,
+
+			window: None,
+
+			cursor: 0,
+
+			history: MotionHistory::default(),
+		}
+	}
 }
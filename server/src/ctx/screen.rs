@@ -2,16 +2,34 @@
 //!
 //! Since a desktop can be split on several screens, each screens has its own virtual position to
 //! determine on which screen the pointer must appears when hitting a corner.
+//!
+//! Windows are drawn onto the contents of their screen, an image of the root depth. When
+//! rendering, the contents are copied to the current framebuffer, which is then displayed.
 
+use super::colormap;
+use super::colormap::Colormap;
+use super::image;
+use super::image::Image;
 use crate::output::card::DRICard;
 use crate::output::connector::DRIConnector;
 use crate::output::connector::DRMModeModeinfo;
 use crate::output::framebuffer::Framebuffer;
 use crate::protocol;
+use std::cell::Cell;
+use std::cell::Ref;
+use std::cell::RefCell;
+use std::cell::RefMut;
 use std::mem::size_of;
 use std::num::NonZeroU32;
 use std::ptr;
 
+/// The depth of root windows.
+pub const ROOT_DEPTH: u8 = 24;
+/// The class of root visuals.
+pub const ROOT_VISUAL_CLASS: protocol::VisualClass = protocol::VisualClass::DirectColor;
+/// The number of entries of colormaps for root visuals.
+pub const ROOT_VISUAL_ENTRIES: u16 = 1 << 8;
+
 /// Structure representing a screen.
 pub struct Screen<'a> {
 	/// A reference to the card device.
@@ -30,6 +48,11 @@ pub struct Screen<'a> {
 	/// The index of the current framebuffer.
 	curr_fb: usize,
 
+	/// The contents of the screen.
+	contents: RefCell<Image>,
+	/// Tells whether the contents changed since they were last displayed.
+	damaged: Cell<bool>,
+
 	/// The absolute virtual X position of the screen.
 	x: u32,
 	/// The absolute virtual Y position of the screen.
@@ -37,6 +60,12 @@ pub struct Screen<'a> {
 
 	/// The ID of the root window of the screen.
 	root_win_id: NonZeroU32,
+	/// The ID of the visual of the root window.
+	root_visual: u32,
+	/// The ID of the default colormap of the screen.
+	default_colormap: NonZeroU32,
+	/// The IDs of the colormaps installed on the screen, the least recently installed first.
+	installed_colormaps: Vec<NonZeroU32>,
 }
 
 impl<'a> Screen<'a> {
@@ -49,6 +78,7 @@ impl<'a> Screen<'a> {
 	/// - `y` is the absolute virtual Y position of the screen.
 	/// - `mode` is the current mode of the screen.
 	/// - `root_win_id` is the ID of the root window of the screen.
+	/// - `default_colormap` is the default colormap of the screen, for the root window's visual.
 	pub fn new(
 		dev: &'a DRICard,
 		conn: DRIConnector,
@@ -56,6 +86,7 @@ impl<'a> Screen<'a> {
 		x: u32,
 		y: u32,
 		root_win_id: NonZeroU32,
+		default_colormap: &Colormap,
 	) -> Self {
 		// TODO Handle error
 		let crtc = conn.get_crtc(dev).unwrap().crtc_id;
@@ -68,6 +99,8 @@ impl<'a> Screen<'a> {
 		fbs[0].map().unwrap();
 		fbs[1].map().unwrap();
 
+		let contents = Image::new(ROOT_DEPTH, mode.hdisplay, mode.vdisplay).unwrap();
+
 		Self {
 			dev,
 
@@ -78,10 +111,16 @@ impl<'a> Screen<'a> {
 			fbs,
 			curr_fb: 0,
 
+			contents: RefCell::new(contents),
+			damaged: Cell::new(true),
+
 			x,
 			y,
 
 			root_win_id,
+			root_visual: default_colormap.get_visual(),
+			default_colormap: default_colormap.get_id(),
+			installed_colormaps: Vec::new(),
 		}
 	}
 
@@ -107,6 +146,21 @@ impl<'a> Screen<'a> {
 		(self.mode.hdisplay, self.mode.vdisplay)
 	}
 
+	/// Returns the absolute virtual position of the screen.
+	pub fn get_position(&self) -> (u32, u32) {
+		(self.x, self.y)
+	}
+
+	/// Tells whether the screen contains the given absolute virtual position.
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		let x_begin = self.x as i32;
+		let y_begin = self.y as i32;
+		let x_end = x_begin + self.mode.hdisplay as i32;
+		let y_end = y_begin + self.mode.vdisplay as i32;
+
+		(x_begin..x_end).contains(&x) && (y_begin..y_end).contains(&y)
+	}
+
 	/// Tells whether two screens are adjacents.
 	///
 	/// This function is commutative.
@@ -120,19 +174,45 @@ impl<'a> Screen<'a> {
 		x_adj && y_adj
 	}
 
+	/// Returns the list of depths allowed for windows and pixmaps on the screen.
+	pub fn get_allowed_depths(&self) -> Vec<u8> {
+		image::FORMATS.iter().map(|(depth, _)| *depth).collect()
+	}
+
 	/// Returns the ID of the screen's root window.
 	pub fn get_root_window_id(&self) -> NonZeroU32 {
 		self.root_win_id
 	}
 
+	/// Returns the ID of the visual of the screen's root window.
+	pub fn get_root_visual(&self) -> u32 {
+		self.root_visual
+	}
+
+	/// Returns the ID of the screen's default colormap.
+	pub fn get_default_colormap(&self) -> NonZeroU32 {
+		self.default_colormap
+	}
+
+	/// Returns the IDs of the colormaps installed on the screen, the least recently installed
+	/// first.
+	pub fn get_installed_colormaps(&self) -> &[NonZeroU32] {
+		&self.installed_colormaps
+	}
+
+	/// Returns the IDs of the colormaps installed on the screen, mutably.
+	pub fn get_installed_colormaps_mut(&mut self) -> &mut Vec<NonZeroU32> {
+		&mut self.installed_colormaps
+	}
+
 	/// Returns the protocol representation of the screen.
 	pub fn to_protocol_screen(&self) -> Vec<u8> {
 		// TODO Fill according to screen informations
 		let visual = protocol::Visual {
-			visual_id: 0, // TODO
-			class: protocol::VisualClass::DirectColor,
+			visual_id: self.root_visual,
+			class: ROOT_VISUAL_CLASS,
 			bits_per_rgb_value: 24,
-			colormap_entries: 1 << 8, // TODO
+			colormap_entries: ROOT_VISUAL_ENTRIES,
 
 			red_mask: 0xff0000,
 			green_mask: 0x00ff00,
@@ -140,18 +220,10 @@ impl<'a> Screen<'a> {
 
 			_padding: 0,
 		};
-		let depth = protocol::Depth {
-			depth: 24,
-
-			_padding0: 0,
-
-			visuals_len: 1,
-
-			_padding1: 0,
-		};
+		let depths = self.get_allowed_depths();
 		let screen = protocol::Screen {
 			root: self.root_win_id.get(),
-			default_colormap: 0, // TODO
+			default_colormap: self.default_colormap.get(),
 			white_pixel: 0xffffff,
 			black_pixel: 0x000000,
 			current_input_masks: 0, // TODO
@@ -161,19 +233,20 @@ impl<'a> Screen<'a> {
 			millimeters_width: self.conn.mm_width as _,
 			millimeters_height: self.conn.mm_height as _,
 
-			min_installed_maps: 1, // TODO
-			max_installed_maps: 1, // TODO
+			min_installed_maps: colormap::MIN_INSTALLED_MAPS as _,
+			max_installed_maps: colormap::MAX_INSTALLED_MAPS as _,
 
-			root_visual: 0,    // TODO
-			backing_stores: 0, // TODO
-			save_unders: 0,    // TODO
-			root_depth: 24,    // TODO
+			root_visual: self.root_visual,
+			backing_stores: protocol::BackingStore::Always as _,
+			save_unders: 1,
+			root_depth: ROOT_DEPTH,
 
-			allowed_depths_len: 1, // TODO
+			allowed_depths_len: depths.len() as _,
 		};
 
+		// Only the root depth has a visual
 		let len = size_of::<protocol::Screen>()
-			+ size_of::<protocol::Depth>()
+			+ depths.len() * size_of::<protocol::Depth>()
 			+ size_of::<protocol::Visual>();
 		let mut data = vec![0; len];
 
@@ -186,18 +259,33 @@ impl<'a> Screen<'a> {
 			);
 			off += size_of::<protocol::Screen>();
 
-			ptr::copy_nonoverlapping::<u8>(
-				&depth as *const _ as *const u8,
-				&mut data[off],
-				size_of::<protocol::Depth>(),
-			);
-			off += size_of::<protocol::Depth>();
-
-			ptr::copy_nonoverlapping::<u8>(
-				&visual as *const _ as *const u8,
-				&mut data[off],
-				size_of::<protocol::Visual>(),
-			);
+			for d in depths {
+				let visuals_len = (d == ROOT_DEPTH) as u16;
+				let depth = protocol::Depth {
+					depth: d,
+
+					_padding0: 0,
+
+					visuals_len,
+
+					_padding1: 0,
+				};
+				ptr::copy_nonoverlapping::<u8>(
+					&depth as *const _ as *const u8,
+					&mut data[off],
+					size_of::<protocol::Depth>(),
+				);
+				off += size_of::<protocol::Depth>();
+
+				if visuals_len > 0 {
+					ptr::copy_nonoverlapping::<u8>(
+						&visual as *const _ as *const u8,
+						&mut data[off],
+						size_of::<protocol::Visual>(),
+					);
+					off += size_of::<protocol::Visual>();
+				}
+			}
 		}
 
 		data
@@ -208,6 +296,69 @@ impl<'a> Screen<'a> {
 		&self.fbs[self.curr_fb]
 	}
 
+	/// Returns the contents of the screen.
+	pub fn get_contents(&self) -> Ref<'_, Image> {
+		self.contents.borrow()
+	}
+
+	/// Returns the contents of the screen, mutably. The screen is then displayed again on the
+	/// next render.
+	pub fn get_contents_mut(&self) -> RefMut<'_, Image> {
+		self.damaged.set(true);
+		self.contents.borrow_mut()
+	}
+
+	/// Reads the given rectangle of the contents of the screen into an image of the given depth.
+	///
+	/// The position is relative to the screen. Pixels outside of the screen are set to zero.
+	///
+	/// If the depth is not supported, the function returns None.
+	pub fn read_image(&self, x: i32, y: i32, width: u16, height: u16, depth: u8) -> Option<Image> {
+		let mut img = Image::new(depth, width, height).ok()?;
+		let contents = self.get_contents();
+		for dy in 0..height as i32 {
+			for dx in 0..width as i32 {
+				img.set_pixel(dx, dy, contents.get_pixel(x + dx, y + dy));
+			}
+		}
+
+		Some(img)
+	}
+
+	/// If the contents of the screen changed, copies them to the current framebuffer and
+	/// displays it.
+	pub fn present(&mut self) {
+		if !self.damaged.replace(false) {
+			return;
+		}
+
+		let fb = self.get_curr_fb();
+		if let Some(ptr) = fb.get_buffer_ptr() {
+			let contents = self.contents.borrow();
+			let data = contents.get_data();
+			let (width, height) = contents.get_size();
+			let stride = contents.get_stride();
+			let pitch = fb.get_pitch();
+			// Pixels of the root depth are stored on 32 bits, like on the framebuffer. Rows are
+			// copied one by one since the framebuffer may pad them
+			let row_len = (width as usize * size_of::<u32>()).min(pitch);
+			let rows =
+				(height as usize).min(fb.get_buffer_len() * size_of::<u32>() / pitch.max(1));
+			let dst = ptr.as_ptr() as *mut u8;
+			for y in 0..rows {
+				unsafe {
+					ptr::copy_nonoverlapping(
+						data.as_ptr().add(y * stride),
+						dst.add(y * pitch),
+						row_len,
+					);
+				}
+			}
+		}
+
+		self.swap_buffers();
+	}
+
 	/// Swap frame buffers, thus displaying the next frame to the screen.
 	pub fn swap_buffers(&mut self) {
 		let fb = &self.fbs[self.curr_fb];
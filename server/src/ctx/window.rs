@@ -1,19 +1,23 @@
 //! TODO doc
 
+use super::image::Image;
+use super::pixmap::Pixmap;
 use super::Drawable;
 use crate::ctx::Context;
-use crate::ctx::Screen;
 use crate::protocol::BackingStore;
 use crate::protocol::BitGravity;
 use crate::protocol::Class;
 use crate::protocol::MapState;
 use crate::protocol::Rectangle;
+use crate::protocol::VisibilityState;
 use crate::protocol::WinGravity;
-use std::cmp::max;
-use std::cmp::min;
+use crate::region::Bounds;
+use crate::region::Region;
+use std::cell::RefCell;
+use std::cell::RefMut;
 use std::collections::HashMap;
-use std::collections::HashSet;
 use std::num::NonZeroU32;
+use std::rc::Rc;
 
 /// A property associated to a window.
 #[derive(Debug)]
@@ -74,16 +78,62 @@ impl Property {
 	}
 }
 
+/// The background pixmap of a window.
+#[derive(Clone, Debug, Default)]
+pub enum BackgroundPixmap {
+	/// The window has no background pixmap.
+	#[default]
+	None,
+	/// The background of the parent is used.
+	ParentRelative,
+	/// The window's background is tiled with the given pixmap.
+	Pixmap(Rc<Pixmap>),
+}
+
+/// The way the background or border of a window is filled.
+enum Fill {
+	/// A single pixel.
+	Pixel(u32),
+	/// A pixmap tiled from the given origin, in absolute coordinates.
+	Tile(Rc<Pixmap>, (i32, i32)),
+}
+
+impl Fill {
+	/// Returns the pixel at the given absolute position.
+	fn get_pixel(&self, x: i32, y: i32) -> u32 {
+		match self {
+			Self::Pixel(pixel) => *pixel,
+			Self::Tile(pixmap, (origin_x, origin_y)) => {
+				let image = pixmap.get_image();
+				let (width, height) = image.get_size();
+				image.get_pixel(
+					(x - origin_x).rem_euclid(width as _),
+					(y - origin_y).rem_euclid(height as _),
+				)
+			}
+		}
+	}
+}
+
+/// The offscreen copy of the contents of a window, kept with backing store.
+#[derive(Debug)]
+pub struct Backing {
+	/// The pixels of the inside of the window.
+	image: Image,
+	/// The region of the image holding the window's contents, relative to the window's origin.
+	valid: Region,
+}
+
 /// Structure storing a window's attributes.
 #[derive(Debug)]
 pub struct WindowAttributes {
-	/// TODO doc
-	pub background_pixmap: u32,
-	/// TODO doc
-	pub background_pixel: u32,
-	/// TODO doc
-	pub border_pixmap: u32,
-	/// TODO doc
+	/// The background pixmap of the window.
+	pub background_pixmap: BackgroundPixmap,
+	/// The pixel the background is filled with. If set, it has priority over `background_pixmap`.
+	pub background_pixel: Option<u32>,
+	/// The pixmap the border is tiled with. If None, `border_pixel` is used.
+	pub border_pixmap: Option<Rc<Pixmap>>,
+	/// The pixel the border is filled with, if there is no border pixmap.
 	pub border_pixel: u32,
 	/// TODO doc
 	pub bit_gravity: BitGravity,
@@ -124,9 +174,9 @@ impl Default for WindowAttributes {
 	fn default() -> Self {
 		// TODO Set correct values
 		Self {
-			background_pixmap: 0,
-			background_pixel: 0,
-			border_pixmap: 0,
+			background_pixmap: BackgroundPixmap::None,
+			background_pixel: None,
+			border_pixmap: None,
 			border_pixel: 0,
 			bit_gravity: BitGravity::Forget,
 			win_gravity: WinGravity::Unmap,
@@ -157,8 +207,11 @@ pub struct Window {
 
 	/// The ID of the parent window.
 	parent: Option<NonZeroU32>,
-	/// The list of indexes of the childrens of the current window.
-	children: HashSet<NonZeroU32>,
+	/// The ID of the root window this window belongs to.
+	root: NonZeroU32,
+	/// The list of IDs of the children of the current window, in stacking order (bottom-most
+	/// first).
+	children: Vec<NonZeroU32>,
 
 	/// The depth of the pixmap.
 	depth: u8,
@@ -170,38 +223,60 @@ pub struct Window {
 	/// The list of properties of the window. The key is the name of the property.
 	properties: HashMap<String, Property>,
 
+	/// The events selected on the window by each client. The key is the ID of the client.
+	event_masks: HashMap<u32, u32>,
+
 	/// The window's attributes.
 	pub attributes: WindowAttributes,
+	/// The offscreen copy of the window's contents, if it has backing store.
+	backing: RefCell<Option<Backing>>,
+	/// The last visibility state reported for the window. If None, the window is not viewable.
+	visibility: Option<VisibilityState>,
 }
 
 impl Window {
-	/// Creates a new root window.
+	/// Creates a new window.
 	///
 	/// Arguments:
 	/// - `ctx` is the context on which the window will be added.
+	/// - `id` is the ID of the window.
 	/// - `parent` is the ID of the parent window. If None, the window is a root window.
 	/// - `rect` represents the position and dimensions of the window relative to its parent.
 	///
-	/// The function allocates an ID for the window and adds it to the given context.
+	/// The function adds the window to the given context, on top of its siblings.
 	///
 	/// If the window is root, the X/Y position is zero-ed.
+	///
+	/// The parent window must exist.
 	pub fn new<'c>(
 		ctx: &'c mut Context,
+		id: NonZeroU32,
 		parent: Option<NonZeroU32>,
 		mut rect: Rectangle,
 	) -> &'c mut Self {
-		let id = NonZeroU32::new(1).unwrap(); // TODO Allocate? Take as param?
+		let root = match parent {
+			Some(parent_id) => {
+				// TODO Return error if None instead of unwrap
+				let parent = ctx.get_window_mut(parent_id).unwrap();
+				parent.children.push(id);
 
-		if parent.is_none() {
-			rect.x = 0;
-			rect.y = 0;
-		}
+				parent.root
+			}
+
+			None => {
+				rect.x = 0;
+				rect.y = 0;
+
+				id
+			}
+		};
 
 		let win = Self {
 			id,
 
-			parent: None,
-			children: HashSet::new(),
+			parent,
+			root,
+			children: Vec::new(),
 
 			depth: 24, // TODO
 			rect,
@@ -209,15 +284,13 @@ impl Window {
 
 			properties: HashMap::new(),
 
+			event_masks: HashMap::new(),
+
 			attributes: WindowAttributes::default(),
+			backing: RefCell::new(None),
+			visibility: None,
 		};
 
-		if let Some(parent_id) = win.parent {
-			// TODO Return error if None instead of unwrap
-			let parent = ctx.get_window_mut(parent_id).unwrap();
-			parent.children.insert(id);
-		}
-
 		// Insert window in context
 		ctx.windows.insert(id, win);
 
@@ -239,11 +312,16 @@ impl Window {
 		self.parent.is_none()
 	}
 
-	/// The list indexes of the children of the window.
-	pub fn get_children(&self) -> &HashSet<NonZeroU32> {
+	/// The list of IDs of the children of the window, bottom-most first.
+	pub fn get_children(&self) -> &[NonZeroU32] {
 		&self.children
 	}
 
+	/// Tells whether the window is mapped and all its ancestors are mapped.
+	pub fn is_viewable(&self) -> bool {
+		self.attributes.map_state == MapState::Viewable
+	}
+
 	/// Returns the depth of the window.
 	pub fn set_depth(&mut self, depth: u8) {
 		self.depth = depth;
@@ -291,61 +369,515 @@ impl Window {
 		self.attributes = attr;
 	}
 
+	/// Sets the mask of events selected by the client with the given ID.
+	///
+	/// If the mask is zero, the client is removed from the list of listeners.
+	pub fn set_event_mask(&mut self, client: u32, mask: u32) {
+		if mask != 0 {
+			self.event_masks.insert(client, mask);
+		} else {
+			self.event_masks.remove(&client);
+		}
+	}
+
+	/// Returns the mask of events selected by the client with the given ID.
+	pub fn get_event_mask(&self, client: u32) -> u32 {
+		self.event_masks.get(&client).cloned().unwrap_or(0)
+	}
+
+	/// Returns the union of the masks of events selected by all clients.
+	pub fn get_all_event_masks(&self) -> u32 {
+		self.event_masks.values().fold(0, |a, b| a | b)
+	}
+
+	/// Returns an iterator over the IDs of the clients that selected at least one event in `mask`.
+	pub fn get_listeners(&self, mask: u32) -> impl Iterator<Item = u32> + '_ {
+		self.event_masks
+			.iter()
+			.filter(move |(_, m)| *m & mask != 0)
+			.map(|(c, _)| *c)
+	}
+
+	/// Returns the absolute position of the window's origin, inside of its border, relative to
+	/// the root window.
+	///
+	/// `ctx` is the current context.
+	pub fn get_absolute_position(&self, ctx: &Context) -> (i32, i32) {
+		let x = self.rect.x as i32 + self.border_width as i32;
+		let y = self.rect.y as i32 + self.border_width as i32;
+
+		match self.parent.and_then(|p| ctx.get_window(p)) {
+			Some(parent) => {
+				let (px, py) = parent.get_absolute_position(ctx);
+				(px + x, py + y)
+			}
+
+			None => (x, y),
+		}
+	}
+
+	/// Tells whether the given position, relative to the window's parent, is inside of the
+	/// window, border included.
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		let bw = self.border_width as i32 * 2;
+		let x_begin = self.rect.x as i32;
+		let y_begin = self.rect.y as i32;
+		let x_end = x_begin + self.rect.width as i32 + bw;
+		let y_end = y_begin + self.rect.height as i32 + bw;
+
+		(x_begin..x_end).contains(&x) && (y_begin..y_end).contains(&y)
+	}
+
+	/// Returns the ID of the top-most viewable child containing the given position, relative to
+	/// the window's origin.
+	///
+	/// If no child contains the position, the function returns None.
+	///
+	/// `ctx` is the current context.
+	pub fn child_at(&self, ctx: &Context, x: i32, y: i32) -> Option<NonZeroU32> {
+		self.children.iter().rev().cloned().find(|c| {
+			ctx.get_window(*c)
+				.map(|w| w.is_viewable() && w.contains(x, y))
+				.unwrap_or(false)
+		})
+	}
+
+	/// Tells whether the window is an inferior of the window with the given ID.
+	///
+	/// `ctx` is the current context.
+	pub fn is_inferior_of(&self, ctx: &Context, wid: NonZeroU32) -> bool {
+		let mut parent = self.parent;
+		while let Some(p) = parent {
+			if p == wid {
+				return true;
+			}
+
+			parent = ctx.get_window(p).and_then(|w| w.parent);
+		}
+
+		false
+	}
+
 	/// Tells whether the window can render anything on screen.
 	pub fn is_output(&self) -> bool {
 		matches!(self.attributes.class, Class::InputOutput)
 	}
 
-	/// Renders the window's background with a single color.
-	pub fn render_pixel_background(&self, screen: &Screen) {
-		let fb = screen.get_curr_fb();
+	/// Computes the visibility state of the window, ignoring its inferiors.
+	///
+	/// If the window is not viewable or cannot render anything, the function returns None.
+	///
+	/// `ctx` is the current context.
+	pub fn compute_visibility(&self, ctx: &Context) -> Option<VisibilityState> {
+		if !self.is_viewable() || !self.is_output() {
+			return None;
+		}
+
+		let region = self.get_visible_region(ctx, true);
+		let bounds = Bounds::new(0, 0, self.rect.width as _, self.rect.height as _);
+		let state = if region.is_empty() {
+			VisibilityState::FullyObscured
+		} else if region == Region::from_bounds(bounds) {
+			VisibilityState::Unobscured
+		} else {
+			VisibilityState::PartiallyObscured
+		};
+		Some(state)
+	}
+
+	/// Returns the last visibility state reported for the window.
+	pub fn get_visibility(&self) -> Option<VisibilityState> {
+		self.visibility
+	}
 
-		let (screen_width, screen_height) = screen.get_screen_size();
+	/// Sets the last visibility state reported for the window.
+	pub fn set_visibility(&mut self, visibility: Option<VisibilityState>) {
+		self.visibility = visibility;
+	}
 
-		let x = self.rect.x as isize;
-		let y = self.rect.y as isize;
-		let width = self.rect.width as isize;
-		let height = self.rect.height as isize;
+	/// Returns the bounds of the window, border included, in absolute coordinates.
+	///
+	/// `ctx` is the current context.
+	pub fn get_outer_bounds(&self, ctx: &Context) -> Bounds {
+		let (x, y) = self.get_absolute_position(ctx);
+		let bw = self.border_width as i32;
+		Bounds::new(
+			x - bw,
+			y - bw,
+			x + self.rect.width as i32 + bw,
+			y + self.rect.height as i32 + bw,
+		)
+	}
 
-		let x_begin = max(x, 0) as usize;
-		let y_begin = max(y, 0) as usize;
-		let x_end = min((x + width) as usize, screen_width as usize);
-		let y_end = min((y + height) as usize, screen_height as usize);
+	/// Returns the bounds of the inside of the window, in absolute coordinates.
+	///
+	/// `ctx` is the current context.
+	pub fn get_inner_bounds(&self, ctx: &Context) -> Bounds {
+		let (x, y) = self.get_absolute_position(ctx);
+		Bounds::new(
+			x,
+			y,
+			x + self.rect.width as i32,
+			y + self.rect.height as i32,
+		)
+	}
 
-		let ptr = fb.get_buffer_ptr().unwrap().as_ptr();
-		for y in y_begin..y_end {
-			for x in x_begin..x_end {
-				let i = y * width as usize + x;
+	/// Clips the given region, in absolute coordinates, to the parts of the screen the window
+	/// can show.
+	///
+	/// Arguments:
+	/// - `ctx` is the current context.
+	/// - `region` is the region to clip.
+	/// - `include_inferiors` tells whether mapped children are included, instead of obscuring.
+	///
+	/// The region is clipped by the window's ancestors and obscured by the siblings stacked above
+	/// the window and above its ancestors. If the window is not viewable, the region is empty.
+	fn clip_region(&self, ctx: &Context, mut region: Region, include_inferiors: bool) -> Region {
+		// Obscures the region with the given windows, if they are mapped and may draw
+		let obscure = |region: Region, windows: &[NonZeroU32]| {
+			windows
+				.iter()
+				.filter_map(|w| ctx.get_window(*w))
+				.filter(|w| w.is_viewable() && w.is_output())
+				.fold(region, |region, w| {
+					region.subtract(&Region::from_bounds(w.get_outer_bounds(ctx)))
+				})
+		};
 
-				// TODO use window's background color
-				unsafe {
-					*ptr.add(i) = 0xffffff;
-				}
+		let mut win = self;
+		loop {
+			if !win.is_viewable() {
+				return Region::new();
 			}
+			let Some(parent) = win.parent.and_then(|p| ctx.get_window(p)) else {
+				break;
+			};
+
+			region = region.intersect(&Region::from_bounds(parent.get_inner_bounds(ctx)));
+			let pos = parent.children.iter().position(|c| *c == win.id).unwrap();
+			region = obscure(region, &parent.children[(pos + 1)..]);
+
+			win = parent;
+		}
+		if !include_inferiors {
+			region = obscure(region, &self.children);
 		}
+
+		region
 	}
 
-	/// Renders the window's background.
-	pub fn render_background(&self, screen: &Screen) {
-		// TODO If a pixmap is specified, render it to background
-		// However, pixel has priority over pixmap
+	/// Returns the region of the window that is visible on screen, relative to the window's
+	/// origin.
+	///
+	/// Arguments:
+	/// - `ctx` is the current context.
+	/// - `include_inferiors` tells whether mapped children are included, instead of obscuring.
+	///
+	/// If the window is not viewable, the region is empty.
+	pub fn get_visible_region(&self, ctx: &Context, include_inferiors: bool) -> Region {
+		let region = Region::from_bounds(self.get_inner_bounds(ctx));
+		let mut region = self.clip_region(ctx, region, include_inferiors);
+
+		let (x, y) = self.get_absolute_position(ctx);
+		region.translate(-x, -y);
+		region
+	}
+
+	/// Returns the region of the window's border that is visible on screen, relative to the
+	/// window's origin.
+	///
+	/// `ctx` is the current context.
+	pub fn get_visible_border(&self, ctx: &Context) -> Region {
+		let outer = Region::from_bounds(self.get_outer_bounds(ctx));
+		let border = outer.subtract(&Region::from_bounds(self.get_inner_bounds(ctx)));
+		let mut region = self.clip_region(ctx, border, false);
+
+		let (x, y) = self.get_absolute_position(ctx);
+		region.translate(-x, -y);
+		region
+	}
+
+	/// Moves the window with ID `wid` to the top of the children of `parent`.
+	///
+	/// Both windows must exist and be on the same screen, and `wid` must not be a root window.
+	pub fn set_parent(ctx: &mut Context, wid: NonZeroU32, parent: NonZeroU32) {
+		let win = ctx.get_window_mut(wid).unwrap();
+		let Some(prev) = win.parent.replace(parent) else {
+			return;
+		};
+		ctx.get_window_mut(prev)
+			.unwrap()
+			.children
+			.retain(|c| *c != wid);
+		ctx.get_window_mut(parent).unwrap().children.push(wid);
+	}
+
+	/// Moves the given child in the stacking order.
+	///
+	/// Arguments:
+	/// - `child` is the ID of the child to move.
+	/// - `sibling` is the sibling to place the child next to. If None, the stack's end is used.
+	/// - `above` tells whether the child is placed above the sibling, or at the top of the stack.
+	///
+	/// If `child` or `sibling` is not a child of the window, the function does nothing.
+	pub fn restack_child(&mut self, child: NonZeroU32, sibling: Option<NonZeroU32>, above: bool) {
+		let Some(pos) = self.children.iter().position(|c| *c == child) else {
+			return;
+		};
+		self.children.remove(pos);
+
+		let pos = match sibling {
+			Some(sibling) => {
+				let Some(pos) = self.children.iter().position(|c| *c == sibling) else {
+					self.children.insert(pos, child);
+					return;
+				};
+				pos + above as usize
+			}
+			None if above => self.children.len(),
+			None => 0,
+		};
+		self.children.insert(pos, child);
+	}
 
-		self.render_pixel_background(screen);
+	/// Returns the origin of the tiles of the window's background and border, in absolute
+	/// coordinates.
+	///
+	/// With a `ParentRelative` background, the origin is the parent's.
+	///
+	/// `ctx` is the current context.
+	fn get_tile_origin(&self, ctx: &Context) -> (i32, i32) {
+		let parent_relative = self.attributes.background_pixel.is_none()
+			&& matches!(
+				self.attributes.background_pixmap,
+				BackgroundPixmap::ParentRelative
+			);
+		match self.parent.and_then(|p| ctx.get_window(p)) {
+			Some(parent) if parent_relative => parent.get_tile_origin(ctx),
+			_ => self.get_absolute_position(ctx),
+		}
+	}
+
+	/// Returns the fill of the window's background. If the window has no background, the
+	/// function returns None.
+	///
+	/// `ctx` is the current context.
+	fn get_background(&self, ctx: &Context) -> Option<Fill> {
+		match (
+			self.attributes.background_pixel,
+			&self.attributes.background_pixmap,
+		) {
+			(Some(pixel), _) => Some(Fill::Pixel(pixel)),
+			(None, BackgroundPixmap::None) => None,
+			(None, BackgroundPixmap::ParentRelative) => self
+				.parent
+				.and_then(|p| ctx.get_window(p))
+				.and_then(|p| p.get_background(ctx)),
+			(None, BackgroundPixmap::Pixmap(pixmap)) => {
+				Some(Fill::Tile(pixmap.clone(), self.get_tile_origin(ctx)))
+			}
+		}
 	}
 
-	/// Renders the full window, including children windows.
-	pub fn render_full(&self, ctx: &Context, screen: &Screen) {
-		if self.is_output() {
-			self.render_background(screen);
+	/// Fills the given region of the window, relative to its origin, on the screen's contents.
+	///
+	/// `ctx` is the current context.
+	fn paint(&self, ctx: &Context, region: &Region, fill: &Fill) {
+		let Some(screen) = ctx
+			.get_screen_for_root(self.root)
+			.map(|i| &ctx.get_screens()[i])
+		else {
+			return;
+		};
+
+		let (x, y) = self.get_absolute_position(ctx);
+		let mut contents = screen.get_contents_mut();
+		contents.fill_region(region, (x, y), |px, py| fill.get_pixel(x + px, y + py));
+	}
 
-			// TODO render content
+	/// Paints the background on the given region of the window, relative to its origin.
+	///
+	/// If the window has no background, the previous contents are left.
+	///
+	/// `ctx` is the current context.
+	pub fn paint_background(&self, ctx: &Context, region: &Region) {
+		let fill = self.get_background(ctx);
+		if let Some(fill) = &fill {
+			self.paint(ctx, region, fill);
 		}
 
-		for c in &self.children {
-			if let Some(child_win) = ctx.get_window(*c) {
-				child_win.render_full(ctx, screen);
+		// The offscreen copy is up to date, once the client redraws what it is notified of
+		let mut backing = self.backing.borrow_mut();
+		if let Some(backing) = backing.as_mut() {
+			if let Some(fill) = &fill {
+				let (x, y) = self.get_absolute_position(ctx);
+				backing
+					.image
+					.fill_region(region, (0, 0), |px, py| fill.get_pixel(x + px, y + py));
 			}
+			backing.valid = backing.valid.union(region);
+		}
+	}
+
+	/// Paints the border on the given region of the window, relative to its origin.
+	///
+	/// `ctx` is the current context.
+	pub fn paint_border(&self, ctx: &Context, region: &Region) {
+		let fill = match &self.attributes.border_pixmap {
+			Some(pixmap) => Fill::Tile(pixmap.clone(), self.get_tile_origin(ctx)),
+			None => Fill::Pixel(self.attributes.border_pixel),
+		};
+		self.paint(ctx, region, &fill);
+	}
+
+	/// Tells whether the window's contents must be kept offscreen, according to its backing store
+	/// attribute and its map state.
+	fn needs_backing(&self) -> bool {
+		if !self.is_output() {
+			return false;
+		}
+		match self.attributes.backing_store {
+			BackingStore::NotUseful => false,
+			BackingStore::WhenMapped => self.is_viewable(),
+			BackingStore::Always => true,
+		}
+	}
+
+	/// Creates or frees the offscreen copy of the window's contents, according to its backing
+	/// store attribute and its map state. If the window was resized, the copy is created again.
+	///
+	/// Arguments:
+	/// - `ctx` is the current context.
+	/// - `kept` is the region of the screen holding the window's contents, relative to its origin.
+	///
+	/// When the copy is created, `kept` is copied in it.
+	///
+	/// If the copy cannot be allocated, the window is left without copy and the function returns
+	/// false.
+	pub fn update_backing(&self, ctx: &Context, kept: &Region) -> bool {
+		let mut backing = self.backing.borrow_mut();
+		if !self.needs_backing() {
+			*backing = None;
+			return true;
+		}
+		let size = (self.rect.width, self.rect.height);
+		if backing.as_ref().is_some_and(|b| b.image.get_size() == size) {
+			return true;
+		}
+
+		let Ok(mut image) = Image::new(self.depth, size.0, size.1) else {
+			*backing = None;
+			return false;
+		};
+		let bounds = Bounds::new(0, 0, size.0 as _, size.1 as _);
+		let valid = kept.intersect(&Region::from_bounds(bounds));
+		if let Some(screen) = ctx
+			.get_screen_for_root(self.root)
+			.map(|i| &ctx.get_screens()[i])
+		{
+			let (x, y) = self.get_absolute_position(ctx);
+			let contents = screen.get_contents();
+			image.fill_region(&valid, (0, 0), |px, py| contents.get_pixel(x + px, y + py));
+		}
+
+		*backing = Some(Backing {
+			image,
+			valid,
+		});
+		true
+	}
+
+	/// Returns the offscreen copy of the window's contents. If the window has no backing store,
+	/// the function returns None.
+	pub fn get_backing_image_mut(&self) -> Option<RefMut<'_, Image>> {
+		RefMut::filter_map(self.backing.borrow_mut(), |b| {
+			b.as_mut().map(|b| &mut b.image)
+		})
+		.ok()
+	}
+
+	/// Returns the region of the offscreen copy of the window's contents that drawing affects,
+	/// relative to the window's origin.
+	///
+	/// Arguments:
+	/// - `ctx` is the current context.
+	/// - `include_inferiors` tells whether mapped children are included, instead of obscuring.
+	pub fn get_backing_region(&self, ctx: &Context, include_inferiors: bool) -> Region {
+		let bounds = Bounds::new(0, 0, self.rect.width as _, self.rect.height as _);
+		let region = Region::from_bounds(bounds);
+		if include_inferiors {
+			return region;
 		}
+
+		self.children
+			.iter()
+			.filter_map(|c| ctx.get_window(*c))
+			.filter(|c| c.is_viewable() && c.is_output())
+			.fold(region, |region, c| {
+				let bw = c.border_width as i32 * 2;
+				let (x, y) = (c.rect.x as i32, c.rect.y as i32);
+				let outer = Bounds::new(
+					x,
+					y,
+					x + c.rect.width as i32 + bw,
+					y + c.rect.height as i32 + bw,
+				);
+				region.subtract(&Region::from_bounds(outer))
+			})
+	}
+
+	/// Copies the given region of the offscreen copy of the window's contents to the screen.
+	///
+	/// Arguments:
+	/// - `ctx` is the current context.
+	/// - `region` is the region to restore, relative to the window's origin.
+	///
+	/// The function returns the part of the region that could not be restored.
+	pub fn restore_backing(&self, ctx: &Context, region: &Region) -> Region {
+		let backing = self.backing.borrow();
+		let Some(backing) = backing.as_ref() else {
+			return region.clone();
+		};
+		let Some(screen) = ctx
+			.get_screen_for_root(self.root)
+			.map(|i| &ctx.get_screens()[i])
+		else {
+			return region.clone();
+		};
+
+		let restored = region.intersect(&backing.valid);
+		let (x, y) = self.get_absolute_position(ctx);
+		let mut contents = screen.get_contents_mut();
+		contents.fill_region(&restored, (x, y), |px, py| backing.image.get_pixel(px, py));
+
+		region.subtract(&backing.valid)
+	}
+
+	/// Copies the pixels of the window that are kept offscreen but hidden on screen to the given
+	/// image.
+	///
+	/// Arguments:
+	/// - `image` is the image to copy to.
+	/// - `x` and `y` are the position of the image, relative to the window's origin.
+	/// - `visible` is the region of the window visible on screen, relative to its origin.
+	///
+	/// The function returns the region of the image the pixels were copied to.
+	pub fn read_backing(&self, image: &mut Image, x: i32, y: i32, visible: &Region) -> Region {
+		let backing = self.backing.borrow();
+		let Some(backing) = backing.as_ref() else {
+			return Region::new();
+		};
+
+		let (width, height) = image.get_size();
+		let bounds = Bounds::new(x, y, x + width as i32, y + height as i32);
+		let mut region = backing
+			.valid
+			.subtract(visible)
+			.intersect(&Region::from_bounds(bounds));
+		image.fill_region(&region, (-x, -y), |px, py| backing.image.get_pixel(px, py));
+
+		region.translate(-x, -y);
+		region
 	}
 }
 
@@ -355,8 +887,7 @@ impl Drawable for Window {
 	}
 
 	fn get_root(&self) -> u32 {
-		// TODO
-		0
+		self.root.get()
 	}
 
 	fn get_rectangle(&self) -> Rectangle {
@@ -0,0 +1,317 @@
+//! Rasterization of arcs of ellipses.
+//!
+//! An arc is part of the ellipse inscribed in its bounding rectangle. Angles are given in 64ths
+//! of a degree, counter-clockwise from 3 o'clock, and extents are clamped to a full turn.
+//!
+//! Arcs are approximated with paths that are dense enough to be undistinguishable from the curve
+//! once rasterized. Outlines are then drawn like lines and filled arcs like polygons.
+
+use super::line;
+use super::polygon::FPoint;
+use super::polygon::Shape;
+use super::polygon::ShapeHint;
+use super::Canvas;
+use super::Source;
+use crate::ctx::gc::ArcMode;
+use crate::ctx::gc::FillRule;
+use crate::protocol::Arc;
+use std::f64::consts::PI;
+
+/// Converts an angle in 64ths of a degree to radians.
+fn to_radians(angle: i16) -> f64 {
+	angle as f64 / 64. * PI / 180.
+}
+
+/// Returns the extent of the arc in radians, clamped to a full turn.
+fn get_extent(arc: &Arc) -> f64 {
+	to_radians(arc.angle2).clamp(-2. * PI, 2. * PI)
+}
+
+/// Returns the center and radii of the ellipse of the arc.
+fn get_ellipse(arc: &Arc) -> (FPoint, FPoint) {
+	let rx = arc.width as f64 / 2.;
+	let ry = arc.height as f64 / 2.;
+	((arc.x as f64 + rx, arc.y as f64 + ry), (rx, ry))
+}
+
+/// Returns the point of the ellipse of the arc at the given angle in radians.
+fn point_at(arc: &Arc, angle: f64) -> FPoint {
+	let ((cx, cy), (rx, ry)) = get_ellipse(arc);
+	// The Y axis goes downwards
+	(cx + rx * angle.cos(), cy - ry * angle.sin())
+}
+
+/// Returns a list of points along the arc, from its start to its end.
+///
+/// If the arc is a full ellipse, the last point is equal to the first.
+fn sample(arc: &Arc) -> Vec<FPoint> {
+	let start = to_radians(arc.angle1);
+	let extent = get_extent(arc);
+	let (_, (rx, ry)) = get_ellipse(arc);
+
+	// About one point every pixel along the curve
+	let count = (rx.max(ry) * extent.abs()).ceil().clamp(4., 4096.) as usize;
+	let mut points: Vec<FPoint> = (0..=count)
+		.map(|i| point_at(arc, start + extent * i as f64 / count as f64))
+		.collect();
+	if extent.abs() >= 2. * PI {
+		points[count] = points[0];
+	}
+
+	points
+}
+
+/// Tells whether two points are close enough to be considered equal.
+fn coincide(a: FPoint, b: FPoint) -> bool {
+	(a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6
+}
+
+/// Draws the outlines of the given arcs.
+///
+/// When the end of an arc coincides with the start of the next, both are drawn as a single path,
+/// joined with the join style of the graphics context.
+pub fn poly_arc(canvas: &mut Canvas, arcs: &[Arc]) {
+	// Gathering arcs into paths, with whether the path is smooth at each point
+	let mut paths: Vec<(Vec<FPoint>, Vec<bool>)> = vec![];
+	for arc in arcs {
+		let points = sample(arc);
+		let joined = paths
+			.last()
+			.and_then(|(p, _)| p.last())
+			.is_some_and(|last| coincide(*last, points[0]));
+		if !joined {
+			paths.push((vec![], vec![]));
+		}
+
+		// The ends of arcs are not smooth: they are either joined with the join style, or capped
+		let (path, smooth) = paths.last_mut().unwrap();
+		let begin = path.len();
+		let points = if joined { &points[1..] } else { &points[..] };
+		path.extend_from_slice(points);
+		smooth.resize(path.len(), true);
+		if !joined {
+			smooth[begin] = false;
+		}
+		let end = smooth.len() - 1;
+		smooth[end] = false;
+	}
+	// Paths ending where they start are closed
+	for (path, _) in &mut paths {
+		let first = path[0];
+		let last = path.last_mut().unwrap();
+		if coincide(first, *last) {
+			*last = first;
+		}
+	}
+
+	let thin = canvas.get_gc().line_width == 0;
+	for (path, smooth) in paths {
+		if thin {
+			let mut points: Vec<(i32, i32)> = path
+				.iter()
+				.map(|(x, y)| (x.round() as i32, y.round() as i32))
+				.collect();
+			points.dedup();
+			if points.len() == 1 {
+				points.push(points[0]);
+			}
+			line::thin_path(canvas, &points);
+		} else {
+			line::wide_path(canvas, &path, &smooth);
+		}
+	}
+}
+
+/// Fills the given arcs, closing each according to the arc mode of the graphics context.
+pub fn poly_fill_arc(canvas: &mut Canvas, arcs: &[Arc]) {
+	let gc = canvas.get_gc();
+	for arc in arcs {
+		let mut points = sample(arc);
+		if gc.arc_mode == ArcMode::PieSlice && get_extent(arc).abs() < 2. * PI {
+			let (center, _) = get_ellipse(arc);
+			points.push(center);
+		}
+
+		// Each arc is filled on its own
+		let mut shape = Shape::default();
+		shape.add(points, false);
+		shape.fill(canvas, FillRule::Winding, ShapeHint::Complex, Source::Fill);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ctx::gc::CapStyle;
+	use crate::ctx::gc::Function;
+	use crate::ctx::gc::JoinStyle;
+	use crate::drawing::testing::render;
+
+	/// Returns the arc inscribed in the given rectangle, with angles in degrees.
+	fn arc(x: i16, y: i16, width: u16, height: u16, angle1: i16, angle2: i16) -> Arc {
+		Arc {
+			x,
+			y,
+			width,
+			height,
+			angle1: angle1 * 64,
+			angle2: angle2 * 64,
+		}
+	}
+
+	#[test]
+	fn thin_outlines() {
+		let circle = render(9, 9, |_| {}, |c| poly_arc(c, &[arc(0, 0, 8, 8, 0, 360)]));
+		assert_eq!(
+			circle,
+			[
+				"...###...", //
+				".##...##.", //
+				"#.......#", //
+				"#.......#", //
+				"#.......#", //
+				"#.......#", //
+				"#.......#", //
+				".##...##.", //
+				"...###...", //
+			]
+		);
+		let ellipse = render(13, 7, |_| {}, |c| poly_arc(c, &[arc(0, 0, 12, 6, 0, 360)]));
+		assert_eq!(
+			ellipse,
+			[
+				"...#######...", //
+				".##.......##.", //
+				"##.........##", //
+				"#...........#", //
+				"##.........##", //
+				".##.......##.", //
+				"...#######...", //
+			]
+		);
+	}
+
+	#[test]
+	fn wide_caps() {
+		// The ends are not aligned with the axes, so that no pixel lies on the border of a cap
+		let draw = |cap_style| {
+			render(
+				11,
+				5,
+				|gc| {
+					gc.line_width = 3;
+					gc.cap_style = cap_style;
+				},
+				|c| poly_arc(c, &[arc(1, 1, 8, 8, 40, 100)]),
+			)
+		};
+		assert_eq!(
+			draw(CapStyle::Butt),
+			[
+				"...#####...", //
+				"..#######..", //
+				"..#######..", //
+				"...#...#...", //
+				"...........", //
+			]
+		);
+		assert_eq!(
+			draw(CapStyle::Projecting),
+			[
+				"...#####...", //
+				"..#######..", //
+				".#########.", //
+				".###...###.", //
+				"..#.....#..", //
+			]
+		);
+		assert_eq!(
+			draw(CapStyle::Round),
+			[
+				"...#####...", //
+				"..#######..", //
+				".#########.", //
+				".###...###.", //
+				"...........", //
+			]
+		);
+	}
+
+	#[test]
+	fn wide_joined() {
+		// The second arc starts where the first ends, turning by a right angle. Drawn with `Xor`,
+		// the joint must not be painted twice
+		let draw = |join_style| {
+			render(
+				14,
+				8,
+				|gc| {
+					gc.line_width = 3;
+					gc.join_style = join_style;
+					gc.function = Function::Xor;
+				},
+				|c| poly_arc(c, &[arc(8, 1, 8, 8, 120, 60), arc(4, 5, 8, 8, 90, -60)]),
+			)
+		};
+		assert_eq!(
+			draw(JoinStyle::Miter),
+			[
+				"..............", //
+				".........#....", //
+				"........###...", //
+				".......####...", //
+				".......####...", //
+				".......#####..", //
+				".......######.", //
+				"..........##..", //
+			]
+		);
+		assert_eq!(
+			draw(JoinStyle::Bevel),
+			[
+				"..............", //
+				".........#....", //
+				"........###...", //
+				".......####...", //
+				".......####...", //
+				".......#####..", //
+				"........#####.", //
+				"..........##..", //
+			]
+		);
+	}
+
+	#[test]
+	fn fill_modes() {
+		let draw = |arc_mode| {
+			render(
+				11,
+				6,
+				|gc| gc.arc_mode = arc_mode,
+				|c| poly_fill_arc(c, &[arc(0, 0, 10, 10, 0, 90)]),
+			)
+		};
+		assert_eq!(
+			draw(ArcMode::Chord),
+			[
+				"...........", //
+				"......##...", //
+				".......##..", //
+				"........##.", //
+				".........#.", //
+				"...........", //
+			]
+		);
+		assert_eq!(
+			draw(ArcMode::PieSlice),
+			[
+				"...........", //
+				".....###...", //
+				".....####..", //
+				".....#####.", //
+				".....#####.", //
+				"...........", //
+			]
+		);
+	}
+}
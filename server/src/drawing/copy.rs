@@ -0,0 +1,237 @@
+//! Copies of areas between drawables.
+//!
+//! The source area is read entirely before anything is drawn, so that copies between overlapping
+//! areas of the same drawable are correct. Parts of the source that cannot be read, because they
+//! are outside of the drawable or obscured without backing store, are not copied and reported to
+//! the client with `GraphicsExposure` events.
+
+use super::Canvas;
+use crate::ctx::gc::SubWindowMode;
+use crate::ctx::image;
+use crate::ctx::image::Image;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::event::Event;
+use crate::protocol::event::GraphicsExposureEvent;
+use crate::protocol::event::NoExposureEvent;
+use crate::protocol::Rectangle;
+use crate::region::Bounds;
+use crate::region::Region;
+use std::num::NonZeroU32;
+
+/// An area read from a drawable.
+pub struct Area {
+	/// The pixels of the area.
+	pub image: Image,
+	/// The region of the area that could be read, relative to the area.
+	pub readable: Region,
+}
+
+/// Reads the given rectangle of a drawable.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `id` is the ID of the drawable.
+/// - `rect` is the rectangle to read, relative to the drawable's origin.
+/// - `subwindow_mode` tells whether the contents of mapped children of a window are read.
+///
+/// The image has the size of `rect`, which should thus be clipped to the drawable beforehand.
+///
+/// If the drawable does not exist or if the image cannot be allocated, the function returns None.
+pub fn read_area(
+	ctx: &Context,
+	id: NonZeroU32,
+	rect: &Rectangle,
+	subwindow_mode: SubWindowMode,
+) -> Option<Area> {
+	let (x, y) = (rect.x as i32, rect.y as i32);
+	let area = Region::from_bounds(Bounds::new(0, 0, rect.width as _, rect.height as _));
+
+	if let Some(pixmap) = ctx.get_pixmap(id) {
+		let image = pixmap.get_image();
+		let (width, height) = image.get_size();
+		let bounds = Bounds::new(-x, -y, width as i32 - x, height as i32 - y);
+
+		return Some(Area {
+			image: image.sub_image(x, y, rect.width, rect.height).ok()?,
+			readable: area.intersect(&Region::from_bounds(bounds)),
+		});
+	}
+
+	let win = ctx.get_window(id)?;
+	let (win_x, win_y) = win.get_absolute_position(ctx);
+	let root = NonZeroU32::new(win.get_root()).unwrap();
+	let image = ctx.get_screen_for_root(root).and_then(|i| {
+		ctx.get_screens()[i].read_image(
+			win_x + x,
+			win_y + y,
+			rect.width,
+			rect.height,
+			win.get_depth(),
+		)
+	});
+	let mut image = match image {
+		Some(image) => image,
+		None => Image::new(win.get_depth(), rect.width, rect.height).ok()?,
+	};
+
+	// Only the visible parts of the window can be read, unless contents are kept offscreen
+	let include_inferiors = subwindow_mode == SubWindowMode::IncludeInferiors;
+	let visible = win.get_visible_region(ctx, include_inferiors);
+	let hidden = win.read_backing(&mut image, x, y, &visible);
+	let mut readable = visible;
+	readable.translate(-x, -y);
+	let readable = readable.union(&hidden);
+
+	Some(Area {
+		image,
+		readable: area.intersect(&readable),
+	})
+}
+
+/// Copies an area from a drawable to another.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `src` and `dst` are the IDs of the source and destination drawables.
+/// - `gc` is the ID of the graphics context.
+/// - `rect` is the rectangle to copy, relative to the source's origin.
+/// - `dst_x` and `dst_y` are the position of the copy on the destination.
+/// - `bit_plane` is the bit plane to copy, drawn like a bitmap. If None, pixels are copied.
+///
+/// If the graphics context has `graphics_exposures` set, the function returns the areas of the
+/// destination that could not be copied.
+pub fn copy(
+	ctx: &Context,
+	src: u32,
+	dst: u32,
+	gc: u32,
+	rect: &Rectangle,
+	(dst_x, dst_y): (i16, i16),
+	bit_plane: Option<u32>,
+) -> Result<Option<Vec<Rectangle>>, Error> {
+	let src_id = NonZeroU32::new(src).ok_or(Error::Drawable(src))?;
+	let src_drawable = ctx.get_drawable(src_id).ok_or(Error::Drawable(src))?;
+	let dst_drawable = NonZeroU32::new(dst)
+		.and_then(|id| ctx.get_drawable(id))
+		.ok_or(Error::Drawable(dst))?;
+	let gc_id = gc;
+	let gc = NonZeroU32::new(gc)
+		.and_then(|id| ctx.get_gc(id))
+		.ok_or(Error::GContext(gc))?;
+
+	if src_drawable.get_root() != dst_drawable.get_root() {
+		return Err(Error::Match);
+	}
+	let src_depth = src_drawable.get_depth();
+	match bit_plane {
+		None if src_depth != dst_drawable.get_depth() => return Err(Error::Match),
+		Some(plane)
+			if plane.count_ones() != 1 || plane & !image::get_pixel_mask(src_depth) != 0 =>
+		{
+			return Err(Error::Value(plane))
+		}
+		_ => {}
+	}
+
+	// The copy in destination coordinates. Only the part inside of both drawables is read, so
+	// that the allocated image is never larger than them
+	let (dx, dy) = (dst_x as i32 - rect.x as i32, dst_y as i32 - rect.y as i32);
+	let dst_rect = dst_drawable.get_rectangle();
+	let dst_bounds = Bounds::new(0, 0, dst_rect.width as _, dst_rect.height as _);
+	let src_rect = src_drawable.get_rectangle();
+	let src_bounds = Bounds::new(0, 0, src_rect.width as _, src_rect.height as _);
+	let Some(area) = Bounds::from(rect).translate(dx, dy).intersect(&dst_bounds) else {
+		return Ok(gc.graphics_exposures.then(Vec::new));
+	};
+
+	let mut copied = Region::new();
+	if let Some(bounds) = area.intersect(&src_bounds.translate(dx, dy)) {
+		let src_rect = bounds.translate(-dx, -dy).to_rectangle();
+		let Area {
+			mut image,
+			readable,
+		} = read_area(ctx, src_id, &src_rect, gc.subwindow_mode).ok_or(Error::Alloc)?;
+		if let Some(plane) = bit_plane {
+			let (width, height) = image.get_size();
+			let mut bitmap = Image::new(1, width, height)?;
+			for y in 0..height as i32 {
+				for x in 0..width as i32 {
+					bitmap.set_pixel(x, y, (image.get_pixel(x, y) & plane != 0) as u32);
+				}
+			}
+			image = bitmap;
+		}
+
+		let (x, y) = (bounds.x1, bounds.y1);
+		super::draw(ctx, dst, gc_id, |canvas: &mut Canvas| {
+			canvas.put_image(&image, Some(&readable), x, y, bit_plane.is_some())
+		})?;
+		copied = readable;
+		copied.translate(x, y);
+	}
+
+	if !gc.graphics_exposures {
+		return Ok(None);
+	}
+	let exposed = Region::from_bounds(area).subtract(&copied);
+	Ok(Some(exposed.to_rectangles()))
+}
+
+/// Sends the exposure events of a copy to the client that performed it.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `client` is the ID of the client.
+/// - `drawable` is the destination of the copy.
+/// - `exposed` is the list of areas of the destination that could not be copied.
+/// - `opcode` is the major opcode of the request.
+///
+/// If every area could be copied, a `NoExposure` event is sent instead.
+pub fn send_exposures(
+	ctx: &mut Context,
+	client: u32,
+	drawable: u32,
+	exposed: &[Rectangle],
+	opcode: u8,
+) {
+	if exposed.is_empty() {
+		ctx.send_event(
+			client,
+			&NoExposureEvent {
+				code: Event::NoExposure as _,
+				_padding0: 0,
+				seq_nbr: 0,
+
+				drawable,
+				minor_opcode: 0,
+				major_opcode: opcode,
+
+				_padding1: [0; 21],
+			},
+		);
+	}
+
+	for (i, r) in exposed.iter().enumerate() {
+		ctx.send_event(
+			client,
+			&GraphicsExposureEvent {
+				code: Event::GraphicsExposure as _,
+				_padding0: 0,
+				seq_nbr: 0,
+
+				drawable,
+				x: r.x as _,
+				y: r.y as _,
+				width: r.width,
+				height: r.height,
+				minor_opcode: 0,
+				count: (exposed.len() - i - 1) as _,
+				major_opcode: opcode,
+
+				_padding1: [0; 11],
+			},
+		);
+	}
+}
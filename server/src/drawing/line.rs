@@ -0,0 +1,765 @@
+//! Rasterization of lines.
+//!
+//! Lines with a width of zero, called thin lines, are drawn with Bresenham's algorithm. The set
+//! of pixels of a thin line only depends on its endpoints, so that it is not affected by
+//! translation or clipping.
+//!
+//! Wide lines are converted to polygons, along with their caps and joins, which are then filled
+//! together so that no pixel is drawn twice.
+
+use super::polygon::FPoint;
+use super::polygon::Shape;
+use super::polygon::ShapeHint;
+use super::Canvas;
+use super::Source;
+use crate::ctx::gc::CapStyle;
+use crate::ctx::gc::FillRule;
+use crate::ctx::gc::JoinStyle;
+use crate::ctx::gc::LineStyle;
+use crate::ctx::gc::GC;
+use crate::protocol::Rectangle;
+use std::f64::consts::PI;
+
+/// A line, given by its two endpoints.
+pub type Segment = ((i32, i32), (i32, i32));
+
+/// Below this angle between two lines, miter joins are drawn as bevel joins.
+const MITER_LIMIT: f64 = 11. * PI / 180.;
+
+/// The position along the dash pattern of a graphics context.
+struct Dashes {
+	/// The lengths of dashes. The list always has an even number of elements.
+	lengths: Vec<f64>,
+	/// The index of the current dash.
+	index: usize,
+	/// The remaining length of the current dash.
+	remaining: f64,
+}
+
+impl Dashes {
+	/// Creates the dash pattern of the given graphics context, starting at its dash offset.
+	fn new(gc: &GC) -> Self {
+		let mut lengths: Vec<f64> = gc.dashes.iter().map(|d| *d as f64).collect();
+		// An odd list is repeated so that dashes alternate between even and odd
+		if !lengths.len().is_multiple_of(2) {
+			lengths.extend_from_within(..);
+		}
+
+		let mut dashes = Self {
+			remaining: lengths[0],
+			lengths,
+			index: 0,
+		};
+		let total: f64 = dashes.lengths.iter().sum();
+		dashes.advance(gc.dash_offset as f64 % total);
+
+		dashes
+	}
+
+	/// Tells whether the current dash is even.
+	fn is_even(&self) -> bool {
+		self.index.is_multiple_of(2)
+	}
+
+	/// Moves forward along the pattern by the given length.
+	fn advance(&mut self, len: f64) {
+		self.remaining -= len;
+		while self.remaining <= 1e-9 {
+			self.index = (self.index + 1) % self.lengths.len();
+			self.remaining += self.lengths[self.index];
+		}
+	}
+
+	/// Splits a line of the given length along the pattern, moving forward.
+	///
+	/// The function returns the list of pieces, with their start and end positions along the
+	/// line and whether they belong to an even dash.
+	fn split(&mut self, len: f64) -> Vec<(f64, f64, bool)> {
+		let mut pieces = vec![];
+
+		let mut pos = 0.;
+		while len - pos > 1e-9 {
+			// The end of the line is kept exact so that callers can recognize it
+			let (step, end) = if self.remaining >= len - pos {
+				(len - pos, len)
+			} else {
+				(self.remaining, pos + self.remaining)
+			};
+			pieces.push((pos, end, self.is_even()));
+			pos = end;
+			self.advance(step);
+		}
+
+		pieces
+	}
+}
+
+/// Returns the source of the pixels of the dash of the given parity, if drawn.
+///
+/// `gc` is the graphics context.
+fn dash_source(gc: &GC, even: bool) -> Option<Source> {
+	match (gc.line_style, even) {
+		(LineStyle::Solid, _) | (_, true) => Some(Source::Fill),
+		(LineStyle::DoubleDash, false) => Some(Source::OddDash),
+		(LineStyle::OnOffDash, false) => None,
+	}
+}
+
+/// Draws a thin line from `p1` to `p2`.
+///
+/// Arguments:
+/// - `canvas` is the canvas to draw on.
+/// - `include_last` tells whether the pixel at `p2` is drawn.
+/// - `dashes` is the dash pattern, if the line is dashed. Each pixel moves it forward by one.
+fn thin_segment(
+	canvas: &mut Canvas,
+	p1: (i32, i32),
+	p2: (i32, i32),
+	include_last: bool,
+	dashes: &mut Option<Dashes>,
+) {
+	let (mut x, mut y) = (p1.0 as i64, p1.1 as i64);
+	let dx = (p2.0 as i64 - x).abs();
+	let dy = -(p2.1 as i64 - y).abs();
+	let sx = if p2.0 as i64 >= x { 1 } else { -1 };
+	let sy = if p2.1 as i64 >= y { 1 } else { -1 };
+
+	let mut count = dx.max(-dy) + include_last as i64;
+	let mut err = dx + dy;
+	while count > 0 {
+		let even = dashes.as_ref().is_none_or(Dashes::is_even);
+		if let Some(source) = dash_source(canvas.get_gc(), even) {
+			canvas.put_pixel(x as i32, y as i32, source);
+		}
+		if let Some(dashes) = dashes {
+			dashes.advance(1.);
+		}
+
+		let e2 = 2 * err;
+		if e2 >= dy {
+			err += dy;
+			x += sx;
+		}
+		if e2 <= dx {
+			err += dx;
+			y += sy;
+		}
+		count -= 1;
+	}
+}
+
+/// Draws a thin path going through the given points.
+pub fn thin_path(canvas: &mut Canvas, points: &[(i32, i32)]) {
+	let gc = canvas.get_gc();
+	let mut dashes = (gc.line_style != LineStyle::Solid).then(|| Dashes::new(gc));
+	let closed = points.len() > 2 && points.first() == points.last();
+	let draw_last = !closed && gc.cap_style != CapStyle::NotLast;
+
+	// The last pixel of each segment is the first of the next, so it is drawn only once
+	for w in points.windows(2) {
+		thin_segment(canvas, w[0], w[1], false, &mut dashes);
+	}
+	if let (true, Some(last)) = (draw_last, points.last()) {
+		thin_segment(canvas, *last, *last, true, &mut dashes);
+	}
+}
+
+/// Returns the distance between the given points.
+fn distance(a: FPoint, b: FPoint) -> f64 {
+	(b.0 - a.0).hypot(b.1 - a.1)
+}
+
+/// Returns the unit vector going from `a` to `b`. If the points are equal, the function returns
+/// None.
+fn direction(a: FPoint, b: FPoint) -> Option<FPoint> {
+	let len = distance(a, b);
+	(len > 0.).then(|| ((b.0 - a.0) / len, (b.1 - a.1) / len))
+}
+
+/// Returns a polygon approximating the circle with the given center and radius.
+fn circle(center: FPoint, radius: f64) -> Vec<FPoint> {
+	let count = ((radius * 4.) as usize).clamp(8, 256);
+	(0..count)
+		.map(|i| {
+			let angle = i as f64 * 2. * PI / count as f64;
+			(
+				center.0 + radius * angle.cos(),
+				center.1 + radius * angle.sin(),
+			)
+		})
+		.collect()
+}
+
+/// Adds to the shape a wide line from `a` to `b`.
+///
+/// Arguments:
+/// - `shape` is the shape to add the line to.
+/// - `half` is half of the width of the line.
+/// - `start_cap` and `end_cap` are the caps drawn at each end.
+fn wide_segment(
+	shape: &mut Shape,
+	a: FPoint,
+	b: FPoint,
+	half: f64,
+	start_cap: CapStyle,
+	end_cap: CapStyle,
+) {
+	let Some(u) = direction(a, b) else {
+		// A line of length zero is only visible through its caps
+		match start_cap {
+			CapStyle::Round => shape.add(circle(a, half), false),
+			CapStyle::Projecting => shape.add(
+				vec![
+					(a.0 - half, a.1 - half),
+					(a.0 + half, a.1 - half),
+					(a.0 + half, a.1 + half),
+					(a.0 - half, a.1 + half),
+				],
+				false,
+			),
+			_ => {}
+		}
+		return;
+	};
+	let n = (-u.1 * half, u.0 * half);
+
+	let a = match start_cap {
+		CapStyle::Projecting => (a.0 - u.0 * half, a.1 - u.1 * half),
+		CapStyle::Round => {
+			shape.add(circle(a, half), false);
+			a
+		}
+		_ => a,
+	};
+	let b = match end_cap {
+		CapStyle::Projecting => (b.0 + u.0 * half, b.1 + u.1 * half),
+		CapStyle::Round => {
+			shape.add(circle(b, half), false);
+			b
+		}
+		_ => b,
+	};
+
+	shape.add(
+		vec![
+			(a.0 + n.0, a.1 + n.1),
+			(b.0 + n.0, b.1 + n.1),
+			(b.0 - n.0, b.1 - n.1),
+			(a.0 - n.0, a.1 - n.1),
+		],
+		false,
+	);
+}
+
+/// Adds to the shape the join at `p1` between the line from `p0` to `p1` and the line from `p1`
+/// to `p2`.
+///
+/// Arguments:
+/// - `shape` is the shape to add the join to.
+/// - `half` is half of the width of the lines.
+/// - `style` is the style of the join.
+fn join(shape: &mut Shape, p0: FPoint, p1: FPoint, p2: FPoint, half: f64, style: JoinStyle) {
+	let (Some(u1), Some(u2)) = (direction(p0, p1), direction(p1, p2)) else {
+		return;
+	};
+	let cross = u1.0 * u2.1 - u1.1 * u2.0;
+	let dot = u1.0 * u2.0 + u1.1 * u2.1;
+	if cross.abs() < 1e-9 && dot > 0. {
+		// The lines are aligned
+		return;
+	}
+
+	// The join is on the outer side of the turn
+	let side = if cross > 0. { -half } else { half };
+	let o1 = (p1.0 - u1.1 * side, p1.1 + u1.0 * side);
+	let o2 = (p1.0 - u2.1 * side, p1.1 + u2.0 * side);
+
+	// The angle between the two lines
+	let angle = PI - dot.clamp(-1., 1.).acos();
+	match style {
+		JoinStyle::Round => shape.add(circle(p1, half), false),
+		JoinStyle::Miter if angle >= MITER_LIMIT => {
+			let bisector = (o1.0 + o2.0 - 2. * p1.0, o1.1 + o2.1 - 2. * p1.1);
+			let len = bisector.0.hypot(bisector.1);
+			let miter_len = half / (angle / 2.).sin();
+			let miter = (
+				p1.0 + bisector.0 / len * miter_len,
+				p1.1 + bisector.1 / len * miter_len,
+			);
+			shape.add(vec![p1, o1, miter, o2], false);
+		}
+		_ => shape.add(vec![p1, o1, o2], false),
+	}
+}
+
+/// Draws a wide path going through the given points.
+///
+/// `smooth` tells, for each point, whether the path is smooth there, such as along a curve. The
+/// lines meeting at a smooth point are joined with a miter instead of the join style of the
+/// graphics context.
+pub fn wide_path(canvas: &mut Canvas, points: &[FPoint], smooth: &[bool]) {
+	let gc = canvas.get_gc();
+	let join_style = |i: usize| {
+		if smooth[i] {
+			JoinStyle::Miter
+		} else {
+			gc.join_style
+		}
+	};
+	let half = gc.line_width as f64 / 2.;
+	let closed = points.len() > 2 && points.first() == points.last();
+	// Thin lines are the only ones affected by `NotLast`
+	let cap = match gc.cap_style {
+		CapStyle::NotLast => CapStyle::Butt,
+		c => c,
+	};
+	let mut dashes = (gc.line_style != LineStyle::Solid).then(|| Dashes::new(gc));
+
+	// Shapes for even and odd dashes
+	let mut even = Shape::default();
+	let mut odd = Shape::default();
+	// Whether each point is inside of an even dash
+	let mut even_points = vec![true; points.len()];
+
+	let last = points.len() - 1;
+	for (i, w) in points.windows(2).enumerate() {
+		let (a, b) = (w[0], w[1]);
+		// Caps are drawn only at the ends of the path
+		let start_cap = if i == 0 && !closed {
+			cap
+		} else {
+			CapStyle::Butt
+		};
+		let end_cap = if i + 1 == last && !closed {
+			cap
+		} else {
+			CapStyle::Butt
+		};
+
+		let Some(dashes) = &mut dashes else {
+			wide_segment(&mut even, a, b, half, start_cap, end_cap);
+			continue;
+		};
+		let len = distance(a, b);
+		if len == 0. {
+			wide_segment(&mut even, a, b, half, start_cap, end_cap);
+			continue;
+		}
+		for (begin, end, is_even) in dashes.split(len) {
+			let pa = (
+				a.0 + (b.0 - a.0) * begin / len,
+				a.1 + (b.1 - a.1) * begin / len,
+			);
+			let pb = (a.0 + (b.0 - a.0) * end / len, a.1 + (b.1 - a.1) * end / len);
+			// With `OnOffDash`, each dash has its own caps
+			let (start, end) = if gc.line_style == LineStyle::OnOffDash {
+				// Dashes going through a point are joined instead
+				let joined_start = begin == 0. && (i > 0 || closed);
+				let joined_end = end == len && (i + 1 < last || closed);
+				(
+					if joined_start { CapStyle::Butt } else { cap },
+					if joined_end { CapStyle::Butt } else { cap },
+				)
+			} else {
+				(
+					if begin == 0. {
+						start_cap
+					} else {
+						CapStyle::Butt
+					},
+					if end == len { end_cap } else { CapStyle::Butt },
+				)
+			};
+
+			let shape = if is_even { &mut even } else { &mut odd };
+			wide_segment(shape, pa, pb, half, start, end);
+		}
+		even_points[i + 1] = dashes.is_even();
+	}
+
+	for i in 1..last {
+		let shape = if even_points[i] { &mut even } else { &mut odd };
+		join(
+			shape,
+			points[i - 1],
+			points[i],
+			points[i + 1],
+			half,
+			join_style(i),
+		);
+	}
+	if closed {
+		let shape = if even_points[last] {
+			&mut even
+		} else {
+			&mut odd
+		};
+		join(
+			shape,
+			points[last - 1],
+			points[0],
+			points[1],
+			half,
+			join_style(0),
+		);
+	}
+
+	if let Some(source) = dash_source(gc, false) {
+		odd.fill(canvas, FillRule::Winding, ShapeHint::Complex, source);
+	}
+	even.fill(canvas, FillRule::Winding, ShapeHint::Complex, Source::Fill);
+}
+
+/// Draws a path going through the given points, joined with the join style of the graphics
+/// context.
+///
+/// If the first and last points are equal, the path is closed and the ends are joined instead of
+/// being capped.
+pub fn poly_line(canvas: &mut Canvas, points: &[(i32, i32)]) {
+	if points.len() < 2 {
+		return;
+	}
+
+	if canvas.get_gc().line_width == 0 {
+		thin_path(canvas, points);
+	} else {
+		let points: Vec<FPoint> = points.iter().map(|(x, y)| (*x as f64, *y as f64)).collect();
+		wide_path(canvas, &points, &vec![false; points.len()]);
+	}
+}
+
+/// Draws independent lines, each given by its two endpoints.
+pub fn poly_segment(canvas: &mut Canvas, segments: &[Segment]) {
+	for (a, b) in segments {
+		poly_line(canvas, &[*a, *b]);
+	}
+}
+
+/// Draws the outlines of the given rectangles.
+pub fn poly_rectangle(canvas: &mut Canvas, rects: &[Rectangle]) {
+	for r in rects {
+		let (x, y) = (r.x as i32, r.y as i32);
+		let (x2, y2) = (x + r.width as i32, y + r.height as i32);
+		poly_line(canvas, &[(x, y), (x2, y), (x2, y2), (x, y2), (x, y)]);
+	}
+}
+
+/// Draws the given points with the foreground, ignoring the line attributes and the fill style
+/// of the graphics context.
+pub fn poly_point(canvas: &mut Canvas, points: &[(i32, i32)]) {
+	let source = Source::Pixel(canvas.get_gc().foreground);
+	for (x, y) in points {
+		canvas.put_pixel(*x, *y, source);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ctx::gc::Function;
+	use crate::drawing::testing::render;
+	use crate::drawing::CoordinateMode;
+	use crate::protocol::Point;
+
+	#[test]
+	fn thin_octants() {
+		let center = (3, 3);
+		let segments: Vec<Segment> = [
+			(3, 1),
+			(1, 3),
+			(-1, 3),
+			(-3, 1),
+			(-3, -1),
+			(-1, -3),
+			(1, -3),
+			(3, -1),
+		]
+		.into_iter()
+		.map(|(dx, dy)| (center, (center.0 + dx, center.1 + dy)))
+		.collect();
+		let res = render(7, 7, |_| {}, |c| poly_segment(c, &segments));
+		assert_eq!(
+			res,
+			[
+				"..#.#..", //
+				"..#.#..", //
+				"##.#.##", //
+				"..###..", //
+				"##.#.##", //
+				"..#.#..", //
+				"..#.#..", //
+			]
+		);
+	}
+
+	#[test]
+	fn thin_translated() {
+		// The line goes through the middle of pixels, so that choosing between them matters. Once
+		// translated, partly outside of the drawable, the same pixels must be chosen
+		let whole = render(12, 7, |_| {}, |c| poly_line(c, &[(2, 1), (10, 5)]));
+		assert_eq!(
+			whole,
+			[
+				"............", //
+				"..#.........", //
+				"...##.......", //
+				".....##.....", //
+				".......##...", //
+				".........##.", //
+				"............", //
+			]
+		);
+		let clipped = render(7, 4, |_| {}, |c| poly_line(c, &[(-2, -1), (6, 3)]));
+		let expected: Vec<_> = whole[2..6].iter().map(|row| &row[4..11]).collect();
+		assert_eq!(clipped, expected);
+	}
+
+	#[test]
+	fn thin_last_pixel() {
+		let points = [(0, 0), (3, 0), (3, 2)];
+		let butt = render(5, 3, |_| {}, |c| poly_line(c, &points));
+		assert_eq!(
+			butt,
+			[
+				"####.", //
+				"...#.", //
+				"...#.", //
+			]
+		);
+		let not_last = render(
+			5,
+			3,
+			|gc| gc.cap_style = CapStyle::NotLast,
+			|c| poly_line(c, &points),
+		);
+		assert_eq!(
+			not_last,
+			[
+				"####.", //
+				"...#.", //
+				".....", //
+			]
+		);
+	}
+
+	#[test]
+	fn thin_closed_path() {
+		// The first point of a closed path is not drawn again, so that `Xor` leaves it set
+		let points = [(0, 0), (3, 0), (3, 2), (0, 2), (0, 0)];
+		let res = render(
+			5,
+			3,
+			|gc| gc.function = Function::Xor,
+			|c| poly_line(c, &points),
+		);
+		assert_eq!(
+			res,
+			[
+				"####.", //
+				"#..#.", //
+				"####.", //
+			]
+		);
+	}
+
+	#[test]
+	fn coordinate_modes() {
+		let expected = [
+			"......", //
+			".####.", //
+			"....#.", //
+			"....#.", //
+		];
+		for (mode, points) in [
+			(CoordinateMode::Origin, [(1, 1), (4, 1), (4, 3)]),
+			(CoordinateMode::Previous, [(1, 1), (3, 0), (0, 2)]),
+		] {
+			let points: Vec<Point> = points
+				.into_iter()
+				.map(|(x, y)| Point {
+					x,
+					y,
+				})
+				.collect();
+			let points = mode.resolve(&points);
+			let res = render(6, 4, |_| {}, |c| poly_line(c, &points));
+			assert_eq!(res, expected, "{mode:?}");
+		}
+	}
+
+	#[test]
+	fn thin_dashes() {
+		let points = [(0, 0), (9, 0)];
+		let dashed = |style| {
+			render(
+				10,
+				1,
+				|gc| {
+					gc.line_style = style;
+					gc.dashes = vec![3, 2];
+					gc.dash_offset = 1;
+				},
+				|c| poly_line(c, &points),
+			)
+		};
+		assert_eq!(dashed(LineStyle::OnOffDash), ["##..###..#"]);
+		assert_eq!(dashed(LineStyle::DoubleDash), ["##oo###oo#"]);
+	}
+
+	#[test]
+	fn thin_dashes_odd_list() {
+		// An odd list is repeated, so that the same length alternates between even and odd dashes
+		let res = render(
+			9,
+			1,
+			|gc| {
+				gc.line_style = LineStyle::DoubleDash;
+				gc.dashes = vec![3];
+			},
+			|c| poly_line(c, &[(0, 0), (8, 0)]),
+		);
+		assert_eq!(res, ["###ooo###"]);
+	}
+
+	#[test]
+	fn thin_dashes_across_segments() {
+		// The pattern continues from one segment to the next
+		let res = render(
+			4,
+			4,
+			|gc| {
+				gc.line_style = LineStyle::OnOffDash;
+				gc.dashes = vec![2, 2];
+			},
+			|c| poly_line(c, &[(0, 0), (3, 0), (3, 3)]),
+		);
+		assert_eq!(
+			res,
+			[
+				"##..", //
+				"...#", //
+				"...#", //
+				"....", //
+			]
+		);
+	}
+
+	#[test]
+	fn wide_dashes() {
+		let dashed = |style| {
+			render(
+				11,
+				3,
+				|gc| {
+					gc.line_width = 2;
+					gc.line_style = style;
+					gc.dashes = vec![3, 2];
+					gc.dash_offset = 1;
+				},
+				|c| poly_line(c, &[(0, 1), (10, 1)]),
+			)
+		};
+		assert_eq!(
+			dashed(LineStyle::OnOffDash),
+			[
+				"##..###..#.", //
+				"##..###..#.", //
+				"...........", //
+			]
+		);
+		assert_eq!(
+			dashed(LineStyle::DoubleDash),
+			[
+				"##oo###oo#.", //
+				"##oo###oo#.", //
+				"...........", //
+			]
+		);
+	}
+
+	#[test]
+	fn wide_caps() {
+		let capped = |cap| {
+			render(
+				8,
+				4,
+				|gc| {
+					gc.line_width = 2;
+					gc.cap_style = cap;
+				},
+				|c| poly_line(c, &[(2, 2), (5, 2)]),
+			)
+		};
+		let butt = [
+			"........", //
+			"..###...", //
+			"..###...", //
+			"........", //
+		];
+		assert_eq!(capped(CapStyle::Butt), butt);
+		// `NotLast` only applies to thin lines
+		assert_eq!(capped(CapStyle::NotLast), butt);
+		assert_eq!(
+			capped(CapStyle::Projecting),
+			[
+				"........", //
+				".#####..", //
+				".#####..", //
+				"........", //
+			]
+		);
+		assert_eq!(
+			capped(CapStyle::Round),
+			[
+				"........", //
+				"..###...", //
+				".#####..", //
+				"........", //
+			]
+		);
+	}
+
+	#[test]
+	fn wide_joins() {
+		let joined = |join| {
+			render(
+				11,
+				9,
+				|gc| {
+					gc.line_width = 4;
+					gc.join_style = join;
+					gc.function = Function::Xor;
+				},
+				|c| poly_line(c, &[(2, 6), (7, 6), (7, 1)]),
+			)
+		};
+		assert_eq!(
+			joined(JoinStyle::Miter),
+			[
+				"...........", //
+				".....####..", //
+				".....####..", //
+				".....####..", //
+				"..#######..", //
+				"..#######..", //
+				"..#######..", //
+				"..#######..", //
+				"...........", //
+			]
+		);
+		assert_eq!(
+			joined(JoinStyle::Bevel),
+			[
+				"...........", //
+				".....####..", //
+				".....####..", //
+				".....####..", //
+				"..#######..", //
+				"..#######..", //
+				"..#######..", //
+				"..######...", //
+				"...........", //
+			]
+		);
+	}
+}
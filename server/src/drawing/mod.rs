@@ -0,0 +1,551 @@
+//! Software rasterizer implementing the graphics primitives of the X protocol.
+//!
+//! Primitives are drawn onto a [`Canvas`] as spans of pixels. The canvas applies the fill style,
+//! the clip of the graphics context and the bounds of the drawable, then combines each pixel with
+//! the destination according to the function and plane mask.
+//!
+//! Pixmaps are drawn onto their own image. Windows are drawn onto the contents of their screen at
+//! their absolute position, restricted to their visible rectangles.
+//!
+//! Following the protocol, the center of a pixel lies on integer coordinates. A pixel belongs to a
+//! shape if its center is inside of it. A center lying exactly on the boundary belongs to the
+//! shape only if the interior is immediately to its right, or immediately below it for
+//! horizontal edges.
+
+pub mod arc;
+pub mod copy;
+pub mod line;
+pub mod polygon;
+
+use crate::ctx::gc::Clip;
+use crate::ctx::gc::FillStyle;
+use crate::ctx::gc::Function;
+use crate::ctx::gc::SubWindowMode;
+use crate::ctx::gc::GC;
+use crate::ctx::image::Image;
+use crate::ctx::pixmap::Pixmap;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::Point;
+use crate::protocol::Rectangle;
+use crate::region::Region;
+use std::cell::Ref;
+use std::num::NonZeroU32;
+use std::ops::Deref;
+
+/// The way coordinates of points are interpreted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CoordinateMode {
+	/// Coordinates are relative to the origin of the drawable.
+	Origin,
+	/// Coordinates are relative to the previous point. The first point is relative to the origin.
+	Previous,
+}
+
+impl TryFrom<u8> for CoordinateMode {
+	type Error = Error;
+
+	fn try_from(v: u8) -> Result<Self, Self::Error> {
+		match v {
+			0 => Ok(Self::Origin),
+			1 => Ok(Self::Previous),
+
+			_ => Err(Error::Value(v as _)),
+		}
+	}
+}
+
+impl CoordinateMode {
+	/// Returns the given points, relative to the origin of the drawable.
+	pub fn resolve(&self, points: &[Point]) -> Vec<(i32, i32)> {
+		let mut prev = (0, 0);
+		points
+			.iter()
+			.map(|p| {
+				let p = (p.x as i32, p.y as i32);
+				prev = match self {
+					Self::Origin => p,
+					Self::Previous => (prev.0 + p.0, prev.1 + p.1),
+				};
+
+				prev
+			})
+			.collect()
+	}
+}
+
+/// The source of the pixels of a fill.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+	/// The fill style of the graphics context.
+	Fill,
+	/// The fill style of the graphics context, for odd dashes of `DoubleDash` lines.
+	OddDash,
+	/// The given pixel, regardless of the fill style.
+	Pixel(u32),
+}
+
+/// An image referenced by a graphics context.
+pub enum GCImage<'a> {
+	/// The image, borrowed from its pixmap.
+	Borrowed(Ref<'a, Image>),
+	/// A copy of the image, since its pixmap is the one being drawn on.
+	Copied(Image),
+}
+
+impl GCImage<'_> {
+	/// Returns the image of the given pixmap, copied if the pixmap is the destination.
+	///
+	/// `dst` is the ID of the pixmap being drawn on, if any.
+	fn new(pixmap: &Pixmap, dst: Option<NonZeroU32>) -> GCImage<'_> {
+		if dst == Some(pixmap.get_id()) {
+			GCImage::Copied(pixmap.get_image().clone())
+		} else {
+			GCImage::Borrowed(pixmap.get_image())
+		}
+	}
+}
+
+impl Deref for GCImage<'_> {
+	type Target = Image;
+
+	fn deref(&self) -> &Self::Target {
+		match self {
+			Self::Borrowed(image) => image,
+			Self::Copied(image) => image,
+		}
+	}
+}
+
+/// The images referenced by a graphics context, shared by the canvases of a drawing.
+#[derive(Default)]
+pub struct GCImages<'a> {
+	/// The clip mask.
+	pub clip_mask: Option<GCImage<'a>>,
+	/// The tile, if used by the fill style.
+	pub tile: Option<GCImage<'a>>,
+	/// The stipple, if used by the fill style.
+	pub stipple: Option<GCImage<'a>>,
+}
+
+impl<'a> GCImages<'a> {
+	/// Returns the images of the given graphics context that are used for drawing.
+	///
+	/// `dst` is the ID of the pixmap being drawn on, if any. Its images are copied so that it can
+	/// be borrowed mutably while drawing.
+	pub fn new(gc: &'a GC, dst: Option<NonZeroU32>) -> Self {
+		let clip_mask = match &gc.clip {
+			Clip::Mask(mask) => Some(GCImage::new(mask, dst)),
+			_ => None,
+		};
+		let tile = match gc.fill_style {
+			FillStyle::Tiled => gc.tile.as_deref().map(|t| GCImage::new(t, dst)),
+			_ => None,
+		};
+		let stipple = match gc.fill_style {
+			FillStyle::Stippled | FillStyle::OpaqueStippled => {
+				gc.stipple.as_deref().map(|s| GCImage::new(s, dst))
+			}
+			_ => None,
+		};
+
+		Self {
+			clip_mask,
+			tile,
+			stipple,
+		}
+	}
+}
+
+/// Returns the pixel of the given pattern at the given position, repeating the pattern from its
+/// origin.
+///
+/// If there is no pattern, the function returns None.
+fn get_pattern_pixel(pattern: Option<&Image>, origin: (i32, i32), x: i32, y: i32) -> Option<u32> {
+	let pattern = pattern?;
+	let (width, height) = pattern.get_size();
+	let x = (x - origin.0).rem_euclid(width as i32);
+	let y = (y - origin.1).rem_euclid(height as i32);
+	Some(pattern.get_pixel(x, y))
+}
+
+/// A surface on which primitives are drawn, with the values of a graphics context.
+pub struct Canvas<'a> {
+	/// The pixels being drawn on.
+	image: &'a mut Image,
+	/// The position of the drawable's origin on the image.
+	origin: (i32, i32),
+	/// The width and height of the drawable.
+	size: (u16, u16),
+	/// The region of the drawable that can be drawn on. If None, the whole drawable can.
+	visible: Option<Region>,
+
+	/// The graphics context.
+	gc: &'a GC,
+	/// The images of the graphics context.
+	images: &'a GCImages<'a>,
+}
+
+impl<'a> Canvas<'a> {
+	/// Creates a canvas drawing on the whole given image.
+	///
+	/// Arguments:
+	/// - `image` is the image to draw on.
+	/// - `gc` is the graphics context.
+	/// - `images` are the images of the graphics context.
+	pub fn new(image: &'a mut Image, gc: &'a GC, images: &'a GCImages<'a>) -> Self {
+		let size = image.get_size();
+		Self {
+			image,
+			origin: (0, 0),
+			size,
+			visible: None,
+
+			gc,
+			images,
+		}
+	}
+
+	/// Creates a canvas drawing on a window, on the contents of its screen.
+	///
+	/// Arguments:
+	/// - `image` is the contents of the screen.
+	/// - `origin` is the absolute position of the window's origin.
+	/// - `size` is the width and height of the window.
+	/// - `visible` is the visible region of the window, relative to its origin.
+	/// - `gc` is the graphics context.
+	/// - `images` are the images of the graphics context.
+	pub fn new_window(
+		image: &'a mut Image,
+		origin: (i32, i32),
+		size: (u16, u16),
+		visible: Region,
+		gc: &'a GC,
+		images: &'a GCImages<'a>,
+	) -> Self {
+		Self {
+			image,
+			origin,
+			size,
+			visible: Some(visible),
+
+			gc,
+			images,
+		}
+	}
+
+	/// Returns the graphics context.
+	pub fn get_gc(&self) -> &'a GC {
+		self.gc
+	}
+
+	/// Returns the width and height of the drawable.
+	pub fn get_size(&self) -> (u16, u16) {
+		self.size
+	}
+
+	/// Tells whether the given position is inside of the drawable.
+	fn contains(&self, x: i32, y: i32) -> bool {
+		(0..self.size.0 as i32).contains(&x) && (0..self.size.1 as i32).contains(&y)
+	}
+
+	/// Tells whether the pixel at the given position is not visible or excluded by the clip of
+	/// the graphics context.
+	fn is_clipped(&self, x: i32, y: i32) -> bool {
+		if self.visible.as_ref().is_some_and(|v| !v.contains(x, y)) {
+			return true;
+		}
+
+		let x = x - self.gc.clip_x_origin as i32;
+		let y = y - self.gc.clip_y_origin as i32;
+
+		match &self.gc.clip {
+			Clip::None => false,
+			Clip::Mask(_) => self
+				.images
+				.clip_mask
+				.as_ref()
+				.is_some_and(|mask| !mask.contains(x, y) || mask.get_pixel(x, y) == 0),
+			Clip::Rectangles(region) => !region.contains(x, y),
+		}
+	}
+
+	/// Returns the value of the pixel at the given position for the given source.
+	///
+	/// If the pixel is not drawn, the function returns None.
+	fn get_source_pixel(&self, x: i32, y: i32, source: Source) -> Option<u32> {
+		let gc = self.gc;
+		let origin = (
+			gc.tile_stipple_x_origin as i32,
+			gc.tile_stipple_y_origin as i32,
+		);
+		// Without a stipple, every bit is considered set
+		let stipple =
+			|| get_pattern_pixel(self.images.stipple.as_deref(), origin, x, y) != Some(0);
+
+		match (source, gc.fill_style) {
+			(Source::Pixel(pixel), _) => Some(pixel),
+			(Source::Fill, FillStyle::Solid) => Some(gc.foreground),
+			(Source::OddDash, FillStyle::Solid) => Some(gc.background),
+			// Without a tile, the tile is filled with the foreground
+			(_, FillStyle::Tiled) => Some(
+				get_pattern_pixel(self.images.tile.as_deref(), origin, x, y)
+					.unwrap_or(gc.foreground),
+			),
+			(Source::Fill, FillStyle::Stippled) => stipple().then_some(gc.foreground),
+			(Source::OddDash, FillStyle::Stippled) => stipple().then_some(gc.background),
+			(_, FillStyle::OpaqueStippled) => Some(if stipple() {
+				gc.foreground
+			} else {
+				gc.background
+			}),
+		}
+	}
+
+	/// Writes the given pixel at the given position, combining it with the pixel already present
+	/// according to the function and plane mask of the graphics context.
+	///
+	/// The position must be inside of the drawable and not excluded by the clip.
+	pub fn write_pixel(&mut self, x: i32, y: i32, src: u32) {
+		let (x, y) = (x + self.origin.0, y + self.origin.1);
+		let gc = self.gc;
+		let mask = self.image.get_pixel_mask();
+		if gc.function == Function::Copy && gc.plane_mask & mask == mask {
+			self.image.set_pixel(x, y, src);
+			return;
+		}
+
+		let dst = self.image.get_pixel(x, y);
+		let res = gc.function.apply(src, dst);
+		self.image
+			.set_pixel(x, y, (res & gc.plane_mask) | (dst & !gc.plane_mask));
+	}
+
+	/// Fills the pixels of the given scanline, from `x_begin` included to `x_end` excluded.
+	///
+	/// Every primitive draws through this function. Pixels outside of the drawable or excluded
+	/// by the clip are left untouched.
+	pub fn fill_span(&mut self, y: i32, x_begin: i32, x_end: i32, source: Source) {
+		let (width, height) = self.size;
+		if !(0..height as i32).contains(&y) {
+			return;
+		}
+
+		let x_begin = x_begin.max(0);
+		let x_end = x_end.min(width as i32);
+		for x in x_begin..x_end {
+			if self.is_clipped(x, y) {
+				continue;
+			}
+			if let Some(pixel) = self.get_source_pixel(x, y, source) {
+				self.write_pixel(x, y, pixel);
+			}
+		}
+	}
+
+	/// Fills the pixel at the given position.
+	pub fn put_pixel(&mut self, x: i32, y: i32, source: Source) {
+		self.fill_span(y, x, x + 1, source);
+	}
+
+	/// Fills the given rectangle.
+	pub fn fill_rectangle(&mut self, rect: &Rectangle, source: Source) {
+		let (x, y) = (rect.x as i32, rect.y as i32);
+		// Restrict to the drawable, avoiding to iterate over scanlines that cannot be drawn
+		let (_, height) = self.size;
+		let y_begin = y.max(0);
+		let y_end = (y + rect.height as i32).min(height as i32);
+
+		for y in y_begin..y_end {
+			self.fill_span(y, x, x + rect.width as i32, source);
+		}
+	}
+
+	/// Draws the given image with its top-left corner at the given position.
+	///
+	/// Arguments:
+	/// - `image` is the image to draw.
+	/// - `mask` is the region of the image to draw, relative to the image. If None, it is whole.
+	/// - `x` and `y` are the position of the image.
+	/// - `bitmap` tells whether the image is a bitmap, drawn with the foreground and background.
+	///
+	/// Set bits of a bitmap are drawn with the foreground and others with the background.
+	pub fn put_image(
+		&mut self,
+		image: &Image,
+		mask: Option<&Region>,
+		x: i32,
+		y: i32,
+		bitmap: bool,
+	) {
+		let (width, height) = image.get_size();
+		for dy in 0..height as i32 {
+			for dx in 0..width as i32 {
+				if mask.is_some_and(|m| !m.contains(dx, dy)) {
+					continue;
+				}
+				let pixel = image.get_pixel(dx, dy);
+				let pixel = match (bitmap, pixel) {
+					(false, _) => pixel,
+					(true, 0) => self.gc.background,
+					(true, _) => self.gc.foreground,
+				};
+
+				let (x, y) = (x + dx, y + dy);
+				if self.contains(x, y) && !self.is_clipped(x, y) {
+					self.write_pixel(x, y, pixel);
+				}
+			}
+		}
+	}
+}
+
+/// Draws on the given drawable with the given graphics context.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `drawable` is the ID of the drawable.
+/// - `gc` is the ID of the graphics context.
+/// - `f` is the function drawing on the canvas, called for each copy of the contents.
+///
+/// If the graphics context has not been created for the root and depth of the drawable, or if the
+/// drawable is an `InputOnly` window, the function returns a `Match` error.
+pub fn draw<F: FnMut(&mut Canvas)>(
+	ctx: &Context,
+	drawable: u32,
+	gc: u32,
+	mut f: F,
+) -> Result<(), Error> {
+	let id = NonZeroU32::new(drawable).ok_or(Error::Drawable(drawable))?;
+	let gc = NonZeroU32::new(gc)
+		.and_then(|id| ctx.get_gc(id))
+		.ok_or(Error::GContext(gc))?;
+
+	if let Some(pixmap) = ctx.get_pixmap(id) {
+		if !gc.is_compatible(&**pixmap) {
+			return Err(Error::Match);
+		}
+
+		let images = GCImages::new(gc, Some(id));
+		let mut image = pixmap.get_image_mut();
+		f(&mut Canvas::new(&mut image, gc, &images));
+
+		return Ok(());
+	}
+
+	let win = ctx.get_window(id).ok_or(Error::Drawable(drawable))?;
+	if !win.is_output() || !gc.is_compatible(win) {
+		return Err(Error::Match);
+	}
+	let include_inferiors = gc.subwindow_mode == SubWindowMode::IncludeInferiors;
+	let origin = win.get_absolute_position(ctx);
+	let rect = win.get_rectangle();
+	let size = (rect.width, rect.height);
+	let images = GCImages::new(gc, None);
+
+	// Contents kept offscreen are drawn on too, even when hidden
+	if let Some(mut backing) = win.get_backing_image_mut() {
+		let region = win.get_backing_region(ctx, include_inferiors);
+		f(&mut Canvas::new_window(
+			&mut backing,
+			(0, 0),
+			size,
+			region,
+			gc,
+			&images,
+		));
+	}
+	for save_under in ctx.get_save_unders() {
+		let region = save_under.get_region(ctx, win);
+		if region.is_empty() {
+			continue;
+		}
+		let (x, y) = save_under.get_position();
+		f(&mut Canvas::new_window(
+			&mut save_under.get_image_mut(),
+			(origin.0 - x, origin.1 - y),
+			size,
+			region,
+			gc,
+			&images,
+		));
+	}
+
+	let visible = win.get_visible_region(ctx, include_inferiors);
+	if visible.is_empty() {
+		return Ok(());
+	}
+	let root = NonZeroU32::new(win.get_root()).unwrap();
+	let Some(screen) = ctx.get_screen_for_root(root).map(|i| &ctx.get_screens()[i]) else {
+		return Ok(());
+	};
+
+	let mut contents = screen.get_contents_mut();
+	f(&mut Canvas::new_window(
+		&mut contents,
+		origin,
+		size,
+		visible,
+		gc,
+		&images,
+	));
+
+	Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+	use super::Canvas;
+	use super::GCImages;
+	use crate::ctx::gc::GC;
+	use crate::ctx::image::Image;
+	use crate::ctx::pixmap::Pixmap;
+	use std::num::NonZeroU32;
+
+	/// The foreground of the graphics context used by `render`.
+	pub const FOREGROUND: u32 = 1;
+	/// The background of the graphics context used by `render`.
+	pub const BACKGROUND: u32 = 2;
+
+	/// Draws on a blank image of the given size and returns its rows.
+	///
+	/// Pixels left untouched are shown as `.`, pixels drawn with the foreground as `#` and pixels
+	/// drawn with the background as `o`. Other values are shown as their last digit.
+	///
+	/// With the `Xor` function, pixels drawn twice show up as holes.
+	///
+	/// Arguments:
+	/// - `width` and `height` are the dimensions of the image.
+	/// - `setup` modifies the graphics context before drawing.
+	/// - `draw` draws on the canvas.
+	pub fn render<S: FnOnce(&mut GC), D: FnOnce(&mut Canvas)>(
+		width: u16,
+		height: u16,
+		setup: S,
+		draw: D,
+	) -> Vec<String> {
+		let id = NonZeroU32::new(1).unwrap();
+		let pixmap = Pixmap::new(id, 0, id, 8, width, height).unwrap();
+		let mut gc = GC::new(id, 0, &pixmap);
+		gc.foreground = FOREGROUND;
+		gc.background = BACKGROUND;
+		setup(&mut gc);
+
+		let mut image = Image::new(8, width, height).unwrap();
+		draw(&mut Canvas::new(&mut image, &gc, &GCImages::default()));
+
+		(0..height as i32)
+			.map(|y| {
+				(0..width as i32)
+					.map(|x| match image.get_pixel(x, y) {
+						0 => '.',
+						FOREGROUND => '#',
+						BACKGROUND => 'o',
+						p => char::from_digit(p % 10, 10).unwrap(),
+					})
+					.collect()
+			})
+			.collect()
+	}
+}
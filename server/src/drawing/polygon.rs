@@ -0,0 +1,423 @@
+//! Scan conversion of polygons.
+//!
+//! Edges are half-open on the Y axis: a scanline crossing the top end of an edge is counted, but
+//! not one crossing its bottom end. On each scanline, a span includes the pixels whose center is
+//! at or after the left crossing and strictly before the right crossing. This implements the
+//! pixel rules of the protocol.
+
+use super::Canvas;
+use super::Source;
+use crate::ctx::gc::FillRule;
+use crate::protocol::error::Error;
+use crate::protocol::Rectangle;
+
+/// A point with fractional coordinates.
+pub type FPoint = (f64, f64);
+
+/// A hint on the shape of a polygon, given by the client.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShapeHint {
+	/// The polygon may intersect itself.
+	Complex,
+	/// The polygon does not intersect itself, but is not necessarily convex.
+	Nonconvex,
+	/// The polygon is convex.
+	Convex,
+}
+
+impl TryFrom<u8> for ShapeHint {
+	type Error = Error;
+
+	fn try_from(v: u8) -> Result<Self, Self::Error> {
+		match v {
+			0 => Ok(Self::Complex),
+			1 => Ok(Self::Nonconvex),
+			2 => Ok(Self::Convex),
+
+			_ => Err(Error::Value(v as _)),
+		}
+	}
+}
+
+/// A non-horizontal edge of a polygon.
+struct Edge {
+	/// The first scanline crossing the edge.
+	y_begin: i32,
+	/// The scanline after the last one crossing the edge.
+	y_end: i32,
+
+	/// The top end of the edge.
+	top: FPoint,
+	/// The change of X for each scanline.
+	slope: f64,
+	/// The direction of the edge: `1` if going downwards, `-1` if going upwards.
+	dir: i32,
+}
+
+impl Edge {
+	/// Creates the edge from `a` to `b`. If the edge crosses no scanline, the function returns
+	/// None.
+	fn new(a: FPoint, b: FPoint) -> Option<Self> {
+		let (top, bottom, dir) = if a.1 < b.1 { (a, b, 1) } else { (b, a, -1) };
+		let y_begin = top.1.ceil() as i32;
+		let y_end = bottom.1.ceil() as i32;
+		// Short edges between two scanlines would otherwise be activated without ever being
+		// retired
+		if y_begin >= y_end {
+			return None;
+		}
+
+		Some(Self {
+			y_begin,
+			y_end,
+
+			top,
+			slope: (bottom.0 - top.0) / (bottom.1 - top.1),
+			dir,
+		})
+	}
+
+	/// Returns the X position at which the edge crosses the given scanline.
+	fn x_at(&self, y: i32) -> f64 {
+		// Computed from the end instead of incrementally, so that errors do not accumulate
+		self.top.0 + (y as f64 - self.top.1) * self.slope
+	}
+}
+
+/// Returns twice the signed area of the given polygon.
+fn signed_area(points: &[FPoint]) -> f64 {
+	points
+		.iter()
+		.zip(points.iter().cycle().skip(1))
+		.map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+		.sum()
+}
+
+/// A set of polygons, filled together.
+#[derive(Default)]
+pub struct Shape {
+	/// The list of polygons, all with the same orientation.
+	polygons: Vec<Vec<FPoint>>,
+}
+
+impl Shape {
+	/// Adds a polygon to the shape.
+	///
+	/// `keep_orientation` tells whether the orientation of the polygon must be kept. If false,
+	/// the polygon is reoriented so that, filled with the `Winding` rule, the shape is the union
+	/// of its polygons.
+	pub fn add(&mut self, mut points: Vec<FPoint>, keep_orientation: bool) {
+		if points.len() < 3 {
+			return;
+		}
+		if !keep_orientation && signed_area(&points) < 0. {
+			points.reverse();
+		}
+
+		self.polygons.push(points);
+	}
+
+	/// Tells whether the shape has no polygon.
+	pub fn is_empty(&self) -> bool {
+		self.polygons.is_empty()
+	}
+
+	/// Fills the shape on the canvas.
+	///
+	/// Arguments:
+	/// - `canvas` is the canvas to draw on.
+	/// - `rule` is the rule deciding which pixels are inside of the shape.
+	/// - `hint` is the hint on the shape, allowing faster paths.
+	/// - `source` is the source of pixels.
+	pub fn fill(&self, canvas: &mut Canvas, rule: FillRule, hint: ShapeHint, source: Source) {
+		// The edge table, sorted by first scanline
+		let mut edges: Vec<Edge> = self
+			.polygons
+			.iter()
+			.flat_map(|p| p.iter().zip(p.iter().cycle().skip(1)))
+			.filter_map(|(a, b)| Edge::new(*a, *b))
+			.collect();
+		edges.sort_unstable_by_key(|e| e.y_begin);
+
+		// Restrict to the drawable, avoiding to iterate over scanlines that cannot be drawn
+		let (_, height) = canvas.get_size();
+		let Some(first) = edges.first() else {
+			return;
+		};
+		let y_begin = first.y_begin.max(0);
+		let y_end = edges
+			.iter()
+			.map(|e| e.y_end)
+			.max()
+			.unwrap()
+			.min(height as i32);
+
+		let mut next = edges.partition_point(|e| e.y_begin < y_begin);
+		let mut active: Vec<&Edge> = edges[..next].iter().filter(|e| e.y_end > y_begin).collect();
+		let mut crossings: Vec<(f64, i32)> = vec![];
+		for y in y_begin..y_end {
+			active.retain(|e| e.y_end > y);
+			while next < edges.len() && edges[next].y_begin <= y {
+				active.push(&edges[next]);
+				next += 1;
+			}
+
+			crossings.clear();
+			crossings.extend(active.iter().map(|e| (e.x_at(y), e.dir)));
+			if hint == ShapeHint::Convex {
+				// A convex shape has a single span on each scanline
+				let (min, max) = crossings
+					.iter()
+					.fold((f64::MAX, f64::MIN), |(min, max), (x, _)| {
+						(min.min(*x), max.max(*x))
+					});
+				if min <= max {
+					canvas.fill_span(y, min.ceil() as i32, max.ceil() as i32, source);
+				}
+				continue;
+			}
+			crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+			let mut winding = 0;
+			let mut span_begin = 0.;
+			for (x, dir) in &crossings {
+				let prev = winding;
+				winding += dir;
+
+				let (was_inside, is_inside) = match rule {
+					FillRule::EvenOdd => (prev % 2 != 0, winding % 2 != 0),
+					FillRule::Winding => (prev != 0, winding != 0),
+				};
+				if !was_inside && is_inside {
+					span_begin = *x;
+				} else if was_inside && !is_inside {
+					canvas.fill_span(y, span_begin.ceil() as i32, x.ceil() as i32, source);
+				}
+			}
+		}
+	}
+}
+
+/// Fills the polygon going through the given points, with the fill rule of the graphics context.
+///
+/// The polygon is closed automatically.
+pub fn fill_poly(canvas: &mut Canvas, points: &[(i32, i32)], hint: ShapeHint) {
+	let gc = canvas.get_gc();
+
+	let mut shape = Shape::default();
+	shape.add(
+		points.iter().map(|(x, y)| (*x as f64, *y as f64)).collect(),
+		true,
+	);
+	shape.fill(canvas, gc.fill_rule, hint, Source::Fill);
+}
+
+/// Fills the given rectangles.
+pub fn poly_fill_rectangle(canvas: &mut Canvas, rects: &[Rectangle]) {
+	for r in rects {
+		canvas.fill_rectangle(r, Source::Fill);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ctx::gc::Function;
+	use crate::drawing::testing::render;
+	use crate::drawing::testing::BACKGROUND;
+	use crate::drawing::testing::FOREGROUND;
+
+	/// Fills each of the given polygons on its own with the `Xor` function, alternating between
+	/// the foreground and the background.
+	///
+	/// A pixel painted twice shows up as a hole or as a `3`, and a pixel missed as a hole.
+	fn render_tiled(width: u16, height: u16, polygons: &[&[(i32, i32)]]) -> Vec<String> {
+		render(
+			width,
+			height,
+			|gc| gc.function = Function::Xor,
+			|c| {
+				for (i, p) in polygons.iter().enumerate() {
+					let mut shape = Shape::default();
+					shape.add(
+						p.iter().map(|(x, y)| (*x as f64, *y as f64)).collect(),
+						true,
+					);
+					let pixel = if i % 2 == 0 { FOREGROUND } else { BACKGROUND };
+					shape.fill(
+						c,
+						FillRule::EvenOdd,
+						ShapeHint::Complex,
+						Source::Pixel(pixel),
+					);
+				}
+			},
+		)
+	}
+
+	#[test]
+	fn shared_axis_aligned_edges() {
+		let res = render_tiled(
+			8,
+			8,
+			&[
+				&[(1, 1), (4, 1), (4, 4), (1, 4)],
+				&[(4, 1), (7, 1), (7, 4), (4, 4)],
+				&[(1, 4), (7, 4), (7, 7), (1, 7)],
+			],
+		);
+		assert_eq!(
+			res,
+			[
+				"........", //
+				".###ooo.", //
+				".###ooo.", //
+				".###ooo.", //
+				".######.", //
+				".######.", //
+				".######.", //
+				"........", //
+			]
+		);
+	}
+
+	#[test]
+	fn shared_diagonal_edge() {
+		// Pixels on the diagonal belong to the triangle on their right
+		let res = render_tiled(
+			7,
+			7,
+			&[&[(0, 0), (6, 0), (6, 6)], &[(0, 0), (6, 6), (0, 6)]],
+		);
+		assert_eq!(
+			res,
+			[
+				"######.", //
+				"o#####.", //
+				"oo####.", //
+				"ooo###.", //
+				"oooo##.", //
+				"ooooo#.", //
+				".......", //
+			]
+		);
+	}
+
+	#[test]
+	fn shared_vertex() {
+		let center = (4, 4);
+		let res = render_tiled(
+			8,
+			8,
+			&[
+				&[(0, 0), (8, 0), center],
+				&[(8, 0), (8, 8), center],
+				&[(8, 8), (0, 8), center],
+				&[(0, 8), (0, 0), center],
+			],
+		);
+		assert_eq!(
+			res,
+			[
+				"########", //
+				"o######o", //
+				"oo####oo", //
+				"ooo##ooo", //
+				"oooooooo", //
+				"ooo##ooo", //
+				"oo####oo", //
+				"o######o", //
+			]
+		);
+	}
+
+	#[test]
+	fn star_fill_rules() {
+		let star = [(6, 0), (10, 11), (0, 4), (12, 4), (2, 11)];
+		let filled = |rule| {
+			render(
+				13,
+				12,
+				|gc| gc.fill_rule = rule,
+				|c| fill_poly(c, &star, ShapeHint::Complex),
+			)
+		};
+		// The pentagon in the middle is crossed twice
+		assert_eq!(
+			filled(FillRule::EvenOdd),
+			[
+				".............", //
+				"......#......", //
+				"......#......", //
+				".....###.....", //
+				"#####...####.", //
+				"..###...###..", //
+				"...#.....#...", //
+				"....#...#....", //
+				"....##.##....", //
+				"...##...##...", //
+				"...#.....#...", //
+				".............", //
+			]
+		);
+		assert_eq!(
+			filled(FillRule::Winding),
+			[
+				".............", //
+				"......#......", //
+				"......#......", //
+				".....###.....", //
+				"############.", //
+				"..#########..", //
+				"...#######...", //
+				"....#####....", //
+				"....#####....", //
+				"...##...##...", //
+				"...#.....#...", //
+				".............", //
+			]
+		);
+	}
+
+	#[test]
+	fn convex_matches_complex() {
+		let polygons: &[&[(i32, i32)]] = &[
+			&[(1, 1), (8, 2), (5, 9)],
+			&[(0, 3), (4, 0), (9, 4), (5, 9)],
+			&[(2, 0), (7, 0), (9, 5), (7, 9), (2, 9), (0, 5)],
+			// Partly outside of the drawable
+			&[(-4, -2), (6, 3), (-1, 12)],
+			// Degenerate
+			&[(2, 2), (6, 6), (8, 8)],
+		];
+		for points in polygons {
+			let fill = |hint| render(10, 10, |_| {}, |c| fill_poly(c, points, hint));
+			assert_eq!(
+				fill(ShapeHint::Convex),
+				fill(ShapeHint::Complex),
+				"{points:?}"
+			);
+		}
+
+		assert_eq!(
+			render(
+				10,
+				10,
+				|_| {},
+				|c| fill_poly(c, polygons[0], ShapeHint::Convex)
+			),
+			[
+				"..........", //
+				"..........", //
+				"..######..", //
+				"..######..", //
+				"...#####..", //
+				"...####...", //
+				"....###...", //
+				"....##....", //
+				".....#....", //
+				"..........", //
+			]
+		);
+	}
+}
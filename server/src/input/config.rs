@@ -0,0 +1,437 @@
+//! The input configuration file allows to set options for each input device.
+//!
+//! The file is made of `[device]` sections. Each section contains `key = value` lines. Keys
+//! starting with `match_` select the devices the section applies to, the other keys are options.
+//! A section without any `match_` key applies to every device. When several sections apply to
+//! the same device, later sections override the options set by earlier ones.
+//!
+//! The `[accessx]` section sets up keyboard accessibility features. It accepts the following
+//! keys: `sticky_keys`, `slow_keys`, `slow_keys_delay`, `bounce_keys`, `bounce_keys_delay`,
+//! `mouse_keys`, `mouse_keys_delay`, `mouse_keys_interval`, `mouse_keys_max_speed`,
+//! `mouse_keys_time_to_max` and `gestures`. Delays and intervals are in milliseconds.
+//!
+//! Lines starting with `#` are comments.
+//!
+//! Example:
+//!
+//! ```text
+//! [device]
+//! match_capability = pointer
+//! accel_profile = adaptive
+//!
+//! [device]
+//! match_name = *Trackball*
+//! left_handed = true
+//! scroll_button = 2
+//!
+//! [accessx]
+//! sticky_keys = true
+//! ```
+
+use super::device::InputDevice;
+use super::device::ABS_X;
+use super::device::BTN_LEFT;
+use super::device::BTN_TOOL_FINGER;
+use super::device::EV_ABS;
+use super::device::EV_KEY;
+use super::device::EV_REL;
+use super::device::INPUT_PROP_DIRECT;
+use super::device::KEY_A;
+use super::device::REL_X;
+use crate::ctx::acceleration::AccelProfile;
+use crate::ctx::accessx::AccessXSettings;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// The default path to the input configuration file. It can be overridden with the `-inputconfig`
+/// command line argument.
+pub const CONFIG_PATH: &str = "/etc/visto/input.conf";
+
+/// A capability of a device.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Capability {
+	/// The device has letter keys.
+	Keyboard,
+	/// The device moves the pointer with relative motions.
+	Pointer,
+	/// The device moves the pointer with a finger on a surface.
+	Touchpad,
+	/// The device reports absolute positions on the screen.
+	Touchscreen,
+}
+
+impl TryFrom<&str> for Capability {
+	type Error = Box<dyn Error>;
+
+	fn try_from(s: &str) -> Result<Self, Self::Error> {
+		match s {
+			"keyboard" => Ok(Self::Keyboard),
+			"pointer" => Ok(Self::Pointer),
+			"touchpad" => Ok(Self::Touchpad),
+			"touchscreen" => Ok(Self::Touchscreen),
+
+			_ => Err(format!("Invalid capability `{}`", s).into()),
+		}
+	}
+}
+
+/// Information about a device, used to select the configuration sections applying to it.
+pub struct DeviceInfo {
+	/// The name of the device.
+	pub name: String,
+	/// The vendor ID.
+	pub vendor: u16,
+	/// The product ID.
+	pub product: u16,
+	/// The path to the device's file.
+	pub path: String,
+	/// The capabilities of the device.
+	pub capabilities: Vec<Capability>,
+}
+
+impl DeviceInfo {
+	/// Gathers information about the given device.
+	pub fn from_device(dev: &InputDevice) -> Self {
+		let id = dev.get_input_id().unwrap_or_default();
+
+		let mut capabilities = vec![];
+		if dev.supports(EV_KEY, KEY_A) {
+			capabilities.push(Capability::Keyboard);
+		}
+		if dev.supports(EV_REL, REL_X) {
+			capabilities.push(Capability::Pointer);
+		}
+		if dev.supports(EV_ABS, ABS_X) {
+			if dev.supports(EV_KEY, BTN_TOOL_FINGER) && !dev.has_property(INPUT_PROP_DIRECT) {
+				capabilities.push(Capability::Touchpad);
+			} else if dev.has_property(INPUT_PROP_DIRECT) || !dev.supports(EV_KEY, BTN_LEFT) {
+				capabilities.push(Capability::Touchscreen);
+			}
+		}
+
+		Self {
+			name: dev.get_name().unwrap_or_default(),
+			vendor: id.vendor,
+			product: id.product,
+			path: dev.get_path().display().to_string(),
+			capabilities,
+		}
+	}
+
+	/// Tells whether the device has the given capability.
+	pub fn has_capability(&self, capability: Capability) -> bool {
+		self.capabilities.contains(&capability)
+	}
+}
+
+/// The options of a device, after every configuration section applying to it has been applied.
+#[derive(Clone, Debug)]
+pub struct DeviceSettings {
+	/// If true, the device is not used.
+	pub ignore: bool,
+	/// If true, the left and right buttons are swapped.
+	pub left_handed: bool,
+	/// If true, the scrolling direction is inverted.
+	pub natural_scrolling: bool,
+	/// While the physical button with this number is held, motions scroll instead of moving the
+	/// pointer.
+	pub scroll_button: Option<u8>,
+	/// If true, pressing the left and right buttons at the same time emulates the middle
+	/// button.
+	pub middle_emulation: bool,
+	/// The acceleration profile. If None, the default one is used.
+	pub accel_profile: Option<AccelProfile>,
+	/// The factor applied to every motion of the device. If None, the default one is used.
+	pub accel_speed: Option<f32>,
+	/// If true, tapping a touchpad clicks.
+	pub tap: bool,
+	/// The matrix applied to the normalized absolute positions, by rows. The last row is always
+	/// `0 0 1`.
+	pub calibration: [f32; 6],
+}
+
+impl Default for DeviceSettings {
+	fn default() -> Self {
+		Self {
+			ignore: false,
+			left_handed: false,
+			natural_scrolling: false,
+			scroll_button: None,
+			middle_emulation: false,
+			accel_profile: None,
+			accel_speed: None,
+			tap: true,
+			calibration: [1., 0., 0., 0., 1., 0.],
+		}
+	}
+}
+
+/// A pattern selecting devices.
+#[derive(Default)]
+struct DeviceMatch {
+	/// A pattern on the name, where `*` matches any sequence of characters.
+	name: Option<String>,
+	/// The vendor ID.
+	vendor: Option<u16>,
+	/// The product ID.
+	product: Option<u16>,
+	/// A pattern on the path, where `*` matches any sequence of characters.
+	path: Option<String>,
+	/// A capability.
+	capability: Option<Capability>,
+}
+
+impl DeviceMatch {
+	/// Tells whether the given device matches.
+	fn matches(&self, info: &DeviceInfo) -> bool {
+		self.name.as_ref().is_none_or(|p| glob_match(p, &info.name))
+			&& self.vendor.is_none_or(|v| v == info.vendor)
+			&& self.product.is_none_or(|p| p == info.product)
+			&& self.path.as_ref().is_none_or(|p| glob_match(p, &info.path))
+			&& self.capability.is_none_or(|c| info.has_capability(c))
+	}
+}
+
+/// A `[device]` section of the configuration.
+#[derive(Default)]
+struct DeviceSection {
+	/// The devices the section applies to.
+	selector: DeviceMatch,
+	/// The options of the section, in order.
+	options: Vec<(String, String)>,
+}
+
+/// The input configuration.
+#[derive(Default)]
+pub struct InputConfig {
+	/// The list of device sections, in order.
+	sections: Vec<DeviceSection>,
+	/// The settings of keyboard accessibility features.
+	accessx: AccessXSettings,
+}
+
+/// Tells whether the string `s` matches the pattern `pattern`, where `*` matches any sequence of
+/// characters.
+fn glob_match(pattern: &str, s: &str) -> bool {
+	let mut parts = pattern.split('*');
+	// `split` always returns at least one element
+	let first = parts.next().unwrap();
+	let Some(mut rest) = s.strip_prefix(first) else {
+		return false;
+	};
+
+	let parts: Vec<&str> = parts.collect();
+	let Some((last, middle)) = parts.split_last() else {
+		// No wildcard
+		return rest.is_empty();
+	};
+	for p in middle {
+		match rest.find(p) {
+			Some(i) => rest = &rest[(i + p.len())..],
+			None => return false,
+		}
+	}
+
+	rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Parses a boolean value.
+fn parse_bool(val: &str) -> Result<bool, Box<dyn Error>> {
+	match val {
+		"true" | "yes" | "on" | "1" => Ok(true),
+		"false" | "no" | "off" | "0" => Ok(false),
+
+		_ => Err(format!("Invalid boolean `{}`", val).into()),
+	}
+}
+
+/// Parses a hexadecimal identifier, with or without the `0x` prefix.
+fn parse_hex_id(val: &str) -> Result<u16, Box<dyn Error>> {
+	let digits = val.strip_prefix("0x").unwrap_or(val);
+	u16::from_str_radix(digits, 16).map_err(|_| format!("Invalid identifier `{}`", val).into())
+}
+
+/// Parses a number.
+fn parse_number<T: std::str::FromStr>(val: &str) -> Result<T, Box<dyn Error>> {
+	val.parse()
+		.map_err(|_| format!("Invalid number `{}`", val).into())
+}
+
+/// Applies the AccessX option with the given key and value to the given settings.
+fn apply_accessx_option(
+	settings: &mut AccessXSettings,
+	key: &str,
+	val: &str,
+) -> Result<(), Box<dyn Error>> {
+	let millis = |val: &str| parse_number(val).map(Duration::from_millis);
+
+	match key {
+		"sticky_keys" => settings.sticky_keys = parse_bool(val)?,
+		"slow_keys" => settings.slow_keys = parse_bool(val)?,
+		"slow_keys_delay" => settings.slow_keys_delay = millis(val)?,
+		"bounce_keys" => settings.bounce_keys = parse_bool(val)?,
+		"bounce_keys_delay" => settings.bounce_keys_delay = millis(val)?,
+		"mouse_keys" => settings.mouse_keys = parse_bool(val)?,
+		"mouse_keys_delay" => settings.mouse_keys_delay = millis(val)?,
+		"mouse_keys_interval" => settings.mouse_keys_interval = millis(val)?,
+		"mouse_keys_max_speed" => settings.mouse_keys_max_speed = parse_number(val)?,
+		"mouse_keys_time_to_max" => settings.mouse_keys_time_to_max = parse_number(val)?,
+		"gestures" => settings.gestures = parse_bool(val)?,
+
+		_ => return Err(format!("Invalid option `{}`", key).into()),
+	}
+
+	Ok(())
+}
+
+/// Applies the option with the given key and value to the given settings.
+fn apply_option(
+	settings: &mut DeviceSettings,
+	key: &str,
+	val: &str,
+) -> Result<(), Box<dyn Error>> {
+	match key {
+		"ignore" => settings.ignore = parse_bool(val)?,
+		"left_handed" => settings.left_handed = parse_bool(val)?,
+		"natural_scrolling" => settings.natural_scrolling = parse_bool(val)?,
+		"scroll_button" => {
+			settings.scroll_button = match val {
+				"none" => None,
+				_ => match val.parse() {
+					Ok(b @ 1..=3) => Some(b),
+					_ => return Err(format!("Invalid scroll button `{}`", val).into()),
+				},
+			}
+		}
+		"middle_emulation" => settings.middle_emulation = parse_bool(val)?,
+		"accel_profile" => {
+			settings.accel_profile = Some(match val {
+				"classic" => AccelProfile::Classic,
+				"adaptive" => AccelProfile::Adaptive,
+				"flat" => AccelProfile::Flat,
+
+				_ => return Err(format!("Invalid acceleration profile `{}`", val).into()),
+			})
+		}
+		"accel_speed" => {
+			let speed: f32 = val
+				.parse()
+				.map_err(|_| format!("Invalid acceleration speed `{}`", val))?;
+			if !speed.is_finite() || speed <= 0. {
+				return Err(format!("Invalid acceleration speed `{}`", val).into());
+			}
+
+			settings.accel_speed = Some(speed);
+		}
+		"tap" => settings.tap = parse_bool(val)?,
+		"calibration" => {
+			let values = val
+				.split_whitespace()
+				.map(|v| v.parse::<f32>())
+				.collect::<Result<Vec<_>, _>>()
+				.map_err(|_| format!("Invalid calibration matrix `{}`", val))?;
+
+			settings.calibration = values
+				.try_into()
+				.map_err(|_| "A calibration matrix must have 6 values")?;
+		}
+
+		_ => return Err(format!("Invalid option `{}`", key).into()),
+	}
+
+	Ok(())
+}
+
+impl InputConfig {
+	/// Loads the configuration from the file at the given path.
+	///
+	/// If the file doesn't exist, the function returns an empty configuration.
+	pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+		let content = match fs::read_to_string(path) {
+			Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+			Err(e) => return Err(e.into()),
+			Ok(c) => c,
+		};
+
+		let mut config = Self::default();
+		// Tells whether the current section is `[accessx]`. If false, options go to the last
+		// device section
+		let mut in_accessx = false;
+		for (i, line) in content.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			match line {
+				"[device]" => {
+					config.sections.push(DeviceSection::default());
+					in_accessx = false;
+					continue;
+				}
+				"[accessx]" => {
+					in_accessx = true;
+					continue;
+				}
+
+				_ => {}
+			}
+
+			let err = |e: Box<dyn Error>| format!("line {}: {}", i + 1, e);
+
+			let Some((key, val)) = line.split_once('=') else {
+				return Err(err(format!("Invalid line `{}`", line).into()).into());
+			};
+			let (key, val) = (key.trim(), val.trim());
+
+			if in_accessx {
+				apply_accessx_option(&mut config.accessx, key, val).map_err(err)?;
+				continue;
+			}
+			let Some(section) = config.sections.last_mut() else {
+				return Err(err("Option outside of a section".into()).into());
+			};
+
+			let selector = &mut section.selector;
+			match key {
+				"match_name" => selector.name = Some(val.to_owned()),
+				"match_vendor" => selector.vendor = Some(parse_hex_id(val).map_err(err)?),
+				"match_product" => selector.product = Some(parse_hex_id(val).map_err(err)?),
+				"match_path" => selector.path = Some(val.to_owned()),
+				"match_capability" => {
+					selector.capability = Some(Capability::try_from(val).map_err(err)?)
+				}
+
+				_ => {
+					// Checking the option now to report errors at startup
+					apply_option(&mut DeviceSettings::default(), key, val).map_err(err)?;
+					section.options.push((key.to_owned(), val.to_owned()));
+				}
+			}
+		}
+
+		Ok(config)
+	}
+
+	/// Returns the settings of keyboard accessibility features.
+	pub fn get_accessx_settings(&self) -> &AccessXSettings {
+		&self.accessx
+	}
+
+	/// Returns the settings of the given device.
+	pub fn get_settings(&self, info: &DeviceInfo) -> DeviceSettings {
+		let mut settings = DeviceSettings::default();
+
+		for section in self.sections.iter().filter(|s| s.selector.matches(info)) {
+			for (key, val) in &section.options {
+				// Options have been checked when loading
+				let _ = apply_option(&mut settings, key, val);
+			}
+		}
+
+		settings
+	}
+}
@@ -1,14 +1,19 @@
 //! TODO doc
 
+use super::DeviceId;
 use crate::util;
 use std::ffi::c_int;
 use std::ffi::c_short;
 use std::fs::File;
+use std::fs::OpenOptions;
 use std::io;
 use std::io::Read;
+use std::io::Write;
 use std::mem::size_of;
+use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::prelude::AsRawFd;
 use std::path::PathBuf;
+use std::slice;
 
 /*
  * Device properties and quirks
@@ -515,6 +520,64 @@ pub const ABS_MT_TOOL_Y: u16 = 0x3d;
 pub const ABS_MAX: u16 = 0x3f;
 pub const ABS_CNT: u16 = ABS_MAX + 1;
 
+/*
+ * LEDs
+ */
+
+pub const LED_NUML: u16 = 0x00;
+pub const LED_CAPSL: u16 = 0x01;
+pub const LED_SCROLLL: u16 = 0x02;
+pub const LED_COMPOSE: u16 = 0x03;
+pub const LED_KANA: u16 = 0x04;
+pub const LED_SLEEP: u16 = 0x05;
+pub const LED_SUSPEND: u16 = 0x06;
+pub const LED_MUTE: u16 = 0x07;
+pub const LED_MISC: u16 = 0x08;
+pub const LED_MAIL: u16 = 0x09;
+pub const LED_CHARGING: u16 = 0x0a;
+pub const LED_MAX: u16 = 0x0f;
+pub const LED_CNT: u16 = LED_MAX + 1;
+
+/*
+ * Sounds
+ */
+
+pub const SND_CLICK: u16 = 0x00;
+pub const SND_BELL: u16 = 0x01;
+pub const SND_TONE: u16 = 0x02;
+pub const SND_MAX: u16 = 0x07;
+pub const SND_CNT: u16 = SND_MAX + 1;
+
+/// Returns the evdev ioctl command reading `len` bytes with the given number.
+const fn evioc_read(nr: u64, len: usize) -> u64 {
+	(2 << 30) | ((len as u64) << 16) | ((b'E' as u64) << 8) | nr
+}
+
+/// ioctl command: Get the device's ID.
+const EVIOCGID: u64 = evioc_read(0x02, size_of::<InputId>());
+
+/// Returns the ioctl command to get the name of a device, `len` being the size of the buffer.
+const fn eviocgname(len: usize) -> u64 {
+	evioc_read(0x06, len)
+}
+
+/// Returns the ioctl command to get the bitmap of properties of a device, `len` being the size of
+/// the bitmap in bytes.
+const fn eviocgprop(len: usize) -> u64 {
+	evioc_read(0x09, len)
+}
+
+/// Returns the ioctl command to get the bitmap of codes supported by a device for the event type
+/// `ev`, `len` being the size of the bitmap in bytes.
+const fn eviocgbit(ev: u16, len: usize) -> u64 {
+	evioc_read(0x20 + ev as u64, len)
+}
+
+/// Returns the ioctl command to get the range of the absolute axis `abs`.
+const fn eviocgabs(abs: u16) -> u64 {
+	evioc_read(0x40 + abs as u64, size_of::<AbsInfo>())
+}
+
 // TODO Allow buffering of several events at once
 
 /// EvDev notifies events in the format represented by this structure.
@@ -527,8 +590,35 @@ pub struct EvDevInputEvent {
 	pub value: c_int,
 }
 
+/// The identifiers of a device.
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct InputId {
+	pub bustype: u16,
+	pub vendor: u16,
+	pub product: u16,
+	pub version: u16,
+}
+
+/// The range of an absolute axis.
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct AbsInfo {
+	pub value: i32,
+	pub minimum: i32,
+	pub maximum: i32,
+	pub fuzz: i32,
+	pub flat: i32,
+	/// The resolution in units per millimeter.
+	pub resolution: i32,
+}
+
 /// Structure representing an input device.
 pub struct InputDevice {
+	/// The ID of the device.
+	id: DeviceId,
+	/// The path to the device's file.
+	path: PathBuf,
 	/// The device's file.
 	file: File,
 
@@ -540,26 +630,99 @@ pub struct InputDevice {
 
 impl InputDevice {
 	/// Returns a device from the given device file's path.
-	pub fn from_path(path: &PathBuf) -> io::Result<Self> {
+	///
+	/// Arguments:
+	/// - `id` is the ID of the device.
+	/// - `path` is the path to the device file.
+	///
+	/// The device is opened for writing too if possible, to allow feedback such as LEDs.
+	pub fn from_path(id: DeviceId, path: &PathBuf) -> io::Result<Self> {
+		let open = |write| {
+			OpenOptions::new()
+				.read(true)
+				.write(write)
+				.custom_flags(libc::O_NONBLOCK)
+				.open(path)
+		};
+
 		Ok(Self {
-			file: File::open(path)?,
+			id,
+			path: path.clone(),
+			file: open(true).or_else(|_| open(false))?,
 
 			buff: [0; size_of::<EvDevInputEvent>()],
 			cursor: 0,
 		})
 	}
 
-	/// Returns the next event. The function blocks until at least one event is available.
+	/// Returns the ID of the device.
+	pub fn get_id(&self) -> DeviceId {
+		self.id
+	}
+
+	/// Returns the path to the device's file.
+	pub fn get_path(&self) -> &PathBuf {
+		&self.path
+	}
+
+	/// Performs an ioctl reading data into the given object.
+	fn ioctl_read<T>(&self, cmd: u64, obj: &mut T) -> io::Result<()> {
+		let res = unsafe { libc::ioctl(self.file.as_raw_fd(), cmd as _, obj as *mut T) };
+		if res < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		Ok(())
+	}
+
+	/// Returns the name of the device.
+	pub fn get_name(&self) -> io::Result<String> {
+		let mut buf = [0u8; 256];
+		self.ioctl_read(eviocgname(buf.len()), &mut buf)?;
+
+		let len = buf.iter().position(|c| *c == 0).unwrap_or(buf.len());
+		Ok(String::from_utf8_lossy(&buf[..len]).into_owned())
+	}
+
+	/// Returns the identifiers of the device.
+	pub fn get_input_id(&self) -> io::Result<InputId> {
+		let mut id = InputId::default();
+		self.ioctl_read(EVIOCGID, &mut id)?;
+
+		Ok(id)
+	}
+
+	/// Returns the range of the given absolute axis.
+	pub fn get_abs_info(&self, abs: u16) -> io::Result<AbsInfo> {
+		let mut info = AbsInfo::default();
+		self.ioctl_read(eviocgabs(abs), &mut info)?;
+
+		Ok(info)
+	}
+
+	/// Tells whether the device has the given property.
+	pub fn has_property(&self, prop: u16) -> bool {
+		let mut bits = [0u8; INPUT_PROP_CNT as usize / 8];
+		if self.ioctl_read(eviocgprop(bits.len()), &mut bits).is_err() {
+			return false;
+		}
+
+		bits.get(prop as usize / 8)
+			.is_some_and(|b| b & (1 << (prop % 8)) != 0)
+	}
+
+	/// Returns the next event.
 	///
-	/// If EOF has been reached, the function returns None.
+	/// If no complete event is available, the function returns None.
 	pub fn next_event(&mut self) -> io::Result<Option<EvDevInputEvent>> {
-		loop {
-			let len = self.file.read(&mut self.buff[self.cursor..])?;
-			if len == 0 {
-				break;
-			}
+		while self.cursor < self.buff.len() {
+			match self.file.read(&mut self.buff[self.cursor..]) {
+				Ok(0) => break,
+				Ok(len) => self.cursor += len,
 
-			self.cursor += len;
+				Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+				Err(e) => return Err(e),
+			}
 		}
 
 		if self.cursor >= size_of::<EvDevInputEvent>() {
@@ -571,6 +734,49 @@ impl InputDevice {
 			Ok(None)
 		}
 	}
+
+	/// Writes an event to the device, without synchronization.
+	fn write_raw_event(&mut self, r#type: u16, code: u16, value: i32) -> io::Result<()> {
+		let ev = EvDevInputEvent {
+			time: libc::timeval {
+				tv_sec: 0,
+				tv_usec: 0,
+			},
+			r#type: r#type as _,
+			code: code as _,
+			value,
+		};
+		let buf = unsafe {
+			slice::from_raw_parts(&ev as *const _ as *const u8, size_of::<EvDevInputEvent>())
+		};
+
+		self.file.write_all(buf)
+	}
+
+	/// Writes an event to the device, followed by a synchronization event.
+	///
+	/// Arguments:
+	/// - `type` is the type of the event.
+	/// - `code` is the code of the event.
+	/// - `value` is the value of the event.
+	pub fn write_event(&mut self, r#type: u16, code: u16, value: i32) -> io::Result<()> {
+		self.write_raw_event(r#type, code, value)?;
+		self.write_raw_event(EV_SYN, SYN_REPORT, 0)
+	}
+
+	/// Tells whether the device supports the given event code for the given event type.
+	pub fn supports(&self, r#type: u16, code: u16) -> bool {
+		let mut bits = [0u8; 96];
+		if self
+			.ioctl_read(eviocgbit(r#type, bits.len()), &mut bits)
+			.is_err()
+		{
+			return false;
+		}
+
+		bits.get(code as usize / 8)
+			.is_some_and(|b| b & (1 << (code % 8)) != 0)
+	}
 }
 
 impl AsRawFd for InputDevice {
@@ -0,0 +1,472 @@
+//! A filter turns the raw events of a device into inputs, applying the device's settings on the
+//! way.
+//!
+//! Events are grouped in frames ended by a synchronization event. Motions are accumulated over a
+//! frame so that a movement on both axes is reported as a single input.
+
+use super::config::Capability;
+use super::config::DeviceInfo;
+use super::config::DeviceSettings;
+use super::device::AbsInfo;
+use super::device::EvDevInputEvent;
+use super::device::InputDevice;
+use super::device::ABS_X;
+use super::device::ABS_Y;
+use super::device::BTN_LEFT;
+use super::device::BTN_MIDDLE;
+use super::device::BTN_RIGHT;
+use super::device::BTN_TOUCH;
+use super::device::EV_ABS;
+use super::device::EV_KEY;
+use super::device::EV_REL;
+use super::device::EV_SYN;
+use super::device::REL_WHEEL;
+use super::device::REL_X;
+use super::device::REL_Y;
+use super::device::SYN_REPORT;
+use super::Input;
+use super::Keycode;
+use super::MouseButton;
+use super::KEYCODE_OFFSET;
+use std::collections::VecDeque;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The distance of motion, in units, producing one wheel click while the scroll button is held.
+const SCROLL_STEP: f32 = 15.;
+/// The delay during which pressing the second button of a left/right pair emulates the middle
+/// button.
+const MIDDLE_EMULATION_TIMEOUT: Duration = Duration::from_millis(50);
+/// The maximum duration of a touch on a touchpad for it to be considered a tap.
+const TAP_TIMEOUT: Duration = Duration::from_millis(180);
+/// The maximum distance in millimeters a finger may move during a tap.
+const TAP_MOVE_THRESHOLD: f32 = 1.5;
+/// The number of motion units produced by moving a finger by one millimeter on a touchpad.
+const TOUCHPAD_UNITS_PER_MM: f32 = 8.;
+/// The resolution assumed for touchpads not reporting it, in units per millimeter.
+const DEFAULT_TOUCHPAD_RESOLUTION: f32 = 30.;
+
+/// The state of middle button emulation.
+#[derive(Clone, Copy)]
+enum MiddleState {
+	/// No button of the pair is held.
+	Idle,
+	/// A button of the pair has been pressed and the other one may follow before the deadline.
+	Pending {
+		/// The number of the pressed button.
+		button: u8,
+		/// The instant after which the press is reported as is.
+		deadline: Instant,
+	},
+	/// The middle button is being emulated.
+	Emulating {
+		/// The number of buttons of the pair still held.
+		held: u8,
+	},
+}
+
+/// The state of a finger on a touchpad.
+struct Touch {
+	/// The instant at which the finger touched the surface.
+	start: Instant,
+	/// The last position of the finger, if known.
+	last: Option<(i32, i32)>,
+	/// The distance travelled by the finger, in millimeters.
+	distance: f32,
+}
+
+/// The filter of a device.
+pub struct DeviceFilter {
+	/// The settings of the device.
+	settings: DeviceSettings,
+	/// Tells whether the device is a touchpad.
+	touchpad: bool,
+	/// Tells whether the device reports absolute positions.
+	absolute: bool,
+	/// The ranges of the absolute X and Y axes.
+	abs_info: (AbsInfo, AbsInfo),
+
+	/// The inputs ready to be consumed.
+	queue: VecDeque<Input>,
+
+	/// The motion accumulated during the current frame.
+	motion: (f32, f32),
+	/// The fractional parts of motions not reported yet.
+	remainder: (f32, f32),
+	/// The absolute position reported by the device.
+	position: (i32, i32),
+	/// Tells whether the absolute position changed during the current frame.
+	position_changed: bool,
+	/// The finger currently touching the touchpad, if any.
+	touch: Option<Touch>,
+
+	/// If the scroll button is held, tells whether motions happened since its press.
+	scrolling: Option<bool>,
+	/// The vertical motion accumulated while scrolling, which didn't produce a click yet.
+	scroll_accum: f32,
+	/// The state of middle button emulation.
+	middle: MiddleState,
+}
+
+impl DeviceFilter {
+	/// Creates a filter for the given device.
+	///
+	/// Arguments:
+	/// - `dev` is the device.
+	/// - `info` is the information about the device.
+	/// - `settings` is the settings of the device.
+	pub fn new(dev: &InputDevice, info: &DeviceInfo, settings: DeviceSettings) -> Self {
+		let touchpad = info.has_capability(Capability::Touchpad);
+		let absolute = info.has_capability(Capability::Touchscreen);
+		let abs_info = if touchpad || absolute {
+			(
+				dev.get_abs_info(ABS_X).unwrap_or_default(),
+				dev.get_abs_info(ABS_Y).unwrap_or_default(),
+			)
+		} else {
+			Default::default()
+		};
+
+		Self {
+			settings,
+			touchpad,
+			absolute,
+			abs_info,
+
+			queue: VecDeque::new(),
+
+			motion: (0., 0.),
+			remainder: (0., 0.),
+			position: (0, 0),
+			position_changed: false,
+			touch: None,
+
+			scrolling: None,
+			scroll_accum: 0.,
+			middle: MiddleState::Idle,
+		}
+	}
+
+	/// Queues a press or release of the button with the given number.
+	fn emit_button(&mut self, n: u8, pressed: bool) {
+		let Some(button) = MouseButton::from_number(n) else {
+			return;
+		};
+
+		if pressed {
+			self.queue.push_back(Input::ButtonPress(button));
+		} else {
+			self.queue.push_back(Input::ButtonRelease(button));
+		}
+	}
+
+	/// Queues a click of the button with the given number.
+	fn emit_click(&mut self, n: u8) {
+		self.emit_button(n, true);
+		self.emit_button(n, false);
+	}
+
+	/// Queues the given number of wheel clicks. Positive values scroll up.
+	fn emit_wheel(&mut self, mut clicks: i32) {
+		if self.settings.natural_scrolling {
+			clicks = -clicks;
+		}
+
+		let button = if clicks > 0 { 4 } else { 5 };
+		for _ in 0..clicks.unsigned_abs() {
+			self.emit_click(button);
+		}
+	}
+
+	/// Reports the press of the pending button of middle button emulation, if any.
+	fn flush_middle(&mut self) {
+		if let MiddleState::Pending {
+			button, ..
+		} = self.middle
+		{
+			self.middle = MiddleState::Idle;
+			self.emit_button(button, true);
+		}
+	}
+
+	/// Handles a press or release of the physical button with the given number.
+	fn handle_button(&mut self, mut n: u8, pressed: bool) {
+		if self.settings.left_handed {
+			n = match n {
+				1 => 3,
+				3 => 1,
+				n => n,
+			};
+		}
+
+		if self.settings.scroll_button == Some(n) {
+			if pressed {
+				self.scrolling = Some(false);
+				self.scroll_accum = 0.;
+			} else if self.scrolling.take() == Some(false) {
+				// The button has been clicked without moving
+				self.flush_middle();
+				self.emit_click(n);
+			}
+			return;
+		}
+
+		if !self.settings.middle_emulation || !matches!(n, 1 | 3) {
+			self.flush_middle();
+			self.emit_button(n, pressed);
+			return;
+		}
+
+		match (self.middle, pressed) {
+			(MiddleState::Idle, true) => {
+				self.middle = MiddleState::Pending {
+					button: n,
+					deadline: Instant::now() + MIDDLE_EMULATION_TIMEOUT,
+				};
+			}
+			(MiddleState::Idle, false) => self.emit_button(n, false),
+
+			(
+				MiddleState::Pending {
+					button, ..
+				},
+				true,
+			) if button != n => {
+				self.middle = MiddleState::Emulating {
+					held: 2,
+				};
+				self.emit_button(2, true);
+			}
+			(
+				MiddleState::Pending {
+					button, ..
+				},
+				false,
+			) if button == n => {
+				self.middle = MiddleState::Idle;
+				self.emit_click(n);
+			}
+			(
+				MiddleState::Pending {
+					..
+				},
+				_,
+			) => {
+				self.flush_middle();
+				self.emit_button(n, pressed);
+			}
+
+			(
+				MiddleState::Emulating {
+					held,
+				},
+				false,
+			) => {
+				if held == 2 {
+					self.emit_button(2, false);
+				}
+				self.middle = match held - 1 {
+					0 => MiddleState::Idle,
+					held => MiddleState::Emulating {
+						held,
+					},
+				};
+			}
+			(
+				MiddleState::Emulating {
+					..
+				},
+				true,
+			) => {}
+		}
+	}
+
+	/// Handles a finger touching or leaving a touchpad.
+	fn handle_touch(&mut self, touching: bool) {
+		if touching {
+			self.touch = Some(Touch {
+				start: Instant::now(),
+				last: None,
+				distance: 0.,
+			});
+			return;
+		}
+
+		let Some(touch) = self.touch.take() else {
+			return;
+		};
+		let tap = touch.start.elapsed() < TAP_TIMEOUT && touch.distance < TAP_MOVE_THRESHOLD;
+		if self.settings.tap && tap {
+			self.flush_middle();
+			self.emit_click(1);
+		}
+	}
+
+	/// Returns the resolution of the touchpad in units per millimeter, on each axis.
+	fn get_touchpad_resolution(&self) -> (f32, f32) {
+		let res = |info: &AbsInfo| match info.resolution {
+			r if r > 0 => r as f32,
+			_ => DEFAULT_TOUCHPAD_RESOLUTION,
+		};
+
+		(res(&self.abs_info.0), res(&self.abs_info.1))
+	}
+
+	/// Returns the absolute position of the device, normalized to `0..1` and transformed by the
+	/// calibration matrix.
+	fn get_normalized_position(&self) -> (f32, f32) {
+		let normalize = |val: i32, info: &AbsInfo| {
+			let range = (info.maximum - info.minimum).max(1) as f32;
+			(val - info.minimum) as f32 / range
+		};
+		let x = normalize(self.position.0, &self.abs_info.0);
+		let y = normalize(self.position.1, &self.abs_info.1);
+
+		let m = &self.settings.calibration;
+		let cx = m[0] * x + m[1] * y + m[2];
+		let cy = m[3] * x + m[4] * y + m[5];
+
+		(cx.clamp(0., 1.), cy.clamp(0., 1.))
+	}
+
+	/// Handles the end of a frame, reporting accumulated motions.
+	fn end_frame(&mut self) {
+		if self.position_changed {
+			self.position_changed = false;
+
+			if self.absolute {
+				let (x, y) = self.get_normalized_position();
+				self.queue.push_back(Input::AbsoluteMove {
+					x,
+					y,
+				});
+			}
+
+			let (res_x, res_y) = self.get_touchpad_resolution();
+			if let Some(touch) = self.touch.as_mut().filter(|_| self.touchpad) {
+				if let Some((last_x, last_y)) = touch.last {
+					let dx = (self.position.0 - last_x) as f32 / res_x;
+					let dy = (self.position.1 - last_y) as f32 / res_y;
+					touch.distance += dx.hypot(dy);
+
+					self.motion.0 += dx * TOUCHPAD_UNITS_PER_MM;
+					self.motion.1 += dy * TOUCHPAD_UNITS_PER_MM;
+				}
+				touch.last = Some(self.position);
+			}
+		}
+
+		let motion = std::mem::replace(&mut self.motion, (0., 0.));
+		if motion == (0., 0.) {
+			return;
+		}
+
+		if let Some(moved) = self.scrolling.as_mut() {
+			*moved = true;
+
+			// Moving up scrolls up
+			self.scroll_accum -= motion.1;
+			let clicks = (self.scroll_accum / SCROLL_STEP).trunc();
+			self.scroll_accum -= clicks * SCROLL_STEP;
+			self.emit_wheel(clicks as _);
+			return;
+		}
+
+		let x = motion.0 + self.remainder.0;
+		let y = motion.1 + self.remainder.1;
+		self.remainder = (x.fract(), y.fract());
+		let (delta_x, delta_y) = (x.trunc() as i32, y.trunc() as i32);
+
+		if (delta_x, delta_y) != (0, 0) {
+			self.flush_middle();
+			self.queue.push_back(Input::RelativeMove {
+				delta_x,
+				delta_y,
+			});
+		}
+	}
+
+	/// Handles the given event from the device.
+	pub fn push_event(&mut self, ev: EvDevInputEvent) {
+		let code = ev.code as u16;
+
+		match ev.r#type as u16 {
+			EV_SYN if code == SYN_REPORT => self.end_frame(),
+
+			EV_REL => match code {
+				REL_X => self.motion.0 += ev.value as f32,
+				REL_Y => self.motion.1 += ev.value as f32,
+				REL_WHEEL => self.emit_wheel(ev.value),
+
+				_ => {}
+			},
+
+			EV_ABS => match code {
+				ABS_X => {
+					self.position.0 = ev.value;
+					self.position_changed = true;
+				}
+				ABS_Y => {
+					self.position.1 = ev.value;
+					self.position_changed = true;
+				}
+
+				_ => {}
+			},
+
+			// Autorepeat from the kernel is ignored
+			EV_KEY if ev.value == 2 => {}
+
+			EV_KEY => {
+				let pressed = ev.value != 0;
+
+				match code {
+					BTN_LEFT => self.handle_button(1, pressed),
+					BTN_MIDDLE => self.handle_button(2, pressed),
+					BTN_RIGHT => self.handle_button(3, pressed),
+
+					// On a touchscreen, touching clicks
+					BTN_TOUCH if self.absolute => self.emit_button(1, pressed),
+					BTN_TOUCH if self.touchpad => self.handle_touch(pressed),
+
+					_ if code + KEYCODE_OFFSET <= Keycode::MAX as u16 => {
+						let keycode = (code + KEYCODE_OFFSET) as Keycode;
+
+						if pressed {
+							self.queue.push_back(Input::KeyPress(keycode));
+						} else {
+							self.queue.push_back(Input::KeyRelease(keycode));
+						}
+					}
+
+					_ => {}
+				}
+			}
+
+			_ => {}
+		}
+	}
+
+	/// Returns the next instant at which the filter has to be ticked, if any.
+	pub fn get_deadline(&self) -> Option<Instant> {
+		match self.middle {
+			MiddleState::Pending {
+				deadline, ..
+			} => Some(deadline),
+
+			_ => None,
+		}
+	}
+
+	/// Handles the timers of the filter that are due.
+	pub fn tick(&mut self) {
+		if self.get_deadline().is_some_and(|d| d <= Instant::now()) {
+			self.flush_middle();
+		}
+	}
+
+	/// Consumes and returns the next input, if any.
+	pub fn next_input(&mut self) -> Option<Input> {
+		self.queue.pop_front()
+	}
+}
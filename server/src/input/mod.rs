@@ -6,14 +6,39 @@
 //! - A touchpad
 //! - A touchscreen
 
+pub mod config;
 pub mod device;
+pub mod filter;
 
 use crate::poll::PollHandler;
-use device::EvDevInputEvent;
+use crate::util;
+use config::DeviceInfo;
+use config::DeviceSettings;
+use config::InputConfig;
 use device::InputDevice;
+use device::EV_LED;
+use device::EV_SND;
+use device::LED_CAPSL;
+use device::LED_COMPOSE;
+use device::LED_KANA;
+use device::LED_NUML;
+use device::LED_SCROLLL;
+use device::SND_BELL;
+use device::SND_CLICK;
+use device::SND_TONE;
+use filter::DeviceFilter;
+use std::ffi::CString;
 use std::fs;
+use std::fs::File;
 use std::io;
-use std::os::unix::io::AsRawFd;
+use std::io::Read;
+use std::mem;
+use std::mem::size_of;
+use std::os::fd::FromRawFd;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
 
 /// The path to the directory containing evdev device files.
 const EV_DEV_DIR: &str = "/dev/input";
@@ -21,14 +46,23 @@ const EV_DEV_DIR: &str = "/dev/input";
 /// A keycode.
 pub type Keycode = u8;
 
+/// The ID of an input device.
+pub type DeviceId = u32;
+
+/// The offset between evdev key codes and X keycodes.
+pub const KEYCODE_OFFSET: u16 = 8;
+
+/// The evdev LED associated with each LED of the protocol, starting from LED 1.
+const LEDS: [u16; 5] = [LED_CAPSL, LED_NUML, LED_SCROLLL, LED_COMPOSE, LED_KANA];
+
 /// Enumeration of mouse button.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MouseButton {
 	/// Left click.
 	Button1,
-	/// Right click.
-	Button2,
 	/// Middle click.
+	Button2,
+	/// Right click.
 	Button3,
 	/// Scroll up.
 	Button4,
@@ -54,35 +88,85 @@ pub enum Input {
 		delta_y: i32,
 	},
 
-	/// Moving the cursor to an absolute position.
+	/// Moving the cursor to an absolute position on the virtual desktop.
 	AbsoluteMove {
-		/// The X position.
-		x: u32,
-		/// The Y position.
-		y: u32,
+		/// The X position, from `0` (left edge) to `1` (right edge).
+		x: f32,
+		/// The Y position, from `0` (top edge) to `1` (bottom edge).
+		y: f32,
 	},
 
 	/// Mouse button press.
 	ButtonPress(MouseButton),
 	/// Mouse button release.
 	ButtonRelease(MouseButton),
-	// TODO touchpad
 }
 
-impl TryFrom<EvDevInputEvent> for Input {
-	type Error = ();
+/// Feedback given to the user through input devices.
+#[derive(Clone, Debug)]
+pub enum Feedback {
+	/// Sets the state of the LEDs. Bit `n` of the mask is LED `n + 1` in the protocol.
+	Leds(u32),
+	/// Rings the bell.
+	Bell {
+		/// The volume in percent.
+		volume: u8,
+		/// The pitch in Hz.
+		pitch: u16,
+		/// The duration in milliseconds.
+		duration: u16,
+	},
+	/// Emits a key click with the given volume in percent.
+	Click(u8),
+}
 
-	fn try_from(ev: EvDevInputEvent) -> Result<Self, Self::Error> {
-		// TODO
-		println!("input: {} {} {}", ev.r#type, ev.code, ev.value);
-		Err(())
-	}
+/// A change in the set of input devices.
+#[derive(Debug)]
+pub enum DeviceChange {
+	/// A device has been added.
+	Added {
+		/// The ID of the device.
+		id: DeviceId,
+		/// The settings of the device.
+		settings: DeviceSettings,
+	},
+	/// The device with the given ID has been removed.
+	Removed(DeviceId),
 }
 
 /// Structure managing input devices.
 pub struct InputManager {
-	/// The list of devices.
-	devs: Vec<InputDevice>,
+	/// The input configuration.
+	config: InputConfig,
+	/// The list of devices, along with their filters.
+	devs: Vec<(InputDevice, DeviceFilter)>,
+	/// The ID of the next device to be added.
+	next_id: DeviceId,
+	/// The inotify instance watching the devices directory, if available.
+	inotify: Option<File>,
+	/// The changes in the set of devices which have not been consumed yet.
+	changes: Vec<DeviceChange>,
+
+	/// The instant at which the bell currently ringing stops.
+	bell_end: Option<Instant>,
+}
+
+/// Creates an inotify instance watching for devices being added to the devices directory.
+fn watch_devices() -> io::Result<File> {
+	let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+	if fd < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	let file = unsafe { File::from_raw_fd(fd) };
+
+	// Permissions on device files may be set after their creation, hence `IN_ATTRIB`
+	let path = CString::new(EV_DEV_DIR).unwrap();
+	let mask = libc::IN_CREATE | libc::IN_ATTRIB;
+	if unsafe { libc::inotify_add_watch(fd, path.as_ptr(), mask) } < 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	Ok(file)
 }
 
 impl InputManager {
@@ -90,8 +174,26 @@ impl InputManager {
 	///
 	/// The function registers devices to the given poll handler in order to wake it up when a
 	/// device is ready for reading.
-	pub fn new(poll: &mut PollHandler) -> io::Result<Self> {
-		let mut devs = vec![];
+	///
+	/// `config` is the input configuration, used to set up devices when they are added.
+	pub fn new(config: InputConfig, poll: &mut PollHandler) -> io::Result<Self> {
+		let inotify = watch_devices()
+			.inspect_err(|e| eprintln!("Cannot watch for new input devices: {}", e))
+			.ok();
+		if let Some(inotify) = &inotify {
+			poll.add_fd(inotify);
+		}
+
+		let mut manager = Self {
+			config,
+			devs: vec![],
+			next_id: 0,
+			inotify,
+			changes: vec![],
+
+			bell_end: None,
+		};
+
 		for ent in fs::read_dir(EV_DEV_DIR)? {
 			let ent = ent?;
 			let ent_type = ent.file_type()?;
@@ -99,56 +201,209 @@ impl InputManager {
 				continue;
 			}
 
-			let path = ent.path();
-			let result = InputDevice::from_path(&path);
+			manager.add_device(&ent.path(), poll);
+		}
+
+		Ok(manager)
+	}
+
+	/// Returns the input configuration.
+	pub fn get_config(&self) -> &InputConfig {
+		&self.config
+	}
+
+	/// Acquires the device at the given path and sets it up according to the configuration.
+	///
+	/// If the device has already been acquired, if it is not an evdev device or if it is ignored
+	/// by the configuration, the function does nothing.
+	fn add_device(&mut self, path: &Path, poll: &mut PollHandler) {
+		let is_evdev = path
+			.file_name()
+			.and_then(|n| n.to_str())
+			.is_some_and(|n| n.starts_with("event"));
+		if !is_evdev || self.devs.iter().any(|(d, _)| d.get_path() == path) {
+			return;
+		}
+
+		let dev = match InputDevice::from_path(self.next_id, &PathBuf::from(path)) {
+			Ok(dev) => dev,
+
+			Err(e) => {
+				eprintln!("Cannot acquire input `{}`: {}", path.display(), e);
+				return;
+			}
+		};
+
+		let info = DeviceInfo::from_device(&dev);
+		let settings = self.config.get_settings(&info);
+		if settings.ignore {
+			println!("Ignoring input: {} ({})", path.display(), info.name);
+			return;
+		}
+		println!("Acquired input: {} ({})", path.display(), info.name);
+
+		poll.add_fd(&dev);
+		let filter = DeviceFilter::new(&dev, &info, settings.clone());
+		self.changes.push(DeviceChange::Added {
+			id: self.next_id,
+			settings,
+		});
+		self.devs.push((dev, filter));
+		self.next_id += 1;
+	}
+
+	/// Handles devices being plugged in since the last call.
+	///
+	/// The function registers new devices to the given poll handler.
+	pub fn handle_hotplug(&mut self, poll: &mut PollHandler) {
+		let Some(inotify) = &mut self.inotify else {
+			return;
+		};
 
-			let dev = match result {
-				Ok(dev) => {
-					println!("Acquired input: {}", path.display());
-					dev
+		let mut buf = [0u8; 4096];
+		let len = match inotify.read(&mut buf) {
+			Ok(len) => len,
+
+			Err(e) if e.kind() == io::ErrorKind::WouldBlock => return,
+			Err(e) => {
+				eprintln!("Cannot watch for new input devices: {}", e);
+				return;
+			}
+		};
+
+		let mut paths = vec![];
+		let mut off = 0;
+		let hdr_size = size_of::<libc::inotify_event>();
+		while off + hdr_size <= len {
+			let ev: &libc::inotify_event = unsafe { util::reinterpret(&buf[off]) };
+			let name = &buf[(off + hdr_size)..(off + hdr_size + ev.len as usize)];
+			off += hdr_size + ev.len as usize;
+
+			let name_len = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+			let name = String::from_utf8_lossy(&name[..name_len]);
+			paths.push(Path::new(EV_DEV_DIR).join(name.as_ref()));
+		}
+
+		for path in paths {
+			self.add_device(&path, poll);
+		}
+	}
+
+	/// Takes the changes in the set of devices that happened since the last call.
+	pub fn take_device_changes(&mut self) -> Vec<DeviceChange> {
+		mem::take(&mut self.changes)
+	}
+
+	/// Consumes and returns the next input, along with the ID of the device it comes from. If no
+	/// input is available, the function returns None.
+	///
+	/// Devices that fail are removed and unregistered from the given poll handler.
+	pub fn next_input(&mut self, poll: &mut PollHandler) -> Option<(DeviceId, Input)> {
+		let mut i = 0;
+		while i < self.devs.len() {
+			let (dev, filter) = &mut self.devs[i];
+
+			filter.tick();
+			let failed = loop {
+				if let Some(input) = filter.next_input() {
+					return Some((dev.get_id(), input));
 				}
 
-				Err(e) => {
-					eprintln!("Cannot acquire input `{}`: {}", path.display(), e);
-					continue;
+				match dev.next_event() {
+					Ok(Some(ev)) => filter.push_event(ev),
+					Ok(None) => break false,
+
+					Err(e) => {
+						eprintln!("Lost input `{}`: {}", dev.get_path().display(), e);
+						break true;
+					}
 				}
 			};
 
-			poll.add_fd(&dev);
-			devs.push(dev);
+			if failed {
+				let (dev, _) = self.devs.remove(i);
+				poll.remove_fd(&dev);
+				self.changes.push(DeviceChange::Removed(dev.get_id()));
+			} else {
+				i += 1;
+			}
 		}
 
-		// TODO Init inotify (hotplug)
-
-		Ok(Self {
-			devs,
-		})
+		None
 	}
 
-	/// Consumes and returns the next input. If no input is available, the function returns None.
-	pub fn next_input(&mut self) -> io::Result<Option<Input>> {
-		// TODO Clean and Optimize
+	/// Gives the given feedback on every device supporting it.
+	///
+	/// Feedback is not essential, so errors are ignored.
+	pub fn apply_feedback(&mut self, feedback: &Feedback) {
+		match feedback {
+			Feedback::Leds(mask) => {
+				for (d, _) in &mut self.devs {
+					for (i, led) in LEDS.iter().enumerate() {
+						if d.supports(EV_LED, *led) {
+							let on = mask & (1 << i) != 0;
+							let _ = d.write_event(EV_LED, *led, on as _);
+						}
+					}
+				}
+			}
 
-		let mut poll_handler = PollHandler::new();
-		for d in &mut self.devs {
-			println!("-> {}", d.as_raw_fd());
-			poll_handler.add_fd(d);
-		}
-		let fds = poll_handler.poll();
-		println!("=> {:?}", fds);
+			Feedback::Bell {
+				volume,
+				pitch,
+				duration,
+			} => {
+				// Evdev has no volume control
+				if *volume == 0 {
+					return;
+				}
 
-		for d in &mut self.devs {
-			if !fds.iter().any(|f| *f == d.as_raw_fd()) {
-				continue;
+				for (d, _) in &mut self.devs {
+					if d.supports(EV_SND, SND_TONE) {
+						let _ = d.write_event(EV_SND, SND_TONE, *pitch as _);
+					} else if d.supports(EV_SND, SND_BELL) {
+						let _ = d.write_event(EV_SND, SND_BELL, 1);
+					}
+				}
+				self.bell_end = Some(Instant::now() + Duration::from_millis(*duration as _));
 			}
 
-			if let Some(i) = d.next_event()? {
-				if let Ok(i) = i.try_into() {
-					return Ok(Some(i));
+			Feedback::Click(volume) => {
+				if *volume == 0 {
+					return;
+				}
+
+				for (d, _) in &mut self.devs {
+					if d.supports(EV_SND, SND_CLICK) {
+						let _ = d.write_event(EV_SND, SND_CLICK, 1);
+					}
 				}
 			}
 		}
+	}
+
+	/// Returns the next instant at which the manager has to be ticked, if any.
+	pub fn get_next_deadline(&self) -> Option<Instant> {
+		self.devs
+			.iter()
+			.filter_map(|(_, f)| f.get_deadline())
+			.chain(self.bell_end)
+			.min()
+	}
 
-		Ok(None)
+	/// Updates timed feedback, such as stopping the bell.
+	pub fn tick(&mut self) {
+		if !self.bell_end.is_some_and(|end| end <= Instant::now()) {
+			return;
+		}
+		self.bell_end = None;
+
+		for (d, _) in &mut self.devs {
+			if d.supports(EV_SND, SND_TONE) {
+				let _ = d.write_event(EV_SND, SND_TONE, 0);
+			} else if d.supports(EV_SND, SND_BELL) {
+				let _ = d.write_event(EV_SND, SND_BELL, 0);
+			}
+		}
 	}
 }
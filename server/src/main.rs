@@ -4,6 +4,7 @@
 #![feature(step_trait)]
 
 pub mod ctx;
+pub mod drawing;
 pub mod extension;
 pub mod id_allocator;
 pub mod input;
@@ -11,19 +12,27 @@ pub mod net;
 pub mod output;
 pub mod poll;
 pub mod protocol;
+pub mod region;
 pub mod screens_layout;
 pub mod util;
 
 use ctx::client::Client;
+use ctx::keyboard::DEFAULT_REPEAT_DELAY;
+use ctx::keyboard::DEFAULT_REPEAT_INTERVAL;
+use ctx::pointer::DEFAULT_MOTION_BUFFER_SIZE;
 use ctx::Context;
 use id_allocator::IDAllocator;
+use input::config::InputConfig;
 use input::InputManager;
 use net::Listener;
 use output::card::DRICard;
 use poll::PollHandler;
 use std::env;
 use std::path::Path;
+use std::path::PathBuf;
 use std::process::exit;
+use std::time::Duration;
+use std::time::Instant;
 
 /// The release number.
 pub const RELEASE_NUMBER: u32 = 0;
@@ -38,6 +47,17 @@ struct Args {
 
 	/// Tells whether the cursor is enabled.
 	cursor: bool,
+
+	/// The number of entries kept in the pointer's motion history.
+	motion_buffer_size: usize,
+
+	/// The delay before a held key starts repeating.
+	repeat_delay: Duration,
+	/// The interval between two repetitions of a held key.
+	repeat_interval: Duration,
+
+	/// The path to the input configuration file.
+	input_config: PathBuf,
 }
 
 impl Args {
@@ -48,6 +68,13 @@ impl Args {
 			network: false,
 
 			cursor: true,
+
+			motion_buffer_size: DEFAULT_MOTION_BUFFER_SIZE,
+
+			repeat_delay: DEFAULT_REPEAT_DELAY,
+			repeat_interval: DEFAULT_REPEAT_INTERVAL,
+
+			input_config: PathBuf::from(input::config::CONFIG_PATH),
 		}
 	}
 }
@@ -65,6 +92,16 @@ fn parse_display(s: &str) -> Result<usize, String> {
 		.map_err(|_| format!("Invalid display `{}`", s))
 }
 
+/// Parses a duration in milliseconds from the value of the given argument.
+fn parse_millis(arg: &str, val: Option<String>) -> Result<Duration, String> {
+	let val = val.ok_or(format!("Missing value for `{}`", arg))?;
+	let millis = val
+		.parse()
+		.map_err(|_| format!("Invalid value `{}` for `{}`", val, arg))?;
+
+	Ok(Duration::from_millis(millis))
+}
+
 /// Parses command line arguments.
 fn parse_args() -> Result<Args, String> {
 	let mut args = Args::default();
@@ -74,11 +111,23 @@ fn parse_args() -> Result<Args, String> {
 		args.display = parse_display(&disp)?;
 	}
 
-	let iter = env::args().skip(1);
-	for arg in iter {
+	let mut iter = env::args().skip(1);
+	while let Some(arg) = iter.next() {
 		match arg.as_str() {
 			"-network" => args.network = true,
 			"-nocursor" => args.cursor = false,
+			"-motionbuffer" => {
+				let size = iter.next().ok_or("Missing value for `-motionbuffer`")?;
+				args.motion_buffer_size = size
+					.parse()
+					.map_err(|_| format!("Invalid motion buffer size `{}`", size))?;
+			}
+			"-ardelay" => args.repeat_delay = parse_millis(&arg, iter.next())?,
+			"-arinterval" => args.repeat_interval = parse_millis(&arg, iter.next())?,
+			"-inputconfig" => {
+				let path = iter.next().ok_or("Missing value for `-inputconfig`")?;
+				args.input_config = PathBuf::from(path);
+			}
 
 			_ if matches!(arg.chars().next(), Some(':')) => {
 				args.display = parse_display(&arg)?;
@@ -108,16 +157,27 @@ fn main() {
 	// Scanning for DRI cards
 	let dri_cards = DRICard::scan();
 
+	// Reading input configuration
+	let input_config = InputConfig::load(&args.input_config).unwrap_or_else(|e| {
+		eprintln!("error reading input configuration: {}", e);
+		exit(1);
+	});
+
 	let mut poll = PollHandler::new();
 
 	// Scanning for input devices
-	let mut input_manager = InputManager::new(&mut poll).unwrap_or_else(|e| {
+	let mut input_manager = InputManager::new(input_config, &mut poll).unwrap_or_else(|e| {
 		eprintln!("error initializing input manager: {}", e);
 		exit(1);
 	});
 
 	// Creating context
 	let mut ctx = Context::new();
+	ctx.set_motion_buffer_size(args.motion_buffer_size);
+	ctx.get_keyboard_mut()
+		.set_repeat_rate(args.repeat_delay, args.repeat_interval);
+	ctx.get_accessx_mut()
+		.set_settings(input_manager.get_config().get_accessx_settings().clone());
 	ctx.init_screens(&dri_cards, None); // TODO read layout from config if present
 
 	// Creating listener
@@ -137,7 +197,12 @@ fn main() {
 	let mut client_id_allocator = IDAllocator::from_range(0..8192);
 	loop {
 		// Waiting until something has to be done
-		poll.poll();
+		let deadline = [ctx.get_next_deadline(), input_manager.get_next_deadline()]
+			.into_iter()
+			.flatten()
+			.min();
+		let timeout = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+		let ready = poll.poll(timeout);
 
 		// TODO Add a maximum number of clients
 
@@ -158,15 +223,31 @@ fn main() {
 		}
 
 		// Ticking clients
-		ctx.tick_clients(&mut poll);
+		ctx.tick_clients(&mut poll, &ready);
 
 		// Handle inputs
-		// TODO handle error
-		while let Some(input) = input_manager.next_input().unwrap() {
-			// TODO
-			println!("input: {input:?}");
+		input_manager.handle_hotplug(&mut poll);
+		for change in input_manager.take_device_changes() {
+			ctx.handle_device_change(change);
+		}
+		while let Some((device, input)) = input_manager.next_input(&mut poll) {
+			ctx.handle_input(device, input);
 		}
+		for change in input_manager.take_device_changes() {
+			ctx.handle_device_change(change);
+		}
+
+		// Handle timers and give feedback to the user
+		ctx.tick_timers();
+		for feedback in ctx.take_feedback() {
+			input_manager.apply_feedback(&feedback);
+		}
+		input_manager.tick();
+
+		// Events generated since clients were ticked are sent before waiting again
+		ctx.flush_clients(&mut poll);
 
-		// TODO ctx.render();
+		// Displaying what was drawn during the iteration
+		ctx.render();
 	}
 }
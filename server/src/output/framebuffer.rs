@@ -77,6 +77,8 @@ pub struct Framebuffer<'a> {
 	buff: Option<NonNull<u32>>,
 	/// The length of the buffer in bytes.
 	buff_len: usize,
+	/// The number of bytes between the beginnings of two consecutive rows.
+	pitch: u32,
 }
 
 impl<'a> Framebuffer<'a> {
@@ -120,6 +122,7 @@ impl<'a> Framebuffer<'a> {
 
 			buff: None,
 			buff_len: dumb_buff.size as _,
+			pitch: dumb_buff.pitch,
 		})
 	}
 
@@ -168,6 +171,13 @@ impl<'a> Framebuffer<'a> {
 	pub fn get_buffer_len(&self) -> usize {
 		self.buff_len / size_of::<u32>()
 	}
+
+	/// Returns the number of bytes between the beginnings of two consecutive rows.
+	///
+	/// The driver may pad rows, so that the pitch can be larger than the width of a row.
+	pub fn get_pitch(&self) -> usize {
+		self.pitch as _
+	}
 }
 
 impl<'a> Framebuffer<'a> {
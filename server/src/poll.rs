@@ -2,6 +2,7 @@
 //! Instead, the process is put to sleep by the kernel until something is available for reading.
 
 use std::os::unix::prelude::AsRawFd;
+use std::time::Duration;
 
 /// The poll handler takes a list of objects working on a file descriptor and allows to wait for
 /// events on them.
@@ -40,10 +41,17 @@ impl PollHandler {
 	/// Polls on every registered file descriptors, blocking until at least one file descriptor is
 	/// ready.
 	///
+	/// `timeout` is the maximum duration to wait for. If None, the function waits indefinitely.
+	///
 	/// The function returns the list of file descriptors ready for reading.
-	pub fn poll(&mut self) -> Vec<i32> {
+	pub fn poll(&mut self, timeout: Option<Duration>) -> Vec<i32> {
+		// Rounding up to avoid waking up right before the deadline
+		let timeout = timeout
+			.map(|t| t.as_micros().div_ceil(1000).min(i32::MAX as _) as i32)
+			.unwrap_or(-1);
+
 		unsafe {
-			libc::poll(self.fds.as_mut_ptr(), self.fds.len() as _, -1);
+			libc::poll(self.fds.as_mut_ptr(), self.fds.len() as _, timeout);
 		}
 
 		// Remove invalid (closed?) file descriptors from the list
@@ -1,43 +1,462 @@
 //! This module implements events.
-
-// TODO
+//!
+//! Every event is 32 bytes long on the wire. The sequence number field is filled when the event
+//! is actually sent to the client.
 
 /// Enumeration of events.
+#[derive(Clone, Copy, Debug)]
+#[repr(u8)]
 pub enum Event {
-	KeyPress,
-	KeyRelease,
-	ButtonPress,
-	ButtonRelease,
-	MotionNotify,
-
-	EnterNotify,
-	LeaveNotify,
-
-	FocusIn,
-	FocusOut,
-
-	KeymapNotify,
-	Expose,
-	GraphicsExposure,
-	NoExposure,
-	VisibilityNotify,
-	CreateNotify,
-	DestroyNotify,
-	UnmapNotify,
-	MapNotify,
-	MapRequest,
-	ReparentNotify,
-	ConfigureNotify,
-	GravityNotify,
-	ResizeRequest,
-	ConfigureRequest,
-	CirculateNotify,
-	CirculateRequest,
-	PropertyNotify,
-	SelectionClear,
-	SelectionRequest,
-	SelectionNotify,
-	ColormapNotify,
-	MappingNotify,
-	ClientMessage,
+	KeyPress = 2,
+	KeyRelease = 3,
+	ButtonPress = 4,
+	ButtonRelease = 5,
+	MotionNotify = 6,
+
+	EnterNotify = 7,
+	LeaveNotify = 8,
+
+	FocusIn = 9,
+	FocusOut = 10,
+
+	KeymapNotify = 11,
+	Expose = 12,
+	GraphicsExposure = 13,
+	NoExposure = 14,
+	VisibilityNotify = 15,
+	CreateNotify = 16,
+	DestroyNotify = 17,
+	UnmapNotify = 18,
+	MapNotify = 19,
+	MapRequest = 20,
+	ReparentNotify = 21,
+	ConfigureNotify = 22,
+	GravityNotify = 23,
+	ResizeRequest = 24,
+	ConfigureRequest = 25,
+	CirculateNotify = 26,
+	CirculateRequest = 27,
+	PropertyNotify = 28,
+	SelectionClear = 29,
+	SelectionRequest = 30,
+	SelectionNotify = 31,
+	ColormapNotify = 32,
+	ClientMessage = 33,
+	MappingNotify = 34,
+}
+
+/// Crossing detail: the pointer left for, or came from, an ancestor.
+pub const NOTIFY_ANCESTOR: u8 = 0;
+/// Crossing detail: the window is between the origin and the destination windows.
+pub const NOTIFY_VIRTUAL: u8 = 1;
+/// Crossing detail: the pointer left for, or came from, an inferior.
+pub const NOTIFY_INFERIOR: u8 = 2;
+/// Crossing detail: neither window is an inferior of the other.
+pub const NOTIFY_NONLINEAR: u8 = 3;
+/// Crossing detail: like `NOTIFY_NONLINEAR`, on a window between the two.
+pub const NOTIFY_NONLINEAR_VIRTUAL: u8 = 4;
+
+/// Crossing mode: normal pointer motion.
+pub const NOTIFY_NORMAL: u8 = 0;
+
+/// Event shared by `KeyPress`, `KeyRelease`, `ButtonPress`, `ButtonRelease` and
+/// `MotionNotify`.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct InputEvent {
+	/// The event code.
+	pub code: u8,
+	/// The keycode, the button or the motion hint.
+	pub detail: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The time at which the event happened in milliseconds.
+	pub time: u32,
+	/// The root window of the source window.
+	pub root: u32,
+	/// The window the event is reported to.
+	pub event: u32,
+	/// The child of the event window on the path to the source window, if any.
+	pub child: u32,
+
+	/// The X position of the pointer relative to the root window.
+	pub root_x: i16,
+	/// The Y position of the pointer relative to the root window.
+	pub root_y: i16,
+	/// The X position of the pointer relative to the event window.
+	pub event_x: i16,
+	/// The Y position of the pointer relative to the event window.
+	pub event_y: i16,
+
+	/// The state of buttons and modifiers before the event.
+	pub state: u16,
+	/// Tells whether the event window is on the same screen as the root.
+	pub same_screen: u8,
+
+	/// Padding.
+	pub _padding: u8,
+}
+
+/// Event shared by `EnterNotify` and `LeaveNotify`.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct CrossingEvent {
+	/// The event code.
+	pub code: u8,
+	/// The crossing detail.
+	pub detail: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The time at which the event happened in milliseconds.
+	pub time: u32,
+	/// The root window on which the pointer is located.
+	pub root: u32,
+	/// The window the event is reported to.
+	pub event: u32,
+	/// The child of the event window containing the pointer, if any.
+	pub child: u32,
+
+	/// The X position of the pointer relative to the root window.
+	pub root_x: i16,
+	/// The Y position of the pointer relative to the root window.
+	pub root_y: i16,
+	/// The X position of the pointer relative to the event window.
+	pub event_x: i16,
+	/// The Y position of the pointer relative to the event window.
+	pub event_y: i16,
+
+	/// The state of buttons and modifiers.
+	pub state: u16,
+	/// The crossing mode.
+	pub mode: u8,
+	/// Bit 0 tells whether the focus is on the window, bit 1 whether the window is on the same
+	/// screen as the root.
+	pub same_screen_focus: u8,
+}
+
+/// Event `KeymapNotify`, reporting the state of the keyboard after the pointer entered a window.
+///
+/// This event has no sequence number.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct KeymapNotifyEvent {
+	/// The event code.
+	pub code: u8,
+	/// Bitmap of the keys currently pressed, for keycodes 8 to 255.
+	pub keys: [u8; 31],
+}
+
+/// Mapping change: the modifier mapping changed.
+pub const MAPPING_MODIFIER: u8 = 0;
+/// Mapping change: the keyboard mapping changed.
+pub const MAPPING_KEYBOARD: u8 = 1;
+/// Mapping change: the pointer mapping changed.
+pub const MAPPING_POINTER: u8 = 2;
+
+/// Event `MappingNotify`, sent to every client when a mapping changes.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct MappingNotifyEvent {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The mapping that changed.
+	pub request: u8,
+	/// The first keycode whose mapping changed, for keyboard mapping changes.
+	pub first_keycode: u8,
+	/// The number of keycodes whose mapping changed, for keyboard mapping changes.
+	pub count: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 25],
+}
+
+/// Event `GraphicsExposure`, reporting an area of the destination of a copy whose source could
+/// not be read.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct GraphicsExposureEvent {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The destination drawable.
+	pub drawable: u32,
+	/// The X position of the area.
+	pub x: u16,
+	/// The Y position of the area.
+	pub y: u16,
+	/// The width of the area.
+	pub width: u16,
+	/// The height of the area.
+	pub height: u16,
+	/// The minor opcode of the request.
+	pub minor_opcode: u16,
+	/// The number of `GraphicsExposure` events following this one for the same request.
+	pub count: u16,
+	/// The major opcode of the request.
+	pub major_opcode: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 11],
+}
+
+/// Event `NoExposure`, reporting that the whole source of a copy could be read.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct NoExposureEvent {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The destination drawable.
+	pub drawable: u32,
+	/// The minor opcode of the request.
+	pub minor_opcode: u16,
+	/// The major opcode of the request.
+	pub major_opcode: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 21],
+}
+
+/// Event `Expose`, reporting an area of a window whose contents were lost.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct ExposeEvent {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The exposed window.
+	pub window: u32,
+	/// The X position of the area.
+	pub x: u16,
+	/// The Y position of the area.
+	pub y: u16,
+	/// The width of the area.
+	pub width: u16,
+	/// The height of the area.
+	pub height: u16,
+	/// The number of `Expose` events following this one for the same window.
+	pub count: u16,
+
+	/// Padding.
+	pub _padding1: [u8; 14],
+}
+
+/// Event `UnmapNotify`, reporting that a window was unmapped.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct UnmapNotifyEvent {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The window the event is reported on.
+	pub event: u32,
+	/// The unmapped window.
+	pub window: u32,
+	/// Tells whether the window was unmapped because its parent was resized.
+	pub from_configure: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 19],
+}
+
+/// Event `MapNotify`, reporting that a window was mapped.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct MapNotifyEvent {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The window the event is reported on.
+	pub event: u32,
+	/// The mapped window.
+	pub window: u32,
+	/// The `override-redirect` attribute of the window.
+	pub override_redirect: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 19],
+}
+
+/// Event `ReparentNotify`, reporting that a window was moved to another parent.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct ReparentNotifyEvent {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The window the event is reported on.
+	pub event: u32,
+	/// The reparented window.
+	pub window: u32,
+	/// The new parent of the window.
+	pub parent: u32,
+	/// The X position of the window relative to its new parent.
+	pub x: i16,
+	/// The Y position of the window relative to its new parent.
+	pub y: i16,
+	/// The `override-redirect` attribute of the window.
+	pub override_redirect: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 11],
+}
+
+/// Event `ConfigureNotify`, reporting a change of the position, size, border or stacking order of
+/// a window.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct ConfigureNotifyEvent {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The window the event is reported on.
+	pub event: u32,
+	/// The configured window.
+	pub window: u32,
+	/// The sibling just below the window. If zero, the window is at the bottom of the stack.
+	pub above_sibling: u32,
+	/// The X position of the window, relative to its parent.
+	pub x: i16,
+	/// The Y position of the window, relative to its parent.
+	pub y: i16,
+	/// The width of the window.
+	pub width: u16,
+	/// The height of the window.
+	pub height: u16,
+	/// The width of the window's border.
+	pub border_width: u16,
+	/// The `override-redirect` attribute of the window.
+	pub override_redirect: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 5],
+}
+
+/// Event `GravityNotify`, reporting that a window was moved because its parent was resized.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct GravityNotifyEvent {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The window the event is reported on.
+	pub event: u32,
+	/// The moved window.
+	pub window: u32,
+	/// The new X position of the window, relative to its parent.
+	pub x: i16,
+	/// The new Y position of the window, relative to its parent.
+	pub y: i16,
+
+	/// Padding.
+	pub _padding1: [u8; 16],
+}
+
+/// Event `VisibilityNotify`, reporting a change of the visibility of a window.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct VisibilityNotifyEvent {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The window.
+	pub window: u32,
+	/// The new visibility state of the window.
+	pub state: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 23],
+}
+
+/// Event `CirculateNotify`, reporting that a window was restacked by `CirculateWindow`.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct CirculateNotifyEvent {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The window the event is reported on.
+	pub event: u32,
+	/// The restacked window.
+	pub window: u32,
+	/// Unused.
+	pub _unused: u32,
+	/// The new place of the window: `0` on top of its siblings, `1` below them.
+	pub place: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 15],
+}
+
+/// Event `ColormapNotify`, reporting that the colormap of a window changed, or that it was
+/// installed or uninstalled.
+#[derive(Clone)]
+#[repr(C, packed)]
+pub struct ColormapNotifyEvent {
+	/// The event code.
+	pub code: u8,
+	/// Padding.
+	pub _padding0: u8,
+	/// The sequence number.
+	pub seq_nbr: u16,
+
+	/// The window.
+	pub window: u32,
+	/// The colormap of the window, or zero if it was freed.
+	pub colormap: u32,
+	/// `1` if the colormap attribute of the window changed, `0` if the colormap was installed or
+	/// uninstalled.
+	pub new: u8,
+	/// Whether the colormap is installed.
+	pub state: u8,
+
+	/// Padding.
+	pub _padding1: [u8; 18],
 }
@@ -3,6 +3,7 @@
 pub mod connect;
 pub mod error;
 pub mod event;
+pub mod keysym;
 pub mod request;
 
 use error::Error;
@@ -20,6 +21,13 @@ pub const REPLY_TYPE_ERROR: u8 = 0;
 /// Reply type: Normal reply
 pub const REPLY_TYPE_REPLY: u8 = 1;
 
+/// Mapping status: the mapping has been changed
+pub const MAPPING_SUCCESS: u8 = 0;
+/// Mapping status: the mapping of a key or button currently pressed would have changed
+pub const MAPPING_BUSY: u8 = 1;
+/// Mapping status: the server refused the mapping
+pub const MAPPING_FAILED: u8 = 2;
+
 /// The header of a request.
 #[repr(C, packed)]
 pub struct XRequest {
@@ -32,7 +40,7 @@ pub struct XRequest {
 }
 
 /// TODO doc
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u16)]
 pub enum Class {
 	CopyFromParent = 0,
@@ -142,7 +150,7 @@ impl TryFrom<u8> for WinGravity {
 }
 
 /// TODO doc
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum MapState {
 	Unmapped = 0,
@@ -150,6 +158,23 @@ pub enum MapState {
 	Viewable = 2,
 }
 
+/// The visibility of a viewable window, ignoring its inferiors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum VisibilityState {
+	Unobscured = 0,
+	PartiallyObscured = 1,
+	FullyObscured = 2,
+}
+
+/// Tells whether a colormap is installed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ColormapState {
+	Uninstalled = 0,
+	Installed = 1,
+}
+
 /// Enumeration of events.
 #[derive(Clone, Copy, Debug)]
 #[repr(u32)]
@@ -214,27 +239,68 @@ pub enum DeviceEvent {
 }
 
 /// Enumeration of keymasks.
+#[derive(Clone, Copy, Debug)]
+#[repr(u16)]
 pub enum KeyMask {
-	Shift,
-	Lock,
-	Control,
-	Mod1,
-	Mod2,
-	Mod3,
-	Mod4,
-	Mod5,
+	Shift = 0x0001,
+	Lock = 0x0002,
+	Control = 0x0004,
+	Mod1 = 0x0008,
+	Mod2 = 0x0010,
+	Mod3 = 0x0020,
+	Mod4 = 0x0040,
+	Mod5 = 0x0080,
+}
+
+impl KeyMask {
+	/// Returns the mask of the modifier at the given index, in the order of the modifier map.
+	///
+	/// If the index is invalid, the function returns None.
+	pub fn from_index(index: usize) -> Option<Self> {
+		match index {
+			0 => Some(Self::Shift),
+			1 => Some(Self::Lock),
+			2 => Some(Self::Control),
+			3 => Some(Self::Mod1),
+			4 => Some(Self::Mod2),
+			5 => Some(Self::Mod3),
+			6 => Some(Self::Mod4),
+			7 => Some(Self::Mod5),
+			_ => None,
+		}
+	}
 }
 
 /// Enumeration of button masks.
+#[derive(Clone, Copy, Debug)]
+#[repr(u16)]
 pub enum ButMask {
-	Button1,
-	Button2,
-	Button3,
-	Button4,
-	Button5,
+	Button1 = 0x0100,
+	Button2 = 0x0200,
+	Button3 = 0x0400,
+	Button4 = 0x0800,
+	Button5 = 0x1000,
+}
+
+impl ButMask {
+	/// Returns the mask of the logical button with the given number.
+	///
+	/// If the button has no mask, the function returns None.
+	pub fn from_button(button: u8) -> Option<Self> {
+		match button {
+			1 => Some(Self::Button1),
+			2 => Some(Self::Button2),
+			3 => Some(Self::Button3),
+			4 => Some(Self::Button4),
+			5 => Some(Self::Button5),
+			_ => None,
+		}
+	}
 }
 
 /// A 2D point.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
 pub struct Point {
 	/// X position.
 	pub x: i16,
@@ -244,6 +310,7 @@ pub struct Point {
 
 /// A rectangle.
 #[derive(Clone, Debug)]
+#[repr(C)]
 pub struct Rectangle {
 	/// X position.
 	pub x: i16,
@@ -257,6 +324,8 @@ pub struct Rectangle {
 }
 
 /// An arc.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
 pub struct Arc {
 	/// X position.
 	pub x: i16,
@@ -268,9 +337,10 @@ pub struct Arc {
 	/// The height of the arc.
 	pub height: u16,
 
-	/// TODO doc
+	/// The start angle of the arc, in 64ths of a degree, counter-clockwise from 3 o'clock.
 	pub angle1: i16,
-	/// TODO doc
+	/// The extent of the arc relative to its start, in 64ths of a degree. Positive values are
+	/// counter-clockwise.
 	pub angle2: i16,
 }
 
@@ -0,0 +1,39 @@
+//! The `Bell` request rings the bell.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+
+/// Structure representing the request.
+pub struct Bell {
+	/// The volume relative to the base volume of the bell, in percent.
+	percent: i8,
+}
+
+impl Request for Bell {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		ctx.get_keyboard_mut().bell(self.percent);
+		Ok(())
+	}
+}
+
+/// Parses `Bell`.
+///
+/// `percent` is the volume relative to the base volume of the bell.
+pub fn read(_buff: &[u8], percent: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	let percent = percent as i8;
+	if !(-100..=100).contains(&percent) {
+		return Err(Error::Value(percent as _));
+	}
+
+	Ok(Some(Box::new(Bell {
+		percent,
+	})))
+}
@@ -0,0 +1,68 @@
+//! The `ChangeGC` request changes components of a graphics context.
+
+use super::create_gc;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::gc;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `ChangeGC` request.
+#[repr(C, packed)]
+pub struct ChangeGCHdr {
+	/// The ID of the graphics context.
+	gc: u32,
+
+	/// The mask of components being changed.
+	value_mask: u32,
+}
+
+/// Structure representing the request.
+pub struct ChangeGC {
+	/// The ID of the graphics context.
+	gc: u32,
+
+	/// The list of values to set.
+	values: Vec<gc::Value>,
+}
+
+impl Request for ChangeGC {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let id = NonZeroU32::new(self.gc).ok_or(HandleError::Client(Error::GContext(self.gc)))?;
+		let mut gc = ctx
+			.get_gc(id)
+			.ok_or(HandleError::Client(Error::GContext(self.gc)))?
+			.clone();
+
+		// Work on a copy so that the graphics context is left untouched on error
+		create_gc::set_values(ctx, &mut gc, &self.values).map_err(HandleError::Client)?;
+		ctx.add_gc(gc);
+
+		Ok(())
+	}
+}
+
+/// Parses `ChangeGC`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ChangeGCHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &ChangeGCHdr = unsafe { util::reinterpret(&buff[0]) };
+	let values = create_gc::read_values(hdr.value_mask, &buff[size_of::<ChangeGCHdr>()..])?;
+
+	Ok(Some(Box::new(ChangeGC {
+		gc: hdr.gc,
+
+		values,
+	})))
+}
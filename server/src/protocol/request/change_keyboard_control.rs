@@ -0,0 +1,201 @@
+//! The `ChangeKeyboardControl` request changes the settings of the keyboard.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::keyboard::DEFAULT_BELL_DURATION;
+use crate::ctx::keyboard::DEFAULT_BELL_PERCENT;
+use crate::ctx::keyboard::DEFAULT_BELL_PITCH;
+use crate::ctx::keyboard::DEFAULT_KEY_CLICK_PERCENT;
+use crate::ctx::keyboard::MAX_KEYCODE;
+use crate::ctx::keyboard::MIN_KEYCODE;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Auto repeat mode: Off.
+const AUTO_REPEAT_OFF: u32 = 0;
+/// Auto repeat mode: On.
+const AUTO_REPEAT_ON: u32 = 1;
+/// Auto repeat mode: Default.
+const AUTO_REPEAT_DEFAULT: u32 = 2;
+
+/// The header of the request.
+#[repr(C, packed)]
+struct ChangeKeyboardControlHdr {
+	/// The mask of values following the header.
+	value_mask: u32,
+}
+
+/// Structure representing the request.
+#[derive(Default)]
+pub struct ChangeKeyboardControl {
+	/// The volume of key clicks in percent. `-1` restores the default.
+	key_click_percent: Option<i8>,
+	/// The volume of the bell in percent. `-1` restores the default.
+	bell_percent: Option<i8>,
+	/// The pitch of the bell in Hz. `-1` restores the default.
+	bell_pitch: Option<i16>,
+	/// The duration of the bell in milliseconds. `-1` restores the default.
+	bell_duration: Option<i16>,
+	/// The LED to change, starting from 1.
+	led: Option<u8>,
+	/// The new state of the LEDs.
+	led_mode: Option<u32>,
+	/// The key whose autorepeat is changed.
+	key: Option<u8>,
+	/// The new autorepeat mode.
+	auto_repeat_mode: Option<u32>,
+}
+
+/// Returns the given percentage, or the default value if `-1`.
+fn get_percent(val: i8, default: u8) -> Result<u8, HandleError> {
+	match val {
+		-1 => Ok(default),
+		0..=100 => Ok(val as _),
+		_ => Err(HandleError::Client(Error::Value(val as _))),
+	}
+}
+
+/// Returns the given value, or the default value if `-1`.
+fn get_value(val: i16, default: u16) -> Result<u16, HandleError> {
+	match val {
+		-1 => Ok(default),
+		0.. => Ok(val as _),
+		_ => Err(HandleError::Client(Error::Value(val as _))),
+	}
+}
+
+impl Request for ChangeKeyboardControl {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		// Validating everything before changing anything
+		let key_click_percent = self
+			.key_click_percent
+			.map(|v| get_percent(v, DEFAULT_KEY_CLICK_PERCENT))
+			.transpose()?;
+		let bell_percent = self
+			.bell_percent
+			.map(|v| get_percent(v, DEFAULT_BELL_PERCENT))
+			.transpose()?;
+		let bell_pitch = self
+			.bell_pitch
+			.map(|v| get_value(v, DEFAULT_BELL_PITCH))
+			.transpose()?;
+		let bell_duration = self
+			.bell_duration
+			.map(|v| get_value(v, DEFAULT_BELL_DURATION))
+			.transpose()?;
+
+		if let Some(led) = self.led {
+			if !(1..=32).contains(&led) {
+				return Err(HandleError::Client(Error::Value(led as _)));
+			}
+			if self.led_mode.is_none() {
+				return Err(HandleError::Client(Error::Match));
+			}
+		}
+		if let Some(mode) = self.led_mode {
+			if mode > 1 {
+				return Err(HandleError::Client(Error::Value(mode)));
+			}
+		}
+
+		if let Some(key) = self.key {
+			if !(MIN_KEYCODE..=MAX_KEYCODE).contains(&key) {
+				return Err(HandleError::Client(Error::Value(key as _)));
+			}
+			if self.auto_repeat_mode.is_none() {
+				return Err(HandleError::Client(Error::Match));
+			}
+		}
+		if let Some(mode) = self.auto_repeat_mode {
+			if !matches!(mode, AUTO_REPEAT_OFF | AUTO_REPEAT_ON | AUTO_REPEAT_DEFAULT) {
+				return Err(HandleError::Client(Error::Value(mode)));
+			}
+		}
+
+		let keyboard = ctx.get_keyboard_mut();
+
+		let control = keyboard.get_control_mut();
+		if let Some(v) = key_click_percent {
+			control.key_click_percent = v;
+		}
+		if let Some(v) = bell_percent {
+			control.bell_percent = v;
+		}
+		if let Some(v) = bell_pitch {
+			control.bell_pitch = v;
+		}
+		if let Some(v) = bell_duration {
+			control.bell_duration = v;
+		}
+
+		// Autorepeat is enabled by default
+		let auto_repeat = self
+			.auto_repeat_mode
+			.map(|mode| matches!(mode, AUTO_REPEAT_ON | AUTO_REPEAT_DEFAULT));
+		match (self.key, auto_repeat) {
+			(Some(key), Some(enable)) => control.set_auto_repeat(key, enable),
+			(None, Some(enable)) => control.global_auto_repeat = enable,
+			_ => {}
+		}
+
+		if let Some(mode) = self.led_mode {
+			// Without a LED, every LED is changed
+			let mask = match self.led {
+				Some(led) => 1 << (led - 1),
+				None => u32::MAX,
+			};
+
+			let leds = keyboard.get_leds();
+			if mode == 1 {
+				keyboard.set_leds(leds | mask);
+			} else {
+				keyboard.set_leds(leds & !mask);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Parses `ChangeKeyboardControl`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ChangeKeyboardControlHdr>() {
+		return Ok(None);
+	}
+	let hdr: &ChangeKeyboardControlHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	let mut req = ChangeKeyboardControl::default();
+
+	// Each value takes 4 bytes
+	let mut off = size_of::<ChangeKeyboardControlHdr>();
+	for i in (0..8).filter(|i| hdr.value_mask & (1 << i) != 0) {
+		if off + 4 > buff.len() {
+			return Err(Error::Length);
+		}
+		let val = unsafe { *util::reinterpret::<_, u32>(&buff[off]) };
+		off += 4;
+
+		match i {
+			0 => req.key_click_percent = Some(val as i8),
+			1 => req.bell_percent = Some(val as i8),
+			2 => req.bell_pitch = Some(val as i16),
+			3 => req.bell_duration = Some(val as i16),
+			4 => req.led = Some(val as u8),
+			5 => req.led_mode = Some(val),
+			6 => req.key = Some(val as u8),
+			7 => req.auto_repeat_mode = Some(val),
+
+			_ => unreachable!(),
+		}
+	}
+
+	Ok(Some(Box::new(req)))
+}
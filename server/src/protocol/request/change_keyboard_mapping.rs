@@ -0,0 +1,109 @@
+//! The `ChangeKeyboardMapping` request changes the keysyms associated with a range of keycodes.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::keyboard::MAX_KEYCODE;
+use crate::ctx::keyboard::MIN_KEYCODE;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::event;
+use crate::protocol::event::MappingNotifyEvent;
+use crate::protocol::keysym::Keysym;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// The header of the request.
+#[repr(C, packed)]
+struct ChangeKeyboardMappingHdr {
+	/// The first keycode.
+	first_keycode: u8,
+	/// The number of keysyms given for each keycode.
+	keysyms_per_keycode: u8,
+
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct ChangeKeyboardMapping {
+	/// The first keycode.
+	first_keycode: u8,
+	/// The number of keycodes.
+	count: u8,
+	/// The number of keysyms given for each keycode.
+	keysyms_per_keycode: u8,
+
+	/// The keysyms.
+	keysyms: Vec<Keysym>,
+}
+
+impl Request for ChangeKeyboardMapping {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		if self.first_keycode < MIN_KEYCODE {
+			return Err(HandleError::Client(Error::Value(self.first_keycode as _)));
+		}
+		let last = self.first_keycode as u32 + self.count as u32;
+		if self.count > 0 && last - 1 > MAX_KEYCODE as u32 {
+			return Err(HandleError::Client(Error::Value(self.count as _)));
+		}
+		if self.keysyms_per_keycode == 0 {
+			return Err(HandleError::Client(Error::Value(0)));
+		}
+
+		ctx.get_keyboard_mut().get_keymap_mut().set(
+			self.first_keycode,
+			self.keysyms_per_keycode,
+			&self.keysyms,
+		);
+
+		ctx.broadcast_event(&MappingNotifyEvent {
+			code: event::Event::MappingNotify as _,
+			_padding0: 0,
+			seq_nbr: 0,
+
+			request: event::MAPPING_KEYBOARD,
+			first_keycode: self.first_keycode,
+			count: self.count,
+
+			_padding1: [0; 25],
+		});
+
+		Ok(())
+	}
+}
+
+/// Parses `ChangeKeyboardMapping`.
+///
+/// `count` is the number of keycodes to change.
+pub fn read(buff: &[u8], count: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ChangeKeyboardMappingHdr>() {
+		return Ok(None);
+	}
+	let hdr: &ChangeKeyboardMappingHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	let keysyms_len = count as usize * hdr.keysyms_per_keycode as usize;
+	let data_begin = size_of::<ChangeKeyboardMappingHdr>();
+	let data_end = data_begin + keysyms_len * size_of::<Keysym>();
+	if data_end > buff.len() {
+		return Err(Error::Length);
+	}
+
+	let keysyms = buff[data_begin..data_end]
+		.chunks(size_of::<Keysym>())
+		.map(|b| Keysym::from_ne_bytes(b.try_into().unwrap()))
+		.collect();
+
+	Ok(Some(Box::new(ChangeKeyboardMapping {
+		first_keycode: hdr.first_keycode,
+		count,
+		keysyms_per_keycode: hdr.keysyms_per_keycode,
+
+		keysyms,
+	})))
+}
@@ -0,0 +1,105 @@
+//! The `ChangePointerControl` request changes the acceleration of the pointer.
+
+use super::Request;
+use crate::ctx::acceleration::DEFAULT_ACCELERATION_DENOMINATOR;
+use crate::ctx::acceleration::DEFAULT_ACCELERATION_NUMERATOR;
+use crate::ctx::acceleration::DEFAULT_THRESHOLD;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// The header of the request.
+#[repr(C, packed)]
+struct ChangePointerControlHdr {
+	/// The numerator of the acceleration fraction.
+	acceleration_numerator: i16,
+	/// The denominator of the acceleration fraction.
+	acceleration_denominator: i16,
+	/// The threshold above which acceleration applies.
+	threshold: i16,
+	/// Tells whether the acceleration fraction is changed.
+	do_acceleration: u8,
+	/// Tells whether the threshold is changed.
+	do_threshold: u8,
+}
+
+/// Structure representing the request.
+pub struct ChangePointerControl {
+	/// The new acceleration fraction. `-1` restores the default.
+	acceleration: Option<(i16, i16)>,
+	/// The new threshold. `-1` restores the default.
+	threshold: Option<i16>,
+}
+
+impl Request for ChangePointerControl {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		// Validating everything before changing anything
+		let acceleration = match self.acceleration {
+			Some((num, den)) => {
+				let num = match num {
+					-1 => DEFAULT_ACCELERATION_NUMERATOR,
+					0.. => num as u16,
+					_ => return Err(HandleError::Client(Error::Value(num as _))),
+				};
+				let den = match den {
+					-1 => DEFAULT_ACCELERATION_DENOMINATOR,
+					1.. => den as u16,
+					_ => return Err(HandleError::Client(Error::Value(den as _))),
+				};
+
+				Some((num, den))
+			}
+
+			None => None,
+		};
+		let threshold = match self.threshold {
+			Some(-1) => Some(DEFAULT_THRESHOLD),
+			Some(t @ 0..) => Some(t as u16),
+			Some(t) => return Err(HandleError::Client(Error::Value(t as _))),
+			None => None,
+		};
+
+		let control = ctx.get_acceleration_mut().get_control_mut();
+		if let Some((num, den)) = acceleration {
+			control.numerator = num;
+			control.denominator = den;
+		}
+		if let Some(threshold) = threshold {
+			control.threshold = threshold;
+		}
+
+		Ok(())
+	}
+}
+
+/// Parses `ChangePointerControl`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ChangePointerControlHdr>() {
+		return Ok(None);
+	}
+	let hdr: &ChangePointerControlHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	let acceleration = match hdr.do_acceleration {
+		0 => None,
+		1 => Some((hdr.acceleration_numerator, hdr.acceleration_denominator)),
+		v => return Err(Error::Value(v as _)),
+	};
+	let threshold = match hdr.do_threshold {
+		0 => None,
+		1 => Some(hdr.threshold),
+		v => return Err(Error::Value(v as _)),
+	};
+
+	Ok(Some(Box::new(ChangePointerControl {
+		acceleration,
+		threshold,
+	})))
+}
@@ -1,12 +1,18 @@
-//! TODO doc
+//! The `ChangeWindowAttributes` request changes the attributes of a window.
 
 use super::create_window;
 use super::Request;
 use crate::ctx::client::Client;
 use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol;
 use crate::protocol::error::Error;
+use crate::protocol::event::ColormapNotifyEvent;
+use crate::protocol::event::Event;
 use crate::protocol::request::HandleError;
+use crate::protocol::ColormapState;
 use crate::util;
+use std::mem;
 use std::mem::size_of;
 use std::num::NonZeroU32;
 
@@ -33,15 +39,73 @@ impl Request for ChangeWindowAttributes {
 	fn handle(
 		&self,
 		ctx: &mut Context,
-		_client: &mut Client,
+		client: &mut Client,
 		_seq_nbr: u16,
 	) -> Result<(), HandleError> {
 		let wid =
 			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
 		let win = ctx
-			.get_window_mut(wid)
+			.get_window(wid)
 			.ok_or(HandleError::Client(Error::Window(self.window)))?;
-		create_window::set_attrs(&mut win.attributes, &self.changed_attrs);
+		let parent = win.get_parent();
+		let root = win.get_root();
+		let depth = win.get_depth();
+		let prev_colormap = win.attributes.colormap;
+
+		// The attributes are taken out of the window while pixmaps are resolved
+		let mut attrs = mem::take(&mut ctx.get_window_mut(wid).unwrap().attributes);
+		let res =
+			create_window::set_attrs(ctx, parent, root, depth, &mut attrs, &self.changed_attrs);
+		let win = ctx.get_window_mut(wid).unwrap();
+		win.attributes = attrs;
+		res.map_err(HandleError::Client)?;
+
+		// Event masks are selected per client
+		for a in &self.changed_attrs {
+			if let create_window::AttrValue::EventMask(mask) = a {
+				win.set_event_mask(client.get_id(), *mask);
+			}
+		}
+
+		// A new border is painted immediately
+		let border_changed = self.changed_attrs.iter().any(|a| {
+			matches!(
+				a,
+				create_window::AttrValue::BorderPixmap(_)
+					| create_window::AttrValue::BorderPixel(_)
+			)
+		});
+		let win = ctx.get_window(wid).unwrap();
+		if border_changed {
+			win.paint_border(ctx, &win.get_visible_border(ctx));
+		}
+		// The visible contents are kept if backing store is enabled
+		let visible = win.get_visible_region(ctx, false);
+		ctx.update_backing(wid, &visible);
+
+		let colormap = ctx.get_window(wid).unwrap().attributes.colormap;
+		if colormap != prev_colormap {
+			let installed =
+				NonZeroU32::new(colormap).is_some_and(|c| ctx.is_colormap_installed(c));
+			let state = if installed {
+				ColormapState::Installed
+			} else {
+				ColormapState::Uninstalled
+			};
+			let event = ColormapNotifyEvent {
+				code: Event::ColormapNotify as _,
+				_padding0: 0,
+				seq_nbr: 0,
+
+				window: self.window,
+				colormap,
+				new: 1,
+				state: state as _,
+
+				_padding1: [0; 18],
+			};
+			ctx.deliver_event(wid, protocol::Event::ColormapChange as _, &event);
+		}
 
 		Ok(())
 	}
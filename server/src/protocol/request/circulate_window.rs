@@ -0,0 +1,115 @@
+//! The `CirculateWindow` request raises the lowest obscured child of a window, or lowers its
+//! highest obscuring child. Parts of windows that become visible are exposed.
+
+use super::configure_window;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::event::CirculateNotifyEvent;
+use crate::protocol::event::Event;
+use crate::protocol::request::HandleError;
+use crate::protocol::MapState;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `CirculateWindow` request.
+#[repr(C, packed)]
+pub struct CirculateWindowHdr {
+	/// The window.
+	window: u32,
+}
+
+/// Structure representing the request.
+pub struct CirculateWindow {
+	/// If true, the highest obscuring child is lowered. Else, the lowest obscured child is
+	/// raised.
+	lower: bool,
+
+	/// The window.
+	window: u32,
+}
+
+impl Request for CirculateWindow {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let wid =
+			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+
+		// The mapped children, bottom-most first
+		let children: Vec<_> = win
+			.get_children()
+			.iter()
+			.filter_map(|c| ctx.get_window(*c))
+			.filter(|c| c.attributes.map_state != MapState::Unmapped)
+			.collect();
+		let child = if self.lower {
+			children.iter().enumerate().rev().find(|(i, c)| {
+				children[..*i]
+					.iter()
+					.any(|s| configure_window::occludes(ctx, c, s))
+			})
+		} else {
+			children.iter().enumerate().find(|(i, c)| {
+				children[(i + 1)..]
+					.iter()
+					.any(|s| configure_window::occludes(ctx, s, c))
+			})
+		};
+		let Some(child) = child.map(|(_, c)| c.get_id()) else {
+			return Ok(());
+		};
+
+		// TODO Send `CirculateRequest` instead if a client selected `SubstructureRedirect`
+		let prev = ctx.get_visibility(root);
+		ctx.get_window_mut(wid)
+			.unwrap()
+			.restack_child(child, None, !self.lower);
+		ctx.deliver_structure_event(child, |event| CirculateNotifyEvent {
+			code: Event::CirculateNotify as _,
+			_padding0: 0,
+			seq_nbr: 0,
+
+			event,
+			window: child.get(),
+			_unused: 0,
+			place: self.lower as _,
+
+			_padding1: [0; 15],
+		});
+		ctx.expose(root, &prev);
+
+		Ok(())
+	}
+}
+
+/// Parses `CirculateWindow`.
+pub fn read(buff: &[u8], direction: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CirculateWindowHdr>() {
+		return Ok(None);
+	}
+	let lower = match direction {
+		0 => false,
+		1 => true,
+
+		_ => return Err(Error::Value(direction as _)),
+	};
+
+	let hdr: &CirculateWindowHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(CirculateWindow {
+		lower,
+
+		window: hdr.window,
+	})))
+}
@@ -0,0 +1,103 @@
+//! The `ClearArea` request paints the background of a window on a rectangle, optionally
+//! reporting it with `Expose` events.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::region::Bounds;
+use crate::region::Region;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `ClearArea` request.
+#[repr(C, packed)]
+pub struct ClearAreaHdr {
+	/// The window.
+	window: u32,
+	/// The X position of the rectangle.
+	x: i16,
+	/// The Y position of the rectangle.
+	y: i16,
+	/// The width of the rectangle. If zero, the rectangle extends to the right of the window.
+	width: u16,
+	/// The height of the rectangle. If zero, the rectangle extends to the bottom of the window.
+	height: u16,
+}
+
+/// Structure representing the request.
+pub struct ClearArea {
+	/// Tells whether `Expose` events are sent for the cleared area.
+	exposures: bool,
+
+	/// The window.
+	window: u32,
+	/// The X position of the rectangle.
+	x: i16,
+	/// The Y position of the rectangle.
+	y: i16,
+	/// The width of the rectangle.
+	width: u16,
+	/// The height of the rectangle.
+	height: u16,
+}
+
+impl Request for ClearArea {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let wid =
+			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		if !win.is_output() {
+			return Err(HandleError::Client(Error::Match));
+		}
+
+		let inner = win.get_inner_bounds(ctx);
+		let (x, y) = (self.x as i32, self.y as i32);
+		let x2 = match self.width {
+			0 => inner.x2 - inner.x1,
+			w => x + w as i32,
+		};
+		let y2 = match self.height {
+			0 => inner.y2 - inner.y1,
+			h => y + h as i32,
+		};
+		let rect = Region::from_bounds(Bounds::new(x, y, x2, y2));
+		let region = rect.intersect(&win.get_visible_region(ctx, false));
+
+		if self.exposures {
+			ctx.expose_region(wid, &region);
+		} else {
+			win.paint_background(ctx, &region);
+		}
+
+		Ok(())
+	}
+}
+
+/// Parses `ClearArea`.
+pub fn read(buff: &[u8], exposures: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ClearAreaHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &ClearAreaHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(ClearArea {
+		exposures: exposures != 0,
+
+		window: hdr.window,
+		x: hdr.x,
+		y: hdr.y,
+		width: hdr.width,
+		height: hdr.height,
+	})))
+}
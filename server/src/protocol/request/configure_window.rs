@@ -0,0 +1,376 @@
+//! The `ConfigureWindow` request changes the position, size, border width or stacking order of a
+//! window. Parts of windows that become visible are exposed.
+//!
+//! When a window is resized, its contents move according to its bit gravity and its children
+//! according to their window gravity. Contents that are kept are not exposed again.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::window::Window;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::event::ConfigureNotifyEvent;
+use crate::protocol::event::Event;
+use crate::protocol::event::GravityNotifyEvent;
+use crate::protocol::request::HandleError;
+use crate::protocol::BitGravity;
+use crate::protocol::MapState;
+use crate::protocol::WinGravity;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// The way a window is restacked relative to its siblings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StackMode {
+	/// The window is placed on top of the sibling, or of the stack.
+	Above,
+	/// The window is placed below the sibling, or at the bottom of the stack.
+	Below,
+	/// The window is placed on top of the stack if the sibling, or any sibling, occludes it.
+	TopIf,
+	/// The window is placed at the bottom of the stack if it occludes the sibling, or any
+	/// sibling.
+	BottomIf,
+	/// Either `TopIf` or `BottomIf`, whichever applies.
+	Opposite,
+}
+
+impl TryFrom<u8> for StackMode {
+	type Error = Error;
+
+	fn try_from(v: u8) -> Result<Self, Self::Error> {
+		match v {
+			0 => Ok(Self::Above),
+			1 => Ok(Self::Below),
+			2 => Ok(Self::TopIf),
+			3 => Ok(Self::BottomIf),
+			4 => Ok(Self::Opposite),
+
+			_ => Err(Error::Value(v as _)),
+		}
+	}
+}
+
+/// Header of the `ConfigureWindow` request.
+#[repr(C, packed)]
+pub struct ConfigureWindowHdr {
+	/// The window.
+	window: u32,
+	/// The mask of values being changed.
+	value_mask: u16,
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+#[derive(Default)]
+pub struct ConfigureWindow {
+	/// The window.
+	window: u32,
+
+	/// The new X position of the window.
+	x: Option<i16>,
+	/// The new Y position of the window.
+	y: Option<i16>,
+	/// The new width of the window.
+	width: Option<u16>,
+	/// The new height of the window.
+	height: Option<u16>,
+	/// The new width of the window's border.
+	border_width: Option<u16>,
+	/// The sibling the window is restacked relative to.
+	sibling: Option<u32>,
+	/// The way the window is restacked.
+	stack_mode: Option<StackMode>,
+}
+
+/// Tells whether the window `a` occludes the window `b`, that is whether `a` is mapped and
+/// overlaps `b`.
+///
+/// `ctx` is the current context.
+pub fn occludes(ctx: &Context, a: &Window, b: &Window) -> bool {
+	a.attributes.map_state != MapState::Unmapped
+		&& a.get_outer_bounds(ctx)
+			.intersect(&b.get_outer_bounds(ctx))
+			.is_some()
+}
+
+/// Returns where the window is moved in the stacking order, as the sibling to place it next to
+/// and whether it is placed above. If the window is not moved, the function returns None.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `win` is the window to restack.
+/// - `siblings` is the list of the window's siblings, including itself, bottom-most first.
+/// - `sibling` is the sibling given with the request.
+/// - `stack_mode` is the way the window is restacked.
+fn get_restack(
+	ctx: &Context,
+	win: &Window,
+	siblings: &[NonZeroU32],
+	sibling: Option<NonZeroU32>,
+	stack_mode: StackMode,
+) -> Option<(Option<NonZeroU32>, bool)> {
+	let pos = siblings.iter().position(|s| *s == win.get_id()).unwrap();
+	let candidates = |range: &[NonZeroU32]| -> Vec<NonZeroU32> {
+		range
+			.iter()
+			.cloned()
+			.filter(|s| sibling.is_none_or(|sibling| sibling == *s))
+			.collect()
+	};
+	let above = candidates(&siblings[(pos + 1)..]);
+	let below = candidates(&siblings[..pos]);
+
+	let occluded = || {
+		above
+			.iter()
+			.filter_map(|s| ctx.get_window(*s))
+			.any(|s| occludes(ctx, s, win))
+	};
+	let occluding = || {
+		below
+			.iter()
+			.filter_map(|s| ctx.get_window(*s))
+			.any(|s| occludes(ctx, win, s))
+	};
+
+	match stack_mode {
+		StackMode::Above => Some((sibling, true)),
+		StackMode::Below => Some((sibling, false)),
+		StackMode::TopIf if occluded() => Some((None, true)),
+		StackMode::BottomIf if occluding() => Some((None, false)),
+		StackMode::Opposite if occluded() => Some((None, true)),
+		StackMode::Opposite if occluding() => Some((None, false)),
+		_ => None,
+	}
+}
+
+/// Returns the offset of an object placed with the given gravity when the window it is placed
+/// in is resized.
+///
+/// Arguments:
+/// - `gravity` is the value of the gravity, shared by bit and window gravities.
+/// - `dw` and `dh` are the changes of the width and height of the window.
+///
+/// Gravities other than `NorthWest` to `SouthEast` give no offset.
+fn get_gravity_offset(gravity: u8, dw: i32, dh: i32) -> (i32, i32) {
+	let x = match gravity {
+		2 | 5 | 8 => dw / 2,
+		3 | 6 | 9 => dw,
+		_ => 0,
+	};
+	let y = match gravity {
+		4..=6 => dh / 2,
+		7..=9 => dh,
+		_ => 0,
+	};
+	(x, y)
+}
+
+/// Moves the children of a resized window according to their window gravity, and sends
+/// `GravityNotify` events.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `wid` is the ID of the resized window.
+/// - `dw` and `dh` are the changes of the width and height of the window.
+/// - `dx` and `dy` are the move of the window's origin on screen, which `Static` gravity cancels.
+fn apply_win_gravity(
+	ctx: &mut Context,
+	wid: NonZeroU32,
+	(dw, dh): (i32, i32),
+	(dx, dy): (i32, i32),
+) {
+	let children = ctx.get_window(wid).unwrap().get_children().to_vec();
+	for child in children {
+		let win = ctx.get_window(child).unwrap();
+		let (off_x, off_y) = match win.attributes.win_gravity {
+			WinGravity::Unmap => {
+				ctx.unmap_window(child, true);
+				continue;
+			}
+			WinGravity::Static => (-dx, -dy),
+			g => get_gravity_offset(g as _, dw, dh),
+		};
+		if (off_x, off_y) == (0, 0) {
+			continue;
+		}
+
+		// Positions that do not fit the protocol's coordinates are clamped
+		let mut rect = win.get_rectangle();
+		rect.x = (rect.x as i32 + off_x).clamp(i16::MIN as _, i16::MAX as _) as _;
+		rect.y = (rect.y as i32 + off_y).clamp(i16::MIN as _, i16::MAX as _) as _;
+		ctx.get_window_mut(child)
+			.unwrap()
+			.set_rectangle(rect.clone());
+		ctx.deliver_structure_event(child, |event| GravityNotifyEvent {
+			code: Event::GravityNotify as _,
+			_padding0: 0,
+			seq_nbr: 0,
+
+			event,
+			window: child.get(),
+			x: rect.x,
+			y: rect.y,
+
+			_padding1: [0; 16],
+		});
+	}
+}
+
+impl Request for ConfigureWindow {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let wid =
+			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+		// The root window cannot be configured
+		let Some(parent) = win.get_parent() else {
+			return Ok(());
+		};
+
+		let sibling = match (self.sibling, self.stack_mode) {
+			(Some(_), None) => return Err(HandleError::Client(Error::Match)),
+			(Some(sibling), Some(_)) => {
+				let sid =
+					NonZeroU32::new(sibling).ok_or(HandleError::Client(Error::Window(sibling)))?;
+				let s = ctx
+					.get_window(sid)
+					.ok_or(HandleError::Client(Error::Window(sibling)))?;
+				if sid == wid || s.get_parent() != Some(parent) {
+					return Err(HandleError::Client(Error::Match));
+				}
+				Some(sid)
+			}
+			(None, _) => None,
+		};
+
+		let mut prev = ctx.get_visibility(root);
+		let mut saved = ctx.save_contents(wid);
+
+		let old_rect = win.get_rectangle();
+		let old_origin = win.get_absolute_position(ctx);
+		let bit_gravity = win.attributes.bit_gravity;
+		let mut rect = old_rect.clone();
+		rect.x = self.x.unwrap_or(rect.x);
+		rect.y = self.y.unwrap_or(rect.y);
+		rect.width = self.width.unwrap_or(rect.width);
+		rect.height = self.height.unwrap_or(rect.height);
+		let border_width = self.border_width.unwrap_or(win.get_border_width());
+		let win = ctx.get_window_mut(wid).unwrap();
+		win.set_rectangle(rect.clone());
+		win.set_border_width(border_width);
+
+		// The contents follow the bit gravity
+		let origin = ctx.get_window(wid).unwrap().get_absolute_position(ctx);
+		let (dx, dy) = (origin.0 - old_origin.0, origin.1 - old_origin.1);
+		let dw = rect.width as i32 - old_rect.width as i32;
+		let dh = rect.height as i32 - old_rect.height as i32;
+		let resized = (dw, dh) != (0, 0);
+		// The area saved under the window does not match its new position
+		if resized || (dx, dy) != (0, 0) {
+			ctx.discard_save_under(wid);
+		}
+		if resized {
+			match bit_gravity {
+				BitGravity::Forget => saved.discard(wid),
+				BitGravity::Static => saved.shift(wid, -dx, -dy),
+				g => {
+					let (x, y) = get_gravity_offset(g as _, dw, dh);
+					saved.shift(wid, x, y);
+				}
+			}
+		}
+
+		if let Some(stack_mode) = self.stack_mode {
+			let win = ctx.get_window(wid).unwrap();
+			let siblings = ctx.get_window(parent).unwrap().get_children();
+			if let Some((sibling, above)) = get_restack(ctx, win, siblings, sibling, stack_mode) {
+				ctx.get_window_mut(parent)
+					.unwrap()
+					.restack_child(wid, sibling, above);
+			}
+		}
+
+		let siblings = ctx.get_window(parent).unwrap().get_children();
+		let pos = siblings.iter().position(|s| *s == wid).unwrap();
+		let above_sibling = pos.checked_sub(1).map(|i| siblings[i].get()).unwrap_or(0);
+		let override_redirect = ctx.get_window(wid).unwrap().attributes.override_redirect;
+		ctx.deliver_structure_event(wid, |event| ConfigureNotifyEvent {
+			code: Event::ConfigureNotify as _,
+			_padding0: 0,
+			seq_nbr: 0,
+
+			event,
+			window: wid.get(),
+			above_sibling,
+			x: rect.x,
+			y: rect.y,
+			width: rect.width,
+			height: rect.height,
+			border_width,
+			override_redirect: override_redirect as _,
+
+			_padding1: [0; 5],
+		});
+
+		if resized {
+			apply_win_gravity(ctx, wid, (dw, dh), (dx, dy));
+		}
+
+		ctx.restore_contents(&saved, &mut prev);
+		ctx.expose(root, &prev);
+
+		Ok(())
+	}
+}
+
+/// Parses `ConfigureWindow`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ConfigureWindowHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &ConfigureWindowHdr = unsafe { util::reinterpret(&buff[0]) };
+	let mut req = ConfigureWindow {
+		window: hdr.window,
+		..Default::default()
+	};
+
+	let buff = &buff[size_of::<ConfigureWindowHdr>()..];
+	let mut off = 0;
+	let set_bits_iter = (0..=6).filter(|i| hdr.value_mask & (1 << i) != 0);
+	for id in set_bits_iter {
+		// Each value is padded to 4 bytes
+		if off + 4 > buff.len() {
+			return Err(Error::Length);
+		}
+		let val = unsafe { *util::reinterpret::<_, u32>(&buff[off]) };
+		off += 4;
+
+		match id {
+			0 => req.x = Some(val as _),
+			1 => req.y = Some(val as _),
+			2 | 3 if val as u16 == 0 => return Err(Error::Value(val)),
+			2 => req.width = Some(val as _),
+			3 => req.height = Some(val as _),
+			4 => req.border_width = Some(val as _),
+			5 => req.sibling = Some(val),
+			6 => req.stack_mode = Some((val as u8).try_into()?),
+
+			_ => unreachable!(),
+		}
+	}
+
+	Ok(Some(Box::new(req)))
+}
@@ -0,0 +1,99 @@
+//! The `CopyArea` request copies an area of a drawable to another drawable.
+
+use super::Request;
+use super::COPY_AREA;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::drawing::copy;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Rectangle;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `CopyArea` request.
+#[repr(C, packed)]
+pub struct CopyAreaHdr {
+	/// The source drawable.
+	src_drawable: u32,
+	/// The destination drawable.
+	dst_drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The X position of the area on the source.
+	src_x: i16,
+	/// The Y position of the area on the source.
+	src_y: i16,
+	/// The X position of the area on the destination.
+	dst_x: i16,
+	/// The Y position of the area on the destination.
+	dst_y: i16,
+	/// The width of the area.
+	width: u16,
+	/// The height of the area.
+	height: u16,
+}
+
+/// Structure representing the request.
+pub struct CopyArea {
+	/// The source drawable.
+	src_drawable: u32,
+	/// The destination drawable.
+	dst_drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The area to copy, relative to the source's origin.
+	src: Rectangle,
+	/// The X position of the area on the destination.
+	dst_x: i16,
+	/// The Y position of the area on the destination.
+	dst_y: i16,
+}
+
+impl Request for CopyArea {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let exposed = copy::copy(
+			ctx,
+			self.src_drawable,
+			self.dst_drawable,
+			self.gc,
+			&self.src,
+			(self.dst_x, self.dst_y),
+			None,
+		)
+		.map_err(HandleError::Client)?;
+
+		if let Some(exposed) = exposed {
+			copy::send_exposures(ctx, client.get_id(), self.dst_drawable, &exposed, COPY_AREA);
+		}
+		Ok(())
+	}
+}
+
+/// Parses `CopyArea`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CopyAreaHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CopyAreaHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(CopyArea {
+		src_drawable: hdr.src_drawable,
+		dst_drawable: hdr.dst_drawable,
+		gc: hdr.gc,
+		src: Rectangle {
+			x: hdr.src_x,
+			y: hdr.src_y,
+			width: hdr.width,
+			height: hdr.height,
+		},
+		dst_x: hdr.dst_x,
+		dst_y: hdr.dst_y,
+	})))
+}
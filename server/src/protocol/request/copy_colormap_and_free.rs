@@ -0,0 +1,65 @@
+//! The `CopyColormapAndFree` request creates a colormap, moving to it the entries the client
+//! allocated in another colormap.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `CopyColormapAndFree` request.
+#[repr(C, packed)]
+pub struct CopyColormapAndFreeHdr {
+	/// The ID of the new colormap.
+	mid: u32,
+	/// The colormap to copy from.
+	src_cmap: u32,
+}
+
+/// Structure representing the request.
+pub struct CopyColormapAndFree {
+	/// The ID of the new colormap.
+	mid: u32,
+	/// The colormap to copy from.
+	src_cmap: u32,
+}
+
+impl Request for CopyColormapAndFree {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let mid =
+			NonZeroU32::new(self.mid).ok_or(HandleError::Client(Error::IDChoice(self.mid)))?;
+		if ctx.is_id_used(mid) {
+			return Err(HandleError::Client(Error::IDChoice(self.mid)));
+		}
+		let src = NonZeroU32::new(self.src_cmap)
+			.and_then(|id| ctx.get_colormap_mut(id))
+			.ok_or(HandleError::Client(Error::Colormap(self.src_cmap)))?;
+
+		let colormap = src.copy_and_free(mid, client.get_id());
+		ctx.add_colormap(colormap);
+
+		Ok(())
+	}
+}
+
+/// Parses `CopyColormapAndFree`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CopyColormapAndFreeHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CopyColormapAndFreeHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(CopyColormapAndFree {
+		mid: hdr.mid,
+		src_cmap: hdr.src_cmap,
+	})))
+}
@@ -0,0 +1,76 @@
+//! The `CopyGC` request copies components from a graphics context to another.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `CopyGC` request.
+#[repr(C, packed)]
+pub struct CopyGCHdr {
+	/// The ID of the source graphics context.
+	src_gc: u32,
+	/// The ID of the destination graphics context.
+	dst_gc: u32,
+
+	/// The mask of components to copy.
+	value_mask: u32,
+}
+
+/// Structure representing the request.
+pub struct CopyGC {
+	/// The ID of the source graphics context.
+	src_gc: u32,
+	/// The ID of the destination graphics context.
+	dst_gc: u32,
+
+	/// The mask of components to copy.
+	value_mask: u32,
+}
+
+impl Request for CopyGC {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		if self.value_mask >> 23 != 0 {
+			return Err(HandleError::Client(Error::Value(self.value_mask)));
+		}
+
+		let src = NonZeroU32::new(self.src_gc)
+			.and_then(|id| ctx.get_gc(id))
+			.ok_or(HandleError::Client(Error::GContext(self.src_gc)))?
+			.clone();
+		let dst = NonZeroU32::new(self.dst_gc)
+			.and_then(|id| ctx.get_gc_mut(id))
+			.ok_or(HandleError::Client(Error::GContext(self.dst_gc)))?;
+		if src.get_root() != dst.get_root() || src.get_depth() != dst.get_depth() {
+			return Err(HandleError::Client(Error::Match));
+		}
+		dst.copy_components(&src, self.value_mask);
+
+		Ok(())
+	}
+}
+
+/// Parses `CopyGC`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CopyGCHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CopyGCHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(CopyGC {
+		src_gc: hdr.src_gc,
+		dst_gc: hdr.dst_gc,
+
+		value_mask: hdr.value_mask,
+	})))
+}
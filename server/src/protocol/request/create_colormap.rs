@@ -0,0 +1,111 @@
+//! The `CreateColormap` request creates a colormap for a visual on the screen of a window.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::colormap::Colormap;
+use crate::ctx::screen;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::VisualClass;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `CreateColormap` request.
+#[repr(C, packed)]
+pub struct CreateColormapHdr {
+	/// The ID of the colormap.
+	mid: u32,
+	/// A window on the screen of the colormap.
+	window: u32,
+	/// The visual of the colormap.
+	visual: u32,
+}
+
+/// Structure representing the request.
+pub struct CreateColormap {
+	/// Tells whether every entry of the colormap is allocated to the client.
+	alloc_all: bool,
+
+	/// The ID of the colormap.
+	mid: u32,
+	/// A window on the screen of the colormap.
+	window: u32,
+	/// The visual of the colormap.
+	visual: u32,
+}
+
+impl Request for CreateColormap {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let mid =
+			NonZeroU32::new(self.mid).ok_or(HandleError::Client(Error::IDChoice(self.mid)))?;
+		if ctx.is_id_used(mid) {
+			return Err(HandleError::Client(Error::IDChoice(self.mid)));
+		}
+		let win = NonZeroU32::new(self.window)
+			.and_then(|wid| ctx.get_window(wid))
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+		let screen = ctx
+			.get_screen_for_root(root)
+			.map(|i| &ctx.get_screens()[i])
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+
+		// Only the root visual is supported
+		if self.visual != screen.get_root_visual() {
+			return Err(HandleError::Client(Error::Match));
+		}
+		// Entries of static visuals cannot be allocated
+		let static_class = matches!(
+			screen::ROOT_VISUAL_CLASS,
+			VisualClass::StaticGray | VisualClass::StaticColor | VisualClass::TrueColor
+		);
+		if self.alloc_all && static_class {
+			return Err(HandleError::Client(Error::Match));
+		}
+
+		let colormap = Colormap::new(
+			mid,
+			Some(client.get_id()),
+			root,
+			self.visual,
+			screen::ROOT_VISUAL_ENTRIES as _,
+			self.alloc_all,
+		);
+		ctx.add_colormap(colormap);
+
+		Ok(())
+	}
+}
+
+/// Parses `CreateColormap`.
+///
+/// `alloc` tells whether no entry (`0`) or every entry (`1`) of the colormap is allocated.
+pub fn read(buff: &[u8], alloc: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CreateColormapHdr>() {
+		return Ok(None);
+	}
+	let alloc_all = match alloc {
+		0 => false,
+		1 => true,
+
+		_ => return Err(Error::Value(alloc as _)),
+	};
+
+	let hdr: &CreateColormapHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(CreateColormap {
+		alloc_all,
+
+		mid: hdr.mid,
+		window: hdr.window,
+		visual: hdr.visual,
+	})))
+}
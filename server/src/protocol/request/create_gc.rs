@@ -3,12 +3,17 @@
 use super::Request;
 use crate::ctx::client::Client;
 use crate::ctx::gc;
+use crate::ctx::gc::Clip;
 use crate::ctx::gc::GC;
+use crate::ctx::pixmap::Pixmap;
 use crate::ctx::Context;
+use crate::ctx::Drawable;
 use crate::protocol::error::Error;
 use crate::protocol::request::HandleError;
 use crate::util;
 use std::mem::size_of;
+use std::num::NonZeroU32;
+use std::rc::Rc;
 
 /// TODO doc
 #[repr(C, packed)]
@@ -26,35 +31,59 @@ pub struct CreateGCHdr {
 pub struct CreateGC {
 	/// The graphics context's ID.
 	cid: u32,
-	/// The graphics context.
-	gc: GC,
+	/// The ID of the drawable.
+	drawable: u32,
+
+	/// The list of values to set.
+	values: Vec<gc::Value>,
 }
 
 impl Request for CreateGC {
 	fn handle(
 		&self,
-		_ctx: &mut Context,
+		ctx: &mut Context,
 		client: &mut Client,
 		_seq_nbr: u16,
 	) -> Result<(), HandleError> {
-		client.set_gc(self.cid, self.gc.clone());
+		let cid =
+			NonZeroU32::new(self.cid).ok_or(HandleError::Client(Error::IDChoice(self.cid)))?;
+		if ctx.is_id_used(cid) {
+			return Err(HandleError::Client(Error::IDChoice(self.cid)));
+		}
+		let drawable = NonZeroU32::new(self.drawable)
+			.and_then(|id| ctx.get_drawable(id))
+			.ok_or(HandleError::Client(Error::Drawable(self.drawable)))?;
+
+		let mut gc = GC::new(cid, client.get_id(), drawable);
+		set_values(ctx, &mut gc, &self.values).map_err(HandleError::Client)?;
+		ctx.add_gc(gc);
+
 		Ok(())
 	}
 }
 
-/// Parses `CreateGC`.
-pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
-	if buff.len() < size_of::<CreateGCHdr>() {
-		return Ok(None);
+/// Reads graphics context values from a request. The function returns the list of values.
+///
+/// Arguments:
+/// - `bitmask` is the bitmask of values to read.
+/// - `buff` is the buffer containing the values.
+///
+/// If the bitmask has bits set beyond the last component, the function returns `Value`.
+pub fn read_values(bitmask: u32, buff: &[u8]) -> Result<Vec<gc::Value>, Error> {
+	if bitmask >> 23 != 0 {
+		return Err(Error::Value(bitmask));
 	}
 
-	let hdr: &CreateGCHdr = unsafe { util::reinterpret(&buff[0]) };
-
 	let mut values = vec![];
 
-	let mut off = size_of::<CreateGCHdr>();
-	let set_bits_iter = (0..=22).filter(|i| hdr.bitmask & (1 << i) != 0);
+	let mut off = 0;
+	let set_bits_iter = (0..=22).filter(|i| bitmask & (1 << i) != 0);
 	for id in set_bits_iter {
+		// Each value is padded to 4 bytes
+		if off + 4 > buff.len() {
+			return Err(Error::Length);
+		}
+
 		let size = match id {
 			0 => 1,
 			1 => 4,
@@ -90,7 +119,7 @@ pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
 
 			_ => unreachable!(),
 		};
-		off += size;
+		off += 4;
 
 		let val = match id {
 			0 => gc::Value::Function((val as u8).try_into()?),
@@ -123,13 +152,90 @@ pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
 		values.push(val);
 	}
 
-	let gc = GC {
-		drawable: hdr.drawable,
-		values,
-	};
+	Ok(values)
+}
+
+/// Returns the pixmap with the given ID, to be used as a tile, stipple or clip mask.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `id` is the ID of the pixmap.
+/// - `root` is the root of the graphics context.
+/// - `depth` is the depth the pixmap is required to have.
+fn get_pixmap(ctx: &Context, id: u32, root: u32, depth: u8) -> Result<Rc<Pixmap>, Error> {
+	let pixmap = NonZeroU32::new(id)
+		.and_then(|id| ctx.get_pixmap(id))
+		.ok_or(Error::Pixmap(id))?;
+	if pixmap.get_root() != root || pixmap.get_depth() != depth {
+		return Err(Error::Match);
+	}
+
+	Ok(pixmap.clone())
+}
+
+/// Sets the given values list on the given graphics context.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `gc` is the graphics context to modify.
+/// - `list` is the list of values to set.
+///
+/// On error, the graphics context may be partially modified. Callers that must leave it
+/// untouched work on a copy.
+pub fn set_values(ctx: &Context, gc: &mut GC, list: &[gc::Value]) -> Result<(), Error> {
+	let root = gc.get_root();
+	let depth = gc.get_depth();
+
+	for v in list {
+		match v {
+			gc::Value::Function(val) => gc.function = *val,
+			gc::Value::PlaneMask(val) => gc.plane_mask = *val,
+			gc::Value::Foreground(val) => gc.foreground = *val,
+			gc::Value::Background(val) => gc.background = *val,
+			gc::Value::LineWidth(val) => gc.line_width = *val,
+			gc::Value::LineStyle(val) => gc.line_style = *val,
+			gc::Value::CapStyle(val) => gc.cap_style = *val,
+			gc::Value::JoinStyle(val) => gc.join_style = *val,
+			gc::Value::FillStyle(val) => gc.fill_style = *val,
+			gc::Value::FillRule(val) => gc.fill_rule = *val,
+			gc::Value::Tile(id) => gc.tile = Some(get_pixmap(ctx, *id, root, depth)?),
+			gc::Value::Stipple(id) => gc.stipple = Some(get_pixmap(ctx, *id, root, 1)?),
+			gc::Value::TileStippleXOrigin(val) => gc.tile_stipple_x_origin = *val,
+			gc::Value::TileStippleYOrigin(val) => gc.tile_stipple_y_origin = *val,
+			gc::Value::Font(val) => gc.font = *val,
+			gc::Value::SubwindowMode(val) => gc.subwindow_mode = *val,
+			gc::Value::GraphicsExposures(val) => match val {
+				0 => gc.graphics_exposures = false,
+				1 => gc.graphics_exposures = true,
+				_ => return Err(Error::Value(*val as _)),
+			},
+			gc::Value::ClipXOrigin(val) => gc.clip_x_origin = *val,
+			gc::Value::ClipYOrigin(val) => gc.clip_y_origin = *val,
+			gc::Value::ClipMask(0) => gc.clip = Clip::None,
+			gc::Value::ClipMask(id) => gc.clip = Clip::Mask(get_pixmap(ctx, *id, root, 1)?),
+			gc::Value::DashOffset(val) => gc.dash_offset = *val,
+			gc::Value::Dashes(0) => return Err(Error::Value(0)),
+			gc::Value::Dashes(val) => gc.dashes = vec![*val, *val],
+			gc::Value::ArcMode(val) => gc.arc_mode = *val,
+		}
+	}
+
+	Ok(())
+}
+
+/// Parses `CreateGC`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<CreateGCHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &CreateGCHdr = unsafe { util::reinterpret(&buff[0]) };
+	let values = read_values(hdr.bitmask, &buff[size_of::<CreateGCHdr>()..])?;
 
 	Ok(Some(Box::new(CreateGC {
 		cid: hdr.cid,
-		gc,
+		drawable: hdr.drawable,
+
+		values,
 	})))
 }
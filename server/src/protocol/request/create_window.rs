@@ -1,20 +1,26 @@
-//! TODO doc
+//! The `CreateWindow` request creates an unmapped window.
 
 use super::Request;
 use crate::ctx::client::Client;
+use crate::ctx::pixmap::Pixmap;
+use crate::ctx::window::BackgroundPixmap;
 use crate::ctx::window::Window;
 use crate::ctx::window::WindowAttributes;
 use crate::ctx::Context;
+use crate::ctx::Drawable;
 use crate::protocol::error::Error;
 use crate::protocol::request::HandleError;
 use crate::protocol::BackingStore;
 use crate::protocol::BitGravity;
 use crate::protocol::Class;
+use crate::protocol::MapState;
 use crate::protocol::Rectangle;
 use crate::protocol::WinGravity;
+use crate::region::Region;
 use crate::util;
 use std::mem::size_of;
 use std::num::NonZeroU32;
+use std::rc::Rc;
 
 /// Enumeration of window attribute values read from a request.
 pub enum AttrValue {
@@ -93,8 +99,61 @@ impl Request for CreateWindow {
 		&self,
 		ctx: &mut Context,
 		client: &mut Client,
-		seq_nbr: u16,
+		_seq_nbr: u16,
 	) -> Result<(), HandleError> {
+		let wid =
+			NonZeroU32::new(self.wid).ok_or(HandleError::Client(Error::IDChoice(self.wid)))?;
+		if ctx.is_id_used(wid) {
+			return Err(HandleError::Client(Error::IDChoice(self.wid)));
+		}
+		let parent_id =
+			NonZeroU32::new(self.parent).ok_or(HandleError::Client(Error::Window(self.parent)))?;
+		let parent = ctx
+			.get_window(parent_id)
+			.ok_or(HandleError::Client(Error::Window(self.parent)))?;
+
+		// Interpreting CopyFromParent
+		let class = match self.class {
+			Class::CopyFromParent => parent.attributes.class,
+			c => c,
+		};
+		let depth = match self.depth {
+			0 => parent.get_depth(),
+			d => d,
+		};
+		let visual = match self.visual {
+			0 => parent.attributes.visual,
+			v => v,
+		};
+
+		let mut attr = WindowAttributes {
+			visual,
+			class,
+			map_state: MapState::Unmapped,
+			..Default::default()
+		};
+		let root = parent.get_root();
+		// The border and the colormap default to CopyFromParent
+		let border_set = self
+			.attrs
+			.iter()
+			.any(|a| matches!(a, AttrValue::BorderPixmap(_) | AttrValue::BorderPixel(_)));
+		let colormap_set = self
+			.attrs
+			.iter()
+			.any(|a| matches!(a, AttrValue::Colormap(_)));
+		let default: Vec<_> = [
+			(!border_set).then_some(AttrValue::BorderPixmap(0)),
+			(!colormap_set).then_some(AttrValue::Colormap(0)),
+		]
+		.into_iter()
+		.flatten()
+		.collect();
+		set_attrs(ctx, Some(parent_id), root, depth, &mut attr, &default)
+			.map_err(HandleError::Client)?;
+		set_attrs(ctx, Some(parent_id), root, depth, &mut attr, &self.attrs)
+			.map_err(HandleError::Client)?;
+
 		let rect = Rectangle {
 			x: self.x,
 			y: self.y,
@@ -102,17 +161,16 @@ impl Request for CreateWindow {
 			width: self.width,
 			height: self.height,
 		};
-		let mut window = Window::new(ctx, NonZeroU32::new(self.parent), rect);
+		let window = Window::new(ctx, wid, Some(parent_id), rect);
 
-		window.set_depth(self.depth);
+		window.set_depth(depth);
 		window.set_border_width(self.border_width);
-
-		// TODO Interpret copy_from_parent
-		let mut attr = WindowAttributes::default();
-		set_attrs(&mut attr, &self.attrs);
-
+		window.set_event_mask(client.get_id(), attr.event_mask);
 		window.set_attributes(attr);
 
+		// With backing store `Always`, contents are kept even before the window is mapped
+		ctx.update_backing(wid, &Region::new());
+
 		Ok(())
 	}
 }
@@ -183,14 +241,129 @@ pub fn read_attrs(bitmask: u32, buff: &[u8]) -> Result<Vec<AttrValue>, Error> {
 	Ok(values)
 }
 
+/// Returns the pixmap with the given ID, to be used as a background or border.
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `id` is the ID of the pixmap.
+/// - `root` is the root of the window.
+/// - `depth` is the depth of the window.
+fn get_attr_pixmap(ctx: &Context, id: u32, root: u32, depth: u8) -> Result<Rc<Pixmap>, Error> {
+	let pixmap = NonZeroU32::new(id)
+		.and_then(|id| ctx.get_pixmap(id))
+		.ok_or(Error::Pixmap(id))?;
+	if pixmap.get_root() != root || pixmap.get_depth() != depth {
+		return Err(Error::Match);
+	}
+
+	Ok(pixmap.clone())
+}
+
 /// Sets the given attributes list on the given attributes structure.
-pub fn set_attrs(attrs: &mut WindowAttributes, list: &[AttrValue]) {
+///
+/// Arguments:
+/// - `ctx` is the current context.
+/// - `parent` is the ID of the parent of the window. If None, the window is a root window.
+/// - `root` is the root of the window.
+/// - `depth` is the depth of the window.
+/// - `attrs` is the attributes structure to modify.
+/// - `list` is the list of attributes to set.
+///
+/// Pixmaps and colormaps are resolved before anything is modified, so that `attrs` is left
+/// untouched on error.
+pub fn set_attrs(
+	ctx: &Context,
+	parent: Option<NonZeroU32>,
+	root: u32,
+	depth: u8,
+	attrs: &mut WindowAttributes,
+	list: &[AttrValue],
+) -> Result<(), Error> {
+	let parent = parent.and_then(|p| ctx.get_window(p));
+
+	let mut background_pixmap = None;
+	// The border pixmap, with the border pixel when copied from the parent
+	let mut border_pixmap = None;
+	let mut colormap = None;
 	for a in list {
 		match a {
-			AttrValue::BackgroundPixmap(val) => attrs.background_pixmap = *val,
-			AttrValue::BackgroundPixel(val) => attrs.background_pixel = *val,
-			AttrValue::BorderPixmap(val) => attrs.border_pixmap = *val,
-			AttrValue::BorderPixel(val) => attrs.border_pixel = *val,
+			AttrValue::BackgroundPixmap(0) => background_pixmap = Some(BackgroundPixmap::None),
+			AttrValue::BackgroundPixmap(1) => {
+				if parent.is_some_and(|p| p.get_depth() != depth) {
+					return Err(Error::Match);
+				}
+				background_pixmap = Some(BackgroundPixmap::ParentRelative);
+			}
+			AttrValue::BackgroundPixmap(id) => {
+				let pixmap = get_attr_pixmap(ctx, *id, root, depth)?;
+				background_pixmap = Some(BackgroundPixmap::Pixmap(pixmap));
+			}
+
+			// CopyFromParent
+			AttrValue::BorderPixmap(0) => {
+				let Some(parent) = parent else {
+					border_pixmap = Some((None, None));
+					continue;
+				};
+				if parent.get_depth() != depth {
+					return Err(Error::Match);
+				}
+				border_pixmap = Some((
+					parent.attributes.border_pixmap.clone(),
+					Some(parent.attributes.border_pixel),
+				));
+			}
+			AttrValue::BorderPixmap(id) => {
+				border_pixmap = Some((Some(get_attr_pixmap(ctx, *id, root, depth)?), None));
+			}
+
+			// CopyFromParent
+			AttrValue::Colormap(0) => {
+				let Some(parent) = parent else {
+					continue;
+				};
+				if parent.attributes.visual != attrs.visual {
+					return Err(Error::Match);
+				}
+				colormap = Some(parent.attributes.colormap);
+			}
+			AttrValue::Colormap(id) => {
+				let cmap = NonZeroU32::new(*id)
+					.and_then(|id| ctx.get_colormap(id))
+					.ok_or(Error::Colormap(*id))?;
+				if cmap.get_root().get() != root || cmap.get_visual() != attrs.visual {
+					return Err(Error::Match);
+				}
+				colormap = Some(*id);
+			}
+
+			_ => {}
+		}
+	}
+
+	// A background pixmap replaces the background pixel, unless both are given
+	if let Some(background_pixmap) = background_pixmap {
+		attrs.background_pixmap = background_pixmap;
+		attrs.background_pixel = None;
+	}
+	if let Some((border_pixmap, border_pixel)) = border_pixmap {
+		attrs.border_pixmap = border_pixmap;
+		attrs.border_pixel = border_pixel.unwrap_or(attrs.border_pixel);
+	}
+	if let Some(colormap) = colormap {
+		attrs.colormap = colormap;
+	}
+	for a in list {
+		match a {
+			AttrValue::BackgroundPixmap(_)
+			| AttrValue::BorderPixmap(_)
+			| AttrValue::Colormap(_) => {}
+			AttrValue::BackgroundPixel(val) => attrs.background_pixel = Some(*val),
+			// The border pixel replaces the border pixmap
+			AttrValue::BorderPixel(val) => {
+				attrs.border_pixmap = None;
+				attrs.border_pixel = *val;
+			}
 			AttrValue::BitGravity(val) => attrs.bit_gravity = *val,
 			AttrValue::WinGravity(val) => attrs.win_gravity = *val,
 			AttrValue::BackingStore(val) => attrs.backing_store = *val,
@@ -200,10 +373,11 @@ pub fn set_attrs(attrs: &mut WindowAttributes, list: &[AttrValue]) {
 			AttrValue::SaveUnder(val) => attrs.save_under = *val,
 			AttrValue::EventMask(val) => attrs.event_mask = *val,
 			AttrValue::DoNotPropagateMask(val) => attrs.do_not_propagate_mask = *val,
-			AttrValue::Colormap(val) => attrs.colormap = *val,
 			AttrValue::Cursor(val) => attrs.cursor = *val,
 		}
 	}
+
+	Ok(())
 }
 
 /// Parses `CreateWindow`.
@@ -0,0 +1,73 @@
+//! The `FillPoly` request fills a polygon.
+
+use super::poly_point;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::drawing;
+use crate::drawing::polygon;
+use crate::drawing::polygon::ShapeHint;
+use crate::drawing::CoordinateMode;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `FillPoly` request.
+#[repr(C, packed)]
+pub struct FillPolyHdr {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The hint on the shape of the polygon.
+	shape: u8,
+	/// The way coordinates of points are interpreted.
+	coordinate_mode: u8,
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct FillPoly {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The hint on the shape of the polygon.
+	shape: ShapeHint,
+	/// The points, relative to the origin of the drawable.
+	points: Vec<(i32, i32)>,
+}
+
+impl Request for FillPoly {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		drawing::draw(ctx, self.drawable, self.gc, |canvas| {
+			polygon::fill_poly(canvas, &self.points, self.shape)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `FillPoly`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<FillPolyHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &FillPolyHdr = unsafe { util::reinterpret(&buff[0]) };
+	let mode: CoordinateMode = hdr.coordinate_mode.try_into()?;
+	let points = poly_point::read_points(&buff[size_of::<FillPolyHdr>()..]);
+
+	Ok(Some(Box::new(FillPoly {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		shape: hdr.shape.try_into()?,
+		points: mode.resolve(&points),
+	})))
+}
@@ -0,0 +1,55 @@
+//! The `FreeColormap` request deletes a colormap.
+//!
+//! The colormap is uninstalled if needed, and windows using it are left without colormap. Freeing
+//! the default colormap of a screen has no effect.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `FreeColormap` request.
+#[repr(C, packed)]
+pub struct FreeColormapHdr {
+	/// The ID of the colormap.
+	cmap: u32,
+}
+
+/// Structure representing the request.
+pub struct FreeColormap {
+	/// The ID of the colormap.
+	cmap: u32,
+}
+
+impl Request for FreeColormap {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let id = NonZeroU32::new(self.cmap)
+			.filter(|id| ctx.get_colormap(*id).is_some())
+			.ok_or(HandleError::Client(Error::Colormap(self.cmap)))?;
+		ctx.free_colormap(id);
+
+		Ok(())
+	}
+}
+
+/// Parses `FreeColormap`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<FreeColormapHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &FreeColormapHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(FreeColormap {
+		cmap: hdr.cmap,
+	})))
+}
@@ -0,0 +1,51 @@
+//! The `FreeGC` request deletes a graphics context.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `FreeGC` request.
+#[repr(C, packed)]
+pub struct FreeGCHdr {
+	/// The ID of the graphics context.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct FreeGC {
+	/// The ID of the graphics context.
+	gc: u32,
+}
+
+impl Request for FreeGC {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		NonZeroU32::new(self.gc)
+			.and_then(|id| ctx.free_gc(id))
+			.ok_or(HandleError::Client(Error::GContext(self.gc)))?;
+
+		Ok(())
+	}
+}
+
+/// Parses `FreeGC`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<FreeGCHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &FreeGCHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(FreeGC {
+		gc: hdr.gc,
+	})))
+}
@@ -0,0 +1,198 @@
+//! The `GetImage` request returns the contents of a rectangle of a drawable.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::image::Format;
+use crate::ctx::image::Image;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// The header of the request's reply.
+#[repr(C, packed)]
+pub struct GetImageReply {
+	/// The type of the reply.
+	reply_type: u8,
+	/// The depth of the drawable.
+	depth: u8,
+	/// The sequence number of the request.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+	/// The visual of the window, or zero for a pixmap.
+	visual: u32,
+	/// Padding.
+	_padding: [u8; 20],
+}
+
+/// Header of the `GetImage` request.
+#[repr(C, packed)]
+pub struct GetImageHdr {
+	/// The drawable to read from.
+	drawable: u32,
+	/// The X position of the rectangle.
+	x: i16,
+	/// The Y position of the rectangle.
+	y: i16,
+	/// The width of the rectangle.
+	width: u16,
+	/// The height of the rectangle.
+	height: u16,
+	/// The mask of bit planes to return.
+	plane_mask: u32,
+}
+
+/// Structure representing the request.
+pub struct GetImage {
+	/// The format of the returned image.
+	format: Format,
+	/// The drawable to read from.
+	drawable: u32,
+	/// The X position of the rectangle.
+	x: i16,
+	/// The Y position of the rectangle.
+	y: i16,
+	/// The width of the rectangle.
+	width: u16,
+	/// The height of the rectangle.
+	height: u16,
+	/// The mask of bit planes to return.
+	plane_mask: u32,
+}
+
+impl GetImage {
+	/// Tells whether the requested rectangle is inside of the given bounds, relative to the
+	/// drawable's origin.
+	fn is_inside(&self, x: i32, y: i32, width: i32, height: i32) -> bool {
+		let (x_begin, y_begin) = (self.x as i32, self.y as i32);
+		let x_end = x_begin + self.width as i32;
+		let y_end = y_begin + self.height as i32;
+
+		x_begin >= x && y_begin >= y && x_end <= x + width && y_end <= y + height
+	}
+
+	/// Reads the requested rectangle from the given drawable.
+	///
+	/// The function returns the image along with the visual of the drawable.
+	fn read_image(&self, ctx: &Context, id: NonZeroU32) -> Result<(Image, u32), Error> {
+		if let Some(pixmap) = ctx.get_pixmap(id) {
+			let (width, height) = pixmap.get_image().get_size();
+			if !self.is_inside(0, 0, width as _, height as _) {
+				return Err(Error::Match);
+			}
+
+			let img =
+				pixmap
+					.get_image()
+					.sub_image(self.x as _, self.y as _, self.width, self.height)?;
+			return Ok((img, 0));
+		}
+
+		let win = ctx.get_window(id).ok_or(Error::Drawable(self.drawable))?;
+		if !win.is_viewable() {
+			return Err(Error::Match);
+		}
+		// The rectangle must be inside of the window, border included
+		let rect = win.get_rectangle();
+		let bw = win.get_border_width() as i32;
+		if !self.is_inside(
+			-bw,
+			-bw,
+			rect.width as i32 + bw * 2,
+			rect.height as i32 + bw * 2,
+		) {
+			return Err(Error::Match);
+		}
+
+		// The rectangle must be on screen
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+		let root_rect = ctx.get_window(root).unwrap().get_rectangle();
+		let (win_x, win_y) = win.get_absolute_position(ctx);
+		let (x, y) = (win_x + self.x as i32, win_y + self.y as i32);
+		let inside_root = x >= 0
+			&& y >= 0 && x + self.width as i32 <= root_rect.width as i32
+			&& y + self.height as i32 <= root_rect.height as i32;
+		if !inside_root {
+			return Err(Error::Match);
+		}
+
+		let screen = ctx
+			.get_screen_for_root(root)
+			.map(|i| &ctx.get_screens()[i])
+			.ok_or(Error::Match)?;
+		let mut img = screen
+			.read_image(x, y, self.width, self.height, win.get_depth())
+			.ok_or(Error::Match)?;
+		// Obscured parts are read from the contents kept offscreen
+		let visible = win.get_visible_region(ctx, true);
+		win.read_backing(&mut img, self.x as _, self.y as _, &visible);
+		Ok((img, win.attributes.visual))
+	}
+}
+
+impl Request for GetImage {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let id = NonZeroU32::new(self.drawable)
+			.ok_or(HandleError::Client(Error::Drawable(self.drawable)))?;
+		let (img, visual) = self.read_image(ctx, id).map_err(HandleError::Client)?;
+
+		let data = match self.format {
+			Format::XYPixmap => img.to_xy_pixmap(self.plane_mask),
+			_ => img.to_z_pixmap(self.plane_mask),
+		};
+		let len = data.len();
+
+		let hdr = GetImageReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			depth: img.get_depth(),
+			seq_nbr,
+			reply_length: ((len + protocol::pad(len)) / 4) as u32,
+			visual,
+			_padding: [0; 20],
+		};
+		client.write_obj(&hdr).map_err(HandleError::IO)?;
+		client.write(&data).map_err(HandleError::IO)?;
+		let pad: [u8; 4] = [0; 4];
+		client
+			.write(&pad[..protocol::pad(len)])
+			.map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GetImage`.
+///
+/// `format` is the format of the returned image.
+pub fn read(buff: &[u8], format: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<GetImageHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &GetImageHdr = unsafe { util::reinterpret(&buff[0]) };
+	// Bitmaps cannot be read
+	let format = match format.try_into()? {
+		Format::XYBitmap => return Err(Error::Value(format as _)),
+		f => f,
+	};
+
+	Ok(Some(Box::new(GetImage {
+		format,
+		drawable: hdr.drawable,
+		x: hdr.x,
+		y: hdr.y,
+		width: hdr.width,
+		height: hdr.height,
+		plane_mask: hdr.plane_mask,
+	})))
+}
@@ -0,0 +1,105 @@
+//! The `GetKeyboardMapping` request returns the keysyms associated with a range of keycodes.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::keyboard::MAX_KEYCODE;
+use crate::ctx::keyboard::MIN_KEYCODE;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::keysym::Keysym;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::slice;
+
+/// The header of the request.
+#[repr(C, packed)]
+struct GetKeyboardMappingHdr {
+	/// The first keycode.
+	first_keycode: u8,
+	/// The number of keycodes.
+	count: u8,
+
+	/// Padding.
+	_padding: u16,
+}
+
+/// The header of the reply.
+#[repr(C, packed)]
+struct GetKeyboardMappingReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// The number of keysyms associated with each keycode.
+	keysyms_per_keycode: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// Padding.
+	_padding: [u8; 24],
+}
+
+/// Structure representing the request.
+pub struct GetKeyboardMapping {
+	/// The first keycode.
+	first_keycode: u8,
+	/// The number of keycodes.
+	count: u8,
+}
+
+impl Request for GetKeyboardMapping {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		if self.first_keycode < MIN_KEYCODE {
+			return Err(HandleError::Client(Error::Value(self.first_keycode as _)));
+		}
+		let last = self.first_keycode as u32 + self.count as u32;
+		if self.count > 0 && last - 1 > MAX_KEYCODE as u32 {
+			return Err(HandleError::Client(Error::Value(self.count as _)));
+		}
+
+		let keymap = ctx.get_keyboard().get_keymap();
+		let keysyms: Vec<Keysym> = (self.first_keycode as u32..last)
+			.flat_map(|k| keymap.get(k as _).iter().cloned())
+			.collect();
+
+		let reply = GetKeyboardMappingReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			keysyms_per_keycode: keymap.get_keysyms_per_keycode(),
+			seq_nbr,
+			reply_length: keysyms.len() as _,
+
+			_padding: [0; 24],
+		};
+		client.write_obj(&reply).map_err(HandleError::IO)?;
+
+		let data = unsafe {
+			slice::from_raw_parts(
+				keysyms.as_ptr() as *const u8,
+				keysyms.len() * size_of::<Keysym>(),
+			)
+		};
+		client.write(data).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GetKeyboardMapping`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<GetKeyboardMappingHdr>() {
+		return Ok(None);
+	}
+	let hdr: &GetKeyboardMappingHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(GetKeyboardMapping {
+		first_keycode: hdr.first_keycode,
+		count: hdr.count,
+	})))
+}
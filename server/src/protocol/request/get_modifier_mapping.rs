@@ -0,0 +1,56 @@
+//! The `GetModifierMapping` request returns the keycodes of the keys used as modifiers.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+
+/// The header of the reply.
+#[repr(C, packed)]
+struct GetModifierMappingReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// The number of keycodes associated with each modifier.
+	keycodes_per_modifier: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// Padding.
+	_padding: [u8; 24],
+}
+
+/// Structure representing the request.
+pub struct GetModifierMapping {}
+
+impl Request for GetModifierMapping {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let map = ctx.get_keyboard().get_modifier_map();
+
+		let reply = GetModifierMappingReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			keycodes_per_modifier: map.get_keycodes_per_modifier(),
+			seq_nbr,
+			reply_length: map.get_keycodes_per_modifier() as u32 * 2,
+
+			_padding: [0; 24],
+		};
+		client.write_obj(&reply).map_err(HandleError::IO)?;
+		client.write(map.get_keycodes()).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GetModifierMapping`.
+pub fn read(_buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	Ok(Some(Box::new(GetModifierMapping {})))
+}
@@ -0,0 +1,144 @@
+//! The `GetMotionEvents` request returns the positions of the pointer recorded in the motion
+//! history during a time range.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+use std::slice;
+
+/// The header of the request.
+#[repr(C, packed)]
+struct GetMotionEventsHdr {
+	/// The window.
+	window: u32,
+	/// The beginning of the time range.
+	start: u32,
+	/// The end of the time range.
+	stop: u32,
+}
+
+/// The header of the reply.
+#[repr(C, packed)]
+struct GetMotionEventsReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// The number of entries following the header.
+	events_len: u32,
+
+	/// Padding.
+	_padding1: [u8; 20],
+}
+
+/// An entry of the reply.
+#[repr(C, packed)]
+struct TimeCoord {
+	/// The time of the entry in milliseconds.
+	time: u32,
+	/// The X position of the pointer relative to the window.
+	x: i16,
+	/// The Y position of the pointer relative to the window.
+	y: i16,
+}
+
+/// Structure representing the request.
+pub struct GetMotionEvents {
+	/// The window.
+	window: u32,
+	/// The beginning of the time range. Zero stands for the current time.
+	start: u32,
+	/// The end of the time range. Zero stands for the current time.
+	stop: u32,
+}
+
+impl Request for GetMotionEvents {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let wid =
+			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+
+		let now = ctx.get_timestamp();
+		let start = if self.start == 0 { now } else { self.start };
+		// A stop time in the future is equivalent to the current time
+		let stop = if self.stop == 0 || self.stop > now {
+			now
+		} else {
+			self.stop
+		};
+
+		let screen = NonZeroU32::new(win.get_root()).and_then(|r| ctx.get_screen_for_root(r));
+		let (win_x, win_y) = win.get_absolute_position(ctx);
+
+		let events: Vec<TimeCoord> = match screen {
+			Some(screen) if start <= stop => ctx
+				.get_pointer()
+				.get_history()
+				.range(start, stop)
+				.filter(|e| e.screen == screen)
+				.map(|e| TimeCoord {
+					time: e.time,
+					x: (e.x as i32 - win_x) as _,
+					y: (e.y as i32 - win_y) as _,
+				})
+				.collect(),
+
+			_ => vec![],
+		};
+
+		let reply = GetMotionEventsReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: (events.len() * 2) as _,
+
+			events_len: events.len() as _,
+
+			_padding1: [0; 20],
+		};
+		client.write_obj(&reply).map_err(HandleError::IO)?;
+
+		let data = unsafe {
+			slice::from_raw_parts(
+				events.as_ptr() as *const u8,
+				events.len() * size_of::<TimeCoord>(),
+			)
+		};
+		client.write(data).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GetMotionEvents`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<GetMotionEventsHdr>() {
+		return Ok(None);
+	}
+	let hdr: &GetMotionEventsHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(GetMotionEvents {
+		window: hdr.window,
+		start: hdr.start,
+		stop: hdr.stop,
+	})))
+}
@@ -0,0 +1,66 @@
+//! The `GetPointerControl` request returns the acceleration settings of the pointer.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+
+/// The reply.
+#[repr(C, packed)]
+struct GetPointerControlReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// The numerator of the acceleration fraction.
+	acceleration_numerator: u16,
+	/// The denominator of the acceleration fraction.
+	acceleration_denominator: u16,
+	/// The threshold above which acceleration applies.
+	threshold: u16,
+
+	/// Padding.
+	_padding1: [u8; 18],
+}
+
+/// Structure representing the request.
+pub struct GetPointerControl {}
+
+impl Request for GetPointerControl {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let control = ctx.get_acceleration().get_control();
+
+		let reply = GetPointerControlReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: 0,
+
+			acceleration_numerator: control.numerator,
+			acceleration_denominator: control.denominator,
+			threshold: control.threshold,
+
+			_padding1: [0; 18],
+		};
+		client.write_obj(&reply).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GetPointerControl`.
+pub fn read(_buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	Ok(Some(Box::new(GetPointerControl {})))
+}
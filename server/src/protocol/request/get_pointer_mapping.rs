@@ -0,0 +1,62 @@
+//! The `GetPointerMapping` request returns the logical button associated with each physical
+//! button of the pointer.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::pad;
+use crate::protocol::request::HandleError;
+
+/// The header of the reply.
+#[repr(C, packed)]
+struct GetPointerMappingReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// The number of physical buttons.
+	map_len: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// Padding.
+	_padding: [u8; 24],
+}
+
+/// Structure representing the request.
+pub struct GetPointerMapping {}
+
+impl Request for GetPointerMapping {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let mapping = ctx.get_pointer().get_mapping();
+		let len = mapping.len() + pad(mapping.len());
+
+		let reply = GetPointerMappingReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			map_len: mapping.len() as _,
+			seq_nbr,
+			reply_length: (len / 4) as _,
+
+			_padding: [0; 24],
+		};
+		client.write_obj(&reply).map_err(HandleError::IO)?;
+
+		let mut data = mapping.to_vec();
+		data.resize(len, 0);
+		client.write(&data).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `GetPointerMapping`.
+pub fn read(_buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	Ok(Some(Box::new(GetPointerMapping {})))
+}
@@ -104,8 +104,8 @@ impl Request for GetWindowAttributes {
 				0
 			},
 			colormap: win.attributes.colormap,
-			all_event_masks: win.attributes.event_mask,
-			your_event_mask: 0, // TODO
+			all_event_masks: win.get_all_event_masks(),
+			your_event_mask: win.get_event_mask(client.get_id()),
 			do_not_propagate_mask: win.attributes.do_not_propagate_mask as _,
 			_padding: [0; 2],
 		};
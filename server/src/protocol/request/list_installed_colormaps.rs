@@ -0,0 +1,96 @@
+//! The `ListInstalledColormaps` request returns the colormaps installed on the screen of a
+//! window.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `ListInstalledColormaps` request.
+#[repr(C, packed)]
+pub struct ListInstalledColormapsHdr {
+	/// The window.
+	window: u32,
+}
+
+/// The header of the reply.
+#[repr(C, packed)]
+struct ListInstalledColormapsReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// Padding.
+	_padding0: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// The number of colormaps.
+	cmaps_len: u16,
+
+	/// Padding.
+	_padding1: [u8; 22],
+}
+
+/// Structure representing the request.
+pub struct ListInstalledColormaps {
+	/// The window.
+	window: u32,
+}
+
+impl Request for ListInstalledColormaps {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let win = NonZeroU32::new(self.window)
+			.and_then(|wid| ctx.get_window(wid))
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+		let cmaps: Vec<u32> = ctx
+			.get_screen_for_root(root)
+			.map(|i| ctx.get_screens()[i].get_installed_colormaps())
+			.unwrap_or_default()
+			.iter()
+			.map(|c| c.get())
+			.collect();
+
+		let reply = ListInstalledColormapsReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding0: 0,
+			seq_nbr,
+			reply_length: cmaps.len() as _,
+
+			cmaps_len: cmaps.len() as _,
+
+			_padding1: [0; 22],
+		};
+		client.write_obj(&reply).map_err(HandleError::IO)?;
+
+		let data: Vec<u8> = cmaps.iter().flat_map(|c| c.to_ne_bytes()).collect();
+		client.write(&data).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `ListInstalledColormaps`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ListInstalledColormapsHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &ListInstalledColormapsHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(ListInstalledColormaps {
+		window: hdr.window,
+	})))
+}
@@ -0,0 +1,64 @@
+//! The `MapSubwindows` request maps the children of a window, from the top-most to the
+//! bottom-most. Parts of windows that become visible are exposed.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `MapSubwindows` request.
+#[repr(C, packed)]
+pub struct MapSubwindowsHdr {
+	/// The window.
+	window: u32,
+}
+
+/// Structure representing the request.
+pub struct MapSubwindows {
+	/// The window.
+	window: u32,
+}
+
+impl Request for MapSubwindows {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let wid =
+			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+		let children = win.get_children().to_vec();
+
+		let prev = ctx.get_visibility(root);
+		for child in children.iter().rev() {
+			ctx.save_under(*child, &prev);
+			ctx.map_window(*child);
+		}
+		ctx.expose(root, &prev);
+
+		Ok(())
+	}
+}
+
+/// Parses `MapSubwindows`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<MapSubwindowsHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &MapSubwindowsHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(MapSubwindows {
+		window: hdr.window,
+	})))
+}
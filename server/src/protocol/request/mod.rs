@@ -1,20 +1,67 @@
 //! This module implements each requests of the X protocol.
 
+pub mod bell;
+pub mod change_gc;
+pub mod change_keyboard_control;
+pub mod change_keyboard_mapping;
+pub mod change_pointer_control;
 pub mod change_property;
 pub mod change_window_attributes;
+pub mod circulate_window;
+pub mod clear_area;
+pub mod configure_window;
+pub mod copy_area;
+pub mod copy_colormap_and_free;
+pub mod copy_gc;
+pub mod copy_plane;
+pub mod create_colormap;
 pub mod create_gc;
+pub mod create_pixmap;
 pub mod create_window;
+pub mod fill_poly;
+pub mod free_colormap;
+pub mod free_gc;
+pub mod free_pixmap;
 pub mod get_atom_name;
 pub mod get_geometry;
+pub mod get_image;
+pub mod get_keyboard_control;
+pub mod get_keyboard_mapping;
+pub mod get_modifier_mapping;
+pub mod get_motion_events;
+pub mod get_pointer_control;
+pub mod get_pointer_mapping;
 pub mod get_property;
 pub mod get_selection_owner;
 pub mod get_window_attributes;
 pub mod grab_server;
+pub mod install_colormap;
 pub mod intern_atom;
+pub mod list_installed_colormaps;
+pub mod map_subwindows;
+pub mod map_window;
 pub mod no_operation;
+pub mod poly_arc;
+pub mod poly_fill_arc;
+pub mod poly_fill_rectangle;
+pub mod poly_line;
+pub mod poly_point;
+pub mod poly_rectangle;
+pub mod poly_segment;
+pub mod put_image;
 pub mod query_extension;
+pub mod query_keymap;
 pub mod query_pointer;
+pub mod reparent_window;
+pub mod set_clip_rectangles;
+pub mod set_dashes;
+pub mod set_modifier_mapping;
+pub mod set_pointer_mapping;
 pub mod ungrab_server;
+pub mod uninstall_colormap;
+pub mod unmap_subwindows;
+pub mod unmap_window;
+pub mod warp_pointer;
 
 use crate::ctx::client::Client;
 use crate::ctx::Context;
@@ -295,6 +342,13 @@ pub fn build_request(
 		CREATE_WINDOW => create_window::read(buff, optional),
 		CHANGE_WINDOW_ATTRIBUTES => change_window_attributes::read(buff, optional),
 		GET_WINDOW_ATTRIBUTES => get_window_attributes::read(buff, optional),
+		REPARENT_WINDOW => reparent_window::read(buff, optional),
+		MAP_WINDOW => map_window::read(buff, optional),
+		MAP_SUBWINDOWS => map_subwindows::read(buff, optional),
+		UNMAP_WINDOW => unmap_window::read(buff, optional),
+		UNMAP_SUBWINDOWS => unmap_subwindows::read(buff, optional),
+		CONFIGURE_WINDOW => configure_window::read(buff, optional),
+		CIRCULATE_WINDOW => circulate_window::read(buff, optional),
 		GET_GEOMETRY => get_geometry::read(buff, optional),
 		INTERN_ATOM => intern_atom::read(buff, optional),
 		GET_ATOM_NAME => get_atom_name::read(buff, optional),
@@ -304,8 +358,48 @@ pub fn build_request(
 		GRAB_SERVER => grab_server::read(buff, optional),
 		UNGRAB_SERVER => ungrab_server::read(buff, optional),
 		QUERY_POINTER => query_pointer::read(buff, optional),
+		GET_MOTION_EVENTS => get_motion_events::read(buff, optional),
+		WARP_POINTER => warp_pointer::read(buff, optional),
+		QUERY_KEYMAP => query_keymap::read(buff, optional),
+		CREATE_PIXMAP => create_pixmap::read(buff, optional),
+		FREE_PIXMAP => free_pixmap::read(buff, optional),
 		CREATE_GC => create_gc::read(buff, optional),
+		CHANGE_GC => change_gc::read(buff, optional),
+		COPY_GC => copy_gc::read(buff, optional),
+		SET_DASHES => set_dashes::read(buff, optional),
+		SET_CLIP_RECTANGLES => set_clip_rectangles::read(buff, optional),
+		FREE_GC => free_gc::read(buff, optional),
+		CLEAR_AREA => clear_area::read(buff, optional),
+		COPY_AREA => copy_area::read(buff, optional),
+		COPY_PLANE => copy_plane::read(buff, optional),
+		POLY_POINT => poly_point::read(buff, optional),
+		POLY_LINE => poly_line::read(buff, optional),
+		POLY_SEGMENT => poly_segment::read(buff, optional),
+		POLY_RECTANGLE => poly_rectangle::read(buff, optional),
+		POLY_ARC => poly_arc::read(buff, optional),
+		FILL_POLY => fill_poly::read(buff, optional),
+		POLY_FILL_RECTANGLE => poly_fill_rectangle::read(buff, optional),
+		POLY_FILL_ARC => poly_fill_arc::read(buff, optional),
+		PUT_IMAGE => put_image::read(buff, optional),
+		GET_IMAGE => get_image::read(buff, optional),
+		CREATE_COLORMAP => create_colormap::read(buff, optional),
+		FREE_COLORMAP => free_colormap::read(buff, optional),
+		COPY_COLORMAP_AND_FREE => copy_colormap_and_free::read(buff, optional),
+		INSTALL_COLORMAP => install_colormap::read(buff, optional),
+		UNINSTALL_COLORMAP => uninstall_colormap::read(buff, optional),
+		LIST_INSTALLED_COLORMAPS => list_installed_colormaps::read(buff, optional),
 		QUERY_EXTENSION => query_extension::read(buff, optional),
+		CHANGE_KEYBOARD_MAPPING => change_keyboard_mapping::read(buff, optional),
+		GET_KEYBOARD_MAPPING => get_keyboard_mapping::read(buff, optional),
+		CHANGE_KEYBOARD_CONTROL => change_keyboard_control::read(buff, optional),
+		GET_KEYBOARD_CONTROL => get_keyboard_control::read(buff, optional),
+		BELL => bell::read(buff, optional),
+		CHANGE_POINTER_CONTROL => change_pointer_control::read(buff, optional),
+		GET_POINTER_CONTROL => get_pointer_control::read(buff, optional),
+		SET_POINTER_MAPPING => set_pointer_mapping::read(buff, optional),
+		GET_POINTER_MAPPING => get_pointer_mapping::read(buff, optional),
+		SET_MODIFIER_MAPPING => set_modifier_mapping::read(buff, optional),
+		GET_MODIFIER_MAPPING => get_modifier_mapping::read(buff, optional),
 		NO_OPERATION => no_operation::read(buff, optional),
 
 		// TODO _ => Err(Error::Request),
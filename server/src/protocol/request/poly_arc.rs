@@ -0,0 +1,67 @@
+//! The `PolyArc` request draws the outlines of arcs.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::drawing;
+use crate::drawing::arc;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Arc;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolyArc` request.
+#[repr(C, packed)]
+pub struct PolyArcHdr {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct PolyArc {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The arcs.
+	arcs: Vec<Arc>,
+}
+
+impl Request for PolyArc {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		drawing::draw(ctx, self.drawable, self.gc, |canvas| {
+			arc::poly_arc(canvas, &self.arcs)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Reads a list of arcs from the given buffer.
+pub fn read_arcs(buff: &[u8]) -> Vec<Arc> {
+	buff.chunks_exact(size_of::<Arc>())
+		.map(|a| *unsafe { util::reinterpret::<_, Arc>(&a[0]) })
+		.collect()
+}
+
+/// Parses `PolyArc`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyArcHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyArcHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(PolyArc {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		arcs: read_arcs(&buff[size_of::<PolyArcHdr>()..]),
+	})))
+}
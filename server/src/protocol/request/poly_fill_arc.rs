@@ -0,0 +1,61 @@
+//! The `PolyFillArc` request fills arcs.
+
+use super::poly_arc;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::drawing;
+use crate::drawing::arc;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Arc;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolyFillArc` request.
+#[repr(C, packed)]
+pub struct PolyFillArcHdr {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct PolyFillArc {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The arcs.
+	arcs: Vec<Arc>,
+}
+
+impl Request for PolyFillArc {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		drawing::draw(ctx, self.drawable, self.gc, |canvas| {
+			arc::poly_fill_arc(canvas, &self.arcs)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `PolyFillArc`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyFillArcHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyFillArcHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(PolyFillArc {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		arcs: poly_arc::read_arcs(&buff[size_of::<PolyFillArcHdr>()..]),
+	})))
+}
@@ -0,0 +1,61 @@
+//! The `PolyFillRectangle` request fills rectangles.
+
+use super::poly_rectangle;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::drawing;
+use crate::drawing::polygon;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Rectangle;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolyFillRectangle` request.
+#[repr(C, packed)]
+pub struct PolyFillRectangleHdr {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct PolyFillRectangle {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The rectangles.
+	rectangles: Vec<Rectangle>,
+}
+
+impl Request for PolyFillRectangle {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		drawing::draw(ctx, self.drawable, self.gc, |canvas| {
+			polygon::poly_fill_rectangle(canvas, &self.rectangles)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `PolyFillRectangle`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyFillRectangleHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyFillRectangleHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(PolyFillRectangle {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		rectangles: poly_rectangle::read_rectangles(&buff[size_of::<PolyFillRectangleHdr>()..]),
+	})))
+}
@@ -0,0 +1,65 @@
+//! The `PolyLine` request draws lines going through a list of points.
+
+use super::poly_point;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::drawing;
+use crate::drawing::line;
+use crate::drawing::CoordinateMode;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolyLine` request.
+#[repr(C, packed)]
+pub struct PolyLineHdr {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct PolyLine {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The points, relative to the origin of the drawable.
+	points: Vec<(i32, i32)>,
+}
+
+impl Request for PolyLine {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		drawing::draw(ctx, self.drawable, self.gc, |canvas| {
+			line::poly_line(canvas, &self.points)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `PolyLine`.
+///
+/// `coordinate_mode` is the way coordinates of points are interpreted.
+pub fn read(buff: &[u8], coordinate_mode: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyLineHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyLineHdr = unsafe { util::reinterpret(&buff[0]) };
+	let mode: CoordinateMode = coordinate_mode.try_into()?;
+	let points = poly_point::read_points(&buff[size_of::<PolyLineHdr>()..]);
+
+	Ok(Some(Box::new(PolyLine {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		points: mode.resolve(&points),
+	})))
+}
@@ -0,0 +1,72 @@
+//! The `PolyPoint` request draws points.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::drawing;
+use crate::drawing::line;
+use crate::drawing::CoordinateMode;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Point;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolyPoint` request.
+#[repr(C, packed)]
+pub struct PolyPointHdr {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct PolyPoint {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The points, relative to the origin of the drawable.
+	points: Vec<(i32, i32)>,
+}
+
+impl Request for PolyPoint {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		drawing::draw(ctx, self.drawable, self.gc, |canvas| {
+			line::poly_point(canvas, &self.points)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Reads a list of points from the given buffer.
+pub fn read_points(buff: &[u8]) -> Vec<Point> {
+	buff.chunks_exact(size_of::<Point>())
+		.map(|p| *unsafe { util::reinterpret::<_, Point>(&p[0]) })
+		.collect()
+}
+
+/// Parses `PolyPoint`.
+///
+/// `coordinate_mode` is the way coordinates of points are interpreted.
+pub fn read(buff: &[u8], coordinate_mode: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyPointHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyPointHdr = unsafe { util::reinterpret(&buff[0]) };
+	let mode: CoordinateMode = coordinate_mode.try_into()?;
+	let points = read_points(&buff[size_of::<PolyPointHdr>()..]);
+
+	Ok(Some(Box::new(PolyPoint {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		points: mode.resolve(&points),
+	})))
+}
@@ -0,0 +1,67 @@
+//! The `PolyRectangle` request draws the outlines of rectangles.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::drawing;
+use crate::drawing::line;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Rectangle;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolyRectangle` request.
+#[repr(C, packed)]
+pub struct PolyRectangleHdr {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct PolyRectangle {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The rectangles.
+	rectangles: Vec<Rectangle>,
+}
+
+impl Request for PolyRectangle {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		drawing::draw(ctx, self.drawable, self.gc, |canvas| {
+			line::poly_rectangle(canvas, &self.rectangles)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Reads a list of rectangles from the given buffer.
+pub fn read_rectangles(buff: &[u8]) -> Vec<Rectangle> {
+	buff.chunks_exact(size_of::<Rectangle>())
+		.map(|r| unsafe { util::reinterpret::<_, Rectangle>(&r[0]) }.clone())
+		.collect()
+}
+
+/// Parses `PolyRectangle`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolyRectangleHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolyRectangleHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(PolyRectangle {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		rectangles: read_rectangles(&buff[size_of::<PolyRectangleHdr>()..]),
+	})))
+}
@@ -0,0 +1,72 @@
+//! The `PolySegment` request draws independent lines.
+
+use super::poly_point;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::drawing;
+use crate::drawing::line;
+use crate::drawing::line::Segment;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+
+/// Header of the `PolySegment` request.
+#[repr(C, packed)]
+pub struct PolySegmentHdr {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+}
+
+/// Structure representing the request.
+pub struct PolySegment {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The segments, each given by its two endpoints.
+	segments: Vec<Segment>,
+}
+
+impl Request for PolySegment {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		drawing::draw(ctx, self.drawable, self.gc, |canvas| {
+			line::poly_segment(canvas, &self.segments)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `PolySegment`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PolySegmentHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PolySegmentHdr = unsafe { util::reinterpret(&buff[0]) };
+	// A segment is made of two points
+	let points = poly_point::read_points(&buff[size_of::<PolySegmentHdr>()..]);
+	let segments = points
+		.chunks_exact(2)
+		.map(|s| {
+			(
+				(s[0].x as i32, s[0].y as i32),
+				(s[1].x as i32, s[1].y as i32),
+			)
+		})
+		.collect();
+
+	Ok(Some(Box::new(PolySegment {
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		segments,
+	})))
+}
@@ -0,0 +1,132 @@
+//! The `PutImage` request draws an image on a drawable.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::image;
+use crate::ctx::image::Format;
+use crate::ctx::image::Image;
+use crate::ctx::Context;
+use crate::drawing;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `PutImage` request.
+#[repr(C, packed)]
+pub struct PutImageHdr {
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The width of the image.
+	width: u16,
+	/// The height of the image.
+	height: u16,
+	/// The X position of the image on the drawable.
+	dst_x: i16,
+	/// The Y position of the image on the drawable.
+	dst_y: i16,
+	/// The number of bits to skip at the beginning of each scanline.
+	left_pad: u8,
+	/// The depth of the image.
+	depth: u8,
+	/// Padding.
+	_padding: u16,
+}
+
+/// Structure representing the request.
+pub struct PutImage {
+	/// The format of the image.
+	format: Format,
+	/// The drawable to draw on.
+	drawable: u32,
+	/// The graphics context.
+	gc: u32,
+	/// The width of the image.
+	width: u16,
+	/// The height of the image.
+	height: u16,
+	/// The X position of the image on the drawable.
+	dst_x: i16,
+	/// The Y position of the image on the drawable.
+	dst_y: i16,
+	/// The number of bits to skip at the beginning of each scanline.
+	left_pad: u8,
+	/// The depth of the image.
+	depth: u8,
+	/// The image's data.
+	data: Vec<u8>,
+}
+
+impl PutImage {
+	/// Decodes the image.
+	///
+	/// `drawable_depth` is the depth of the drawable the image is drawn on.
+	fn decode(&self, drawable_depth: u8) -> Result<Image, Error> {
+		match self.format {
+			Format::XYBitmap if self.depth != 1 => return Err(Error::Match),
+			Format::XYPixmap | Format::ZPixmap if self.depth != drawable_depth => {
+				return Err(Error::Match)
+			}
+			Format::ZPixmap if self.left_pad != 0 => return Err(Error::Match),
+			_ if self.left_pad >= image::SCANLINE_PAD => return Err(Error::Match),
+			_ => {}
+		}
+
+		let (width, height) = (self.width, self.height);
+		match self.format {
+			Format::XYBitmap | Format::XYPixmap => {
+				Image::from_xy_pixmap(self.depth, width, height, self.left_pad, &self.data)
+			}
+			Format::ZPixmap => Image::from_z_pixmap(self.depth, width, height, &self.data),
+		}
+	}
+}
+
+impl Request for PutImage {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let depth = NonZeroU32::new(self.drawable)
+			.and_then(|id| ctx.get_drawable(id))
+			.ok_or(HandleError::Client(Error::Drawable(self.drawable)))?
+			.get_depth();
+		let img = self.decode(depth).map_err(HandleError::Client)?;
+
+		let bitmap = self.format == Format::XYBitmap;
+		drawing::draw(ctx, self.drawable, self.gc, |canvas| {
+			canvas.put_image(&img, None, self.dst_x as _, self.dst_y as _, bitmap)
+		})
+		.map_err(HandleError::Client)
+	}
+}
+
+/// Parses `PutImage`.
+///
+/// `format` is the format of the image.
+pub fn read(buff: &[u8], format: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<PutImageHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &PutImageHdr = unsafe { util::reinterpret(&buff[0]) };
+	let format: Format = format.try_into()?;
+
+	Ok(Some(Box::new(PutImage {
+		format,
+		drawable: hdr.drawable,
+		gc: hdr.gc,
+		width: hdr.width,
+		height: hdr.height,
+		dst_x: hdr.dst_x,
+		dst_y: hdr.dst_y,
+		left_pad: hdr.left_pad,
+		depth: hdr.depth,
+		data: buff[size_of::<PutImageHdr>()..].to_vec(),
+	})))
+}
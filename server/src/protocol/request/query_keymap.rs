@@ -0,0 +1,53 @@
+//! The `QueryKeymap` request returns the state of the keys of the keyboard.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+
+/// The reply.
+#[repr(C, packed)]
+struct QueryKeymapReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// Padding.
+	_padding: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// Bitmap of the keys currently pressed, indexed by keycode.
+	keys: [u8; 32],
+}
+
+/// Structure representing the request.
+pub struct QueryKeymap {}
+
+impl Request for QueryKeymap {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let reply = QueryKeymapReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			_padding: 0,
+			seq_nbr,
+			reply_length: 2,
+
+			keys: *ctx.get_keyboard().get_keys(),
+		};
+		client.write_obj(&reply).map_err(HandleError::IO)?;
+
+		Ok(())
+	}
+}
+
+/// Parses `QueryKeymap`.
+pub fn read(_buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	Ok(Some(Box::new(QueryKeymap {})))
+}
@@ -1,8 +1,10 @@
-//! TODO doc
+//! The `QueryPointer` request returns the position of the pointer and the state of buttons and
+//! modifiers.
 
 use super::Request;
 use crate::ctx::client::Client;
 use crate::ctx::Context;
+use crate::ctx::Drawable;
 use crate::protocol;
 use crate::protocol::error::Error;
 use crate::protocol::request::HandleError;
@@ -36,7 +38,7 @@ struct QueryPointerReply {
 	/// The Y position of the pointer relative to the given window.
 	win_y: i16,
 
-	/// TODO doc
+	/// The state of buttons and modifiers.
 	mask: u16,
 
 	/// Padding.
@@ -65,25 +67,47 @@ impl Request for QueryPointer {
 	) -> Result<(), HandleError> {
 		let wid =
 			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
-		let _win = ctx
-			.get_window_mut(wid)
+		let win = ctx
+			.get_window(wid)
 			.ok_or(HandleError::Client(Error::Window(self.window)))?;
 
+		let root = ctx
+			.get_pointer_root()
+			.map(|r| r.get())
+			.unwrap_or(win.get_root());
+		let (root_x, root_y) = ctx.get_pointer().get_position();
+
+		// If the pointer is not on the window's screen, window coordinates are not reported
+		let same_screen = win.get_root() == root;
+		let (win_x, win_y, child) = if same_screen {
+			let (x, y) = win.get_absolute_position(ctx);
+			let win_x = root_x as i32 - x;
+			let win_y = root_y as i32 - y;
+			let child = win
+				.child_at(ctx, win_x, win_y)
+				.map(|c| c.get())
+				.unwrap_or(0);
+
+			(win_x as i16, win_y as i16, child)
+		} else {
+			(0, 0, 0)
+		};
+
 		let hdr = QueryPointerReply {
 			reply_type: protocol::REPLY_TYPE_REPLY,
-			same_screen: 1, // TODO
+			same_screen: same_screen as _,
 			seq_nbr,
 			reply_length: 0,
 
-			root: 1,  // TODO
-			child: 0, // TODO
+			root,
+			child,
 
-			root_x: 0, // TODO
-			root_y: 0, // TODO
-			win_x: 0,  // TODO
-			win_y: 0,  // TODO
+			root_x,
+			root_y,
+			win_x,
+			win_y,
 
-			mask: 0, // TODO
+			mask: ctx.get_input_state(),
 
 			_padding: [0; 6],
 		};
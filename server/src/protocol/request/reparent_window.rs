@@ -0,0 +1,104 @@
+//! The `ReparentWindow` request moves a window to another parent on the same screen. Parts of
+//! windows that become visible are exposed.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::window::BackgroundPixmap;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Class;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `ReparentWindow` request.
+#[repr(C, packed)]
+pub struct ReparentWindowHdr {
+	/// The window.
+	window: u32,
+	/// The new parent.
+	parent: u32,
+	/// The X position of the window relative to its new parent.
+	x: i16,
+	/// The Y position of the window relative to its new parent.
+	y: i16,
+}
+
+/// Structure representing the request.
+pub struct ReparentWindow {
+	/// The window.
+	window: u32,
+	/// The new parent.
+	parent: u32,
+	/// The X position of the window relative to its new parent.
+	x: i16,
+	/// The Y position of the window relative to its new parent.
+	y: i16,
+}
+
+impl Request for ReparentWindow {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let wid =
+			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let parent_id =
+			NonZeroU32::new(self.parent).ok_or(HandleError::Client(Error::Window(self.parent)))?;
+		let parent = ctx
+			.get_window(parent_id)
+			.ok_or(HandleError::Client(Error::Window(self.parent)))?;
+
+		if win.is_root()
+			|| parent_id == wid
+			|| parent.is_inferior_of(ctx, wid)
+			|| parent.get_root() != win.get_root()
+		{
+			return Err(HandleError::Client(Error::Match));
+		}
+		// A `ParentRelative` background requires the depth of the parent
+		let parent_relative = win.attributes.background_pixel.is_none()
+			&& matches!(
+				win.attributes.background_pixmap,
+				BackgroundPixmap::ParentRelative
+			);
+		if parent_relative && parent.get_depth() != win.get_depth() {
+			return Err(HandleError::Client(Error::Match));
+		}
+		if parent.attributes.class == Class::InputOnly && win.attributes.class != Class::InputOnly
+		{
+			return Err(HandleError::Client(Error::Match));
+		}
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+
+		let mut prev = ctx.get_visibility(root);
+		ctx.reparent_window(wid, parent_id, self.x, self.y);
+		ctx.restore_save_under(wid, &mut prev);
+		ctx.expose(root, &prev);
+
+		Ok(())
+	}
+}
+
+/// Parses `ReparentWindow`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<ReparentWindowHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &ReparentWindowHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(ReparentWindow {
+		window: hdr.window,
+		parent: hdr.parent,
+		x: hdr.x,
+		y: hdr.y,
+	})))
+}
@@ -0,0 +1,84 @@
+//! The `SetClipRectangles` request restricts drawing with a graphics context to a list of
+//! rectangles.
+
+use super::poly_rectangle;
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::gc::Clip;
+use crate::ctx::gc::Ordering;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::protocol::Rectangle;
+use crate::region::Region;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `SetClipRectangles` request.
+#[repr(C, packed)]
+pub struct SetClipRectanglesHdr {
+	/// The ID of the graphics context.
+	gc: u32,
+	/// The X origin of the clip.
+	clip_x_origin: i16,
+	/// The Y origin of the clip.
+	clip_y_origin: i16,
+}
+
+/// Structure representing the request.
+pub struct SetClipRectangles {
+	/// The ID of the graphics context.
+	gc: u32,
+	/// The X origin of the clip.
+	clip_x_origin: i16,
+	/// The Y origin of the clip.
+	clip_y_origin: i16,
+	/// The ordering of rectangles, as specified by the client.
+	ordering: Ordering,
+	/// The list of rectangles.
+	rectangles: Vec<Rectangle>,
+}
+
+impl Request for SetClipRectangles {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let gc = NonZeroU32::new(self.gc)
+			.and_then(|id| ctx.get_gc_mut(id))
+			.ok_or(HandleError::Client(Error::GContext(self.gc)))?;
+		if !self.ordering.check(&self.rectangles) {
+			return Err(HandleError::Client(Error::Match));
+		}
+
+		gc.clip_x_origin = self.clip_x_origin;
+		gc.clip_y_origin = self.clip_y_origin;
+		gc.clip = Clip::Rectangles(Region::from_rectangles(&self.rectangles));
+
+		Ok(())
+	}
+}
+
+/// Parses `SetClipRectangles`.
+///
+/// `ordering` is the ordering of the rectangles.
+pub fn read(buff: &[u8], ordering: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<SetClipRectanglesHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &SetClipRectanglesHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	let rectangles = poly_rectangle::read_rectangles(&buff[size_of::<SetClipRectanglesHdr>()..]);
+
+	Ok(Some(Box::new(SetClipRectangles {
+		gc: hdr.gc,
+		clip_x_origin: hdr.clip_x_origin,
+		clip_y_origin: hdr.clip_y_origin,
+		ordering: ordering.try_into()?,
+		rectangles,
+	})))
+}
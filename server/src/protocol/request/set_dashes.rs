@@ -0,0 +1,73 @@
+//! The `SetDashes` request sets the dash pattern of a graphics context.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `SetDashes` request.
+#[repr(C, packed)]
+pub struct SetDashesHdr {
+	/// The ID of the graphics context.
+	gc: u32,
+	/// The offset in the dash pattern at which lines start.
+	dash_offset: u16,
+	/// The number of dashes.
+	dashes_len: u16,
+}
+
+/// Structure representing the request.
+pub struct SetDashes {
+	/// The ID of the graphics context.
+	gc: u32,
+	/// The offset in the dash pattern at which lines start.
+	dash_offset: u16,
+	/// The lengths of dashes.
+	dashes: Vec<u8>,
+}
+
+impl Request for SetDashes {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		if self.dashes.is_empty() || self.dashes.contains(&0) {
+			return Err(HandleError::Client(Error::Value(0)));
+		}
+
+		let gc = NonZeroU32::new(self.gc)
+			.and_then(|id| ctx.get_gc_mut(id))
+			.ok_or(HandleError::Client(Error::GContext(self.gc)))?;
+		gc.dash_offset = self.dash_offset;
+		gc.dashes = self.dashes.clone();
+
+		Ok(())
+	}
+}
+
+/// Parses `SetDashes`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<SetDashesHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &SetDashesHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	let begin = size_of::<SetDashesHdr>();
+	let end = begin + hdr.dashes_len as usize;
+	if buff.len() < end {
+		return Err(Error::Length);
+	}
+
+	Ok(Some(Box::new(SetDashes {
+		gc: hdr.gc,
+		dash_offset: hdr.dash_offset,
+		dashes: buff[begin..end].to_vec(),
+	})))
+}
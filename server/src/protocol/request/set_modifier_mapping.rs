@@ -0,0 +1,107 @@
+//! The `SetModifierMapping` request sets the keycodes of the keys used as modifiers.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::keyboard::ModifierMap;
+use crate::ctx::keyboard::MAX_KEYCODE;
+use crate::ctx::keyboard::MIN_KEYCODE;
+use crate::ctx::keyboard::MODIFIERS_COUNT;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::event;
+use crate::protocol::event::MappingNotifyEvent;
+use crate::protocol::request::HandleError;
+
+/// The header of the reply.
+#[repr(C, packed)]
+struct SetModifierMappingReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// The status of the operation.
+	status: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// Padding.
+	_padding: [u8; 24],
+}
+
+/// Structure representing the request.
+pub struct SetModifierMapping {
+	/// The number of keycodes associated with each modifier.
+	keycodes_per_modifier: u8,
+	/// The keycodes, `keycodes_per_modifier` for each modifier.
+	keycodes: Vec<u8>,
+}
+
+impl Request for SetModifierMapping {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		// Zero stands for an unused entry
+		if let Some(k) = self
+			.keycodes
+			.iter()
+			.find(|k| **k != 0 && !(MIN_KEYCODE..=MAX_KEYCODE).contains(*k))
+		{
+			return Err(HandleError::Client(Error::Value(*k as _)));
+		}
+
+		let map = ModifierMap::new(self.keycodes_per_modifier, self.keycodes.clone());
+		// A key cannot act as several modifiers at once
+		let status = if map.has_shared_keycodes() {
+			protocol::MAPPING_FAILED
+		} else if ctx.get_keyboard_mut().set_modifier_map(map) {
+			protocol::MAPPING_SUCCESS
+		} else {
+			protocol::MAPPING_BUSY
+		};
+
+		let reply = SetModifierMappingReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			status,
+			seq_nbr,
+			reply_length: 0,
+
+			_padding: [0; 24],
+		};
+		client.write_obj(&reply).map_err(HandleError::IO)?;
+
+		if status == protocol::MAPPING_SUCCESS {
+			ctx.broadcast_event(&MappingNotifyEvent {
+				code: event::Event::MappingNotify as _,
+				_padding0: 0,
+				seq_nbr: 0,
+
+				request: event::MAPPING_MODIFIER,
+				first_keycode: 0,
+				count: 0,
+
+				_padding1: [0; 25],
+			});
+		}
+
+		Ok(())
+	}
+}
+
+/// Parses `SetModifierMapping`.
+///
+/// `keycodes_per_modifier` is the number of keycodes associated with each modifier.
+pub fn read(buff: &[u8], keycodes_per_modifier: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	let len = keycodes_per_modifier as usize * MODIFIERS_COUNT;
+	if buff.len() < len {
+		return Err(Error::Length);
+	}
+
+	Ok(Some(Box::new(SetModifierMapping {
+		keycodes_per_modifier,
+		keycodes: buff[..len].to_vec(),
+	})))
+}
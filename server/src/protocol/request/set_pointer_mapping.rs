@@ -0,0 +1,102 @@
+//! The `SetPointerMapping` request sets the logical button associated with each physical button
+//! of the pointer.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::pointer::BUTTONS_COUNT;
+use crate::ctx::Context;
+use crate::protocol;
+use crate::protocol::error::Error;
+use crate::protocol::event;
+use crate::protocol::event::MappingNotifyEvent;
+use crate::protocol::request::HandleError;
+
+/// The header of the reply.
+#[repr(C, packed)]
+struct SetPointerMappingReply {
+	/// The type of the reply (normal).
+	reply_type: u8,
+	/// The status of the operation.
+	status: u8,
+	/// Sequence number.
+	seq_nbr: u16,
+	/// The length of the reply in units of 4 bytes.
+	reply_length: u32,
+
+	/// Padding.
+	_padding: [u8; 24],
+}
+
+/// Structure representing the request.
+pub struct SetPointerMapping {
+	/// The logical button of each physical button. Zero disables the button.
+	map: Vec<u8>,
+}
+
+impl Request for SetPointerMapping {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		client: &mut Client,
+		seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let map: [u8; BUTTONS_COUNT] = self
+			.map
+			.as_slice()
+			.try_into()
+			.map_err(|_| HandleError::Client(Error::Value(self.map.len() as _)))?;
+
+		// A logical button cannot be associated with several physical buttons
+		for (i, b) in map.iter().enumerate() {
+			if *b != 0 && map[..i].contains(b) {
+				return Err(HandleError::Client(Error::Value(*b as _)));
+			}
+		}
+
+		let status = if ctx.get_pointer_mut().set_mapping(map) {
+			protocol::MAPPING_SUCCESS
+		} else {
+			protocol::MAPPING_BUSY
+		};
+
+		let reply = SetPointerMappingReply {
+			reply_type: protocol::REPLY_TYPE_REPLY,
+			status,
+			seq_nbr,
+			reply_length: 0,
+
+			_padding: [0; 24],
+		};
+		client.write_obj(&reply).map_err(HandleError::IO)?;
+
+		if status == protocol::MAPPING_SUCCESS {
+			ctx.broadcast_event(&MappingNotifyEvent {
+				code: event::Event::MappingNotify as _,
+				_padding0: 0,
+				seq_nbr: 0,
+
+				request: event::MAPPING_POINTER,
+				first_keycode: 0,
+				count: 0,
+
+				_padding1: [0; 25],
+			});
+		}
+
+		Ok(())
+	}
+}
+
+/// Parses `SetPointerMapping`.
+///
+/// `map_len` is the number of elements in the map.
+pub fn read(buff: &[u8], map_len: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	let len = map_len as usize;
+	if buff.len() < len {
+		return Err(Error::Length);
+	}
+
+	Ok(Some(Box::new(SetPointerMapping {
+		map: buff[..len].to_vec(),
+	})))
+}
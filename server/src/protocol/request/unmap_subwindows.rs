@@ -0,0 +1,64 @@
+//! The `UnmapSubwindows` request unmaps the children of a window, from the bottom-most to the
+//! top-most. Parts of windows that become visible are exposed.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `UnmapSubwindows` request.
+#[repr(C, packed)]
+pub struct UnmapSubwindowsHdr {
+	/// The window.
+	window: u32,
+}
+
+/// Structure representing the request.
+pub struct UnmapSubwindows {
+	/// The window.
+	window: u32,
+}
+
+impl Request for UnmapSubwindows {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let wid =
+			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+		let children = win.get_children().to_vec();
+
+		let mut prev = ctx.get_visibility(root);
+		for child in children.iter() {
+			ctx.unmap_window(*child, false);
+			ctx.restore_save_under(*child, &mut prev);
+		}
+		ctx.expose(root, &prev);
+
+		Ok(())
+	}
+}
+
+/// Parses `UnmapSubwindows`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<UnmapSubwindowsHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &UnmapSubwindowsHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(UnmapSubwindows {
+		window: hdr.window,
+	})))
+}
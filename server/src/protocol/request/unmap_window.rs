@@ -0,0 +1,60 @@
+//! The `UnmapWindow` request unmaps a window. Parts of windows that become visible are exposed.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// Header of the `UnmapWindow` request.
+#[repr(C, packed)]
+pub struct UnmapWindowHdr {
+	/// The window.
+	window: u32,
+}
+
+/// Structure representing the request.
+pub struct UnmapWindow {
+	/// The window.
+	window: u32,
+}
+
+impl Request for UnmapWindow {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		let wid =
+			NonZeroU32::new(self.window).ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.window)))?;
+		let root = NonZeroU32::new(win.get_root()).unwrap();
+
+		let mut prev = ctx.get_visibility(root);
+		ctx.unmap_window(wid, false);
+		ctx.restore_save_under(wid, &mut prev);
+		ctx.expose(root, &prev);
+
+		Ok(())
+	}
+}
+
+/// Parses `UnmapWindow`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<UnmapWindowHdr>() {
+		return Ok(None);
+	}
+
+	let hdr: &UnmapWindowHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(UnmapWindow {
+		window: hdr.window,
+	})))
+}
@@ -0,0 +1,163 @@
+//! The `WarpPointer` request moves the pointer, as if the user had moved it.
+
+use super::Request;
+use crate::ctx::client::Client;
+use crate::ctx::Context;
+use crate::ctx::Drawable;
+use crate::protocol::error::Error;
+use crate::protocol::request::HandleError;
+use crate::util;
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+/// The header of the request.
+#[repr(C, packed)]
+struct WarpPointerHdr {
+	/// The source window.
+	src_window: u32,
+	/// The destination window.
+	dst_window: u32,
+
+	/// The X position of the rectangle in the source window.
+	src_x: i16,
+	/// The Y position of the rectangle in the source window.
+	src_y: i16,
+	/// The width of the rectangle in the source window.
+	src_width: u16,
+	/// The height of the rectangle in the source window.
+	src_height: u16,
+
+	/// The X position relative to the destination window.
+	dst_x: i16,
+	/// The Y position relative to the destination window.
+	dst_y: i16,
+}
+
+/// Structure representing the request.
+pub struct WarpPointer {
+	/// The source window. If zero, the pointer is moved regardless of its position.
+	src_window: u32,
+	/// The destination window. If zero, the pointer is moved relative to its current position.
+	dst_window: u32,
+
+	/// The X position of the rectangle in the source window.
+	src_x: i16,
+	/// The Y position of the rectangle in the source window.
+	src_y: i16,
+	/// The width of the rectangle in the source window. If zero, the rectangle extends to the
+	/// right edge of the window.
+	src_width: u16,
+	/// The height of the rectangle in the source window. If zero, the rectangle extends to the
+	/// bottom edge of the window.
+	src_height: u16,
+
+	/// The X position relative to the destination window.
+	dst_x: i16,
+	/// The Y position relative to the destination window.
+	dst_y: i16,
+}
+
+impl WarpPointer {
+	/// Tells whether the pointer is inside of the source rectangle.
+	///
+	/// `ctx` is the current context.
+	fn is_in_src(&self, ctx: &Context) -> Result<bool, HandleError> {
+		let Some(wid) = NonZeroU32::new(self.src_window) else {
+			return Ok(true);
+		};
+		let win = ctx
+			.get_window(wid)
+			.ok_or(HandleError::Client(Error::Window(self.src_window)))?;
+
+		if Some(win.get_root()) != ctx.get_pointer_root().map(|r| r.get()) {
+			return Ok(false);
+		}
+
+		let (x, y) = win.get_absolute_position(ctx);
+		let (pointer_x, pointer_y) = ctx.get_pointer().get_position();
+		let x = pointer_x as i32 - x;
+		let y = pointer_y as i32 - y;
+
+		let rect = win.get_rectangle();
+		if !(0..rect.width as i32).contains(&x) || !(0..rect.height as i32).contains(&y) {
+			return Ok(false);
+		}
+
+		let width = match self.src_width {
+			0 => rect.width as i32 - self.src_x as i32,
+			w => w as i32,
+		};
+		let height = match self.src_height {
+			0 => rect.height as i32 - self.src_y as i32,
+			h => h as i32,
+		};
+		let x = x - self.src_x as i32;
+		let y = y - self.src_y as i32;
+
+		Ok((0..width).contains(&x) && (0..height).contains(&y))
+	}
+}
+
+impl Request for WarpPointer {
+	fn handle(
+		&self,
+		ctx: &mut Context,
+		_client: &mut Client,
+		_seq_nbr: u16,
+	) -> Result<(), HandleError> {
+		// Computing the destination on the virtual desktop
+		let dst = match NonZeroU32::new(self.dst_window) {
+			Some(wid) => {
+				let win = ctx
+					.get_window(wid)
+					.ok_or(HandleError::Client(Error::Window(self.dst_window)))?;
+
+				let root = NonZeroU32::new(win.get_root()).unwrap();
+				ctx.get_screen_for_root(root).map(|screen| {
+					let (screen_x, screen_y) = ctx.get_screens()[screen].get_position();
+					let (x, y) = win.get_absolute_position(ctx);
+
+					(
+						screen_x as i32 + x + self.dst_x as i32,
+						screen_y as i32 + y + self.dst_y as i32,
+					)
+				})
+			}
+
+			None => {
+				let (x, y) = ctx.get_pointer_global_position();
+				Some((x + self.dst_x as i32, y + self.dst_y as i32))
+			}
+		};
+
+		if !self.is_in_src(ctx)? {
+			return Ok(());
+		}
+		if let Some((x, y)) = dst {
+			ctx.move_pointer(x, y);
+		}
+
+		Ok(())
+	}
+}
+
+/// Parses `WarpPointer`.
+pub fn read(buff: &[u8], _: u8) -> Result<Option<Box<dyn Request>>, Error> {
+	if buff.len() < size_of::<WarpPointerHdr>() {
+		return Ok(None);
+	}
+	let hdr: &WarpPointerHdr = unsafe { util::reinterpret(&buff[0]) };
+
+	Ok(Some(Box::new(WarpPointer {
+		src_window: hdr.src_window,
+		dst_window: hdr.dst_window,
+
+		src_x: hdr.src_x,
+		src_y: hdr.src_y,
+		src_width: hdr.src_width,
+		src_height: hdr.src_height,
+
+		dst_x: hdr.dst_x,
+		dst_y: hdr.dst_y,
+	})))
+}
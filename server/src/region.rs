@@ -0,0 +1,592 @@
+//! A region is a set of pixels, represented as a list of rectangles.
+//!
+//! Like in pixman, rectangles are y-x banded:
+//! - Rectangles are sorted by Y, then by X.
+//! - Rectangles sharing scanlines form a band, with the same top and bottom.
+//! - Rectangles of a band neither overlap nor touch.
+//! - Two adjacent bands never have the same spans, since they would be merged.
+//!
+//! A region thus has a single representation, and regions are combined one band at a time.
+
+use crate::protocol::Rectangle;
+use std::cmp::max;
+use std::cmp::min;
+
+/// A rectangle given by its edges. The right and bottom edges are excluded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Bounds {
+	/// The left edge.
+	pub x1: i32,
+	/// The top edge.
+	pub y1: i32,
+	/// The right edge.
+	pub x2: i32,
+	/// The bottom edge.
+	pub y2: i32,
+}
+
+impl Bounds {
+	/// Creates bounds from the given edges.
+	pub fn new(x1: i32, y1: i32, x2: i32, y2: i32) -> Self {
+		Self {
+			x1,
+			y1,
+			x2,
+			y2,
+		}
+	}
+
+	/// Tells whether the bounds contain no pixel.
+	pub fn is_empty(&self) -> bool {
+		self.x1 >= self.x2 || self.y1 >= self.y2
+	}
+
+	/// Tells whether the given pixel is inside of the bounds.
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		(self.x1..self.x2).contains(&x) && (self.y1..self.y2).contains(&y)
+	}
+
+	/// Returns the intersection with the given bounds. If it is empty, the function returns None.
+	pub fn intersect(&self, other: &Self) -> Option<Self> {
+		let b = Self::new(
+			max(self.x1, other.x1),
+			max(self.y1, other.y1),
+			min(self.x2, other.x2),
+			min(self.y2, other.y2),
+		);
+		(!b.is_empty()).then_some(b)
+	}
+
+	/// Returns the bounds moved by the given offset.
+	pub fn translate(&self, dx: i32, dy: i32) -> Self {
+		Self::new(self.x1 + dx, self.y1 + dy, self.x2 + dx, self.y2 + dy)
+	}
+
+	/// Returns the protocol rectangle with the same position and size.
+	pub fn to_rectangle(&self) -> Rectangle {
+		Rectangle {
+			x: self.x1 as _,
+			y: self.y1 as _,
+			width: (self.x2 - self.x1) as _,
+			height: (self.y2 - self.y1) as _,
+		}
+	}
+}
+
+impl From<&Rectangle> for Bounds {
+	fn from(r: &Rectangle) -> Self {
+		let (x, y) = (r.x as i32, r.y as i32);
+		Self::new(x, y, x + r.width as i32, y + r.height as i32)
+	}
+}
+
+/// An operation combining two regions.
+#[derive(Clone, Copy)]
+enum Op {
+	/// Pixels in either region.
+	Union,
+	/// Pixels in both regions.
+	Intersect,
+	/// Pixels in the first region but not in the second.
+	Subtract,
+}
+
+impl Op {
+	/// Tells whether a pixel belongs to the result, given whether it belongs to each region.
+	fn apply(self, a: bool, b: bool) -> bool {
+		match self {
+			Self::Union => a || b,
+			Self::Intersect => a && b,
+			Self::Subtract => a && !b,
+		}
+	}
+}
+
+/// Returns the rectangles of the band of `rects` covering the scanline `y`.
+///
+/// `start` is the index of the first rectangle that may cover the scanline. It is advanced so that
+/// scanlines must be given in increasing order.
+fn get_band<'a>(rects: &'a [Bounds], start: &mut usize, y: i32) -> &'a [Bounds] {
+	while *start < rects.len() && rects[*start].y2 <= y {
+		*start += 1;
+	}
+	let band = &rects[*start..];
+	if band.first().is_none_or(|r| r.y1 > y) {
+		return &[];
+	}
+
+	let len = band.iter().take_while(|r| r.y1 == band[0].y1).count();
+	&band[..len]
+}
+
+/// Combines two sorted lists of disjoint spans with the given operation.
+///
+/// Spans are given as `(x1, x2)`, the end excluded. The result is sorted, and its spans neither
+/// overlap nor touch.
+fn combine_spans(a: &[(i32, i32)], b: &[(i32, i32)], op: Op) -> Vec<(i32, i32)> {
+	let mut xs: Vec<i32> = a.iter().chain(b).flat_map(|(x1, x2)| [*x1, *x2]).collect();
+	xs.sort_unstable();
+	xs.dedup();
+
+	let mut res: Vec<(i32, i32)> = vec![];
+	let (mut ia, mut ib) = (0, 0);
+	for w in xs.windows(2) {
+		let (x1, x2) = (w[0], w[1]);
+		while ia < a.len() && a[ia].1 <= x1 {
+			ia += 1;
+		}
+		while ib < b.len() && b[ib].1 <= x1 {
+			ib += 1;
+		}
+		let in_a = a.get(ia).is_some_and(|s| s.0 <= x1);
+		let in_b = b.get(ib).is_some_and(|s| s.0 <= x1);
+		if !op.apply(in_a, in_b) {
+			continue;
+		}
+
+		match res.last_mut() {
+			Some(last) if last.1 == x1 => last.1 = x2,
+			_ => res.push((x1, x2)),
+		}
+	}
+
+	res
+}
+
+/// A set of pixels, stored as y-x banded rectangles.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Region {
+	/// The rectangles of the region.
+	rects: Vec<Bounds>,
+	/// The smallest bounds containing the region.
+	extents: Bounds,
+}
+
+impl Region {
+	/// Creates an empty region.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a region made of the given bounds.
+	pub fn from_bounds(bounds: Bounds) -> Self {
+		if bounds.is_empty() {
+			return Self::default();
+		}
+
+		Self {
+			rects: vec![bounds],
+			extents: bounds,
+		}
+	}
+
+	/// Creates a region made of the union of the given rectangles.
+	///
+	/// The region is built in a single pass over the rectangles sorted by their top, instead of
+	/// one union per rectangle.
+	pub fn from_rectangles(rects: &[Rectangle]) -> Self {
+		let mut rects: Vec<Bounds> = rects
+			.iter()
+			.map(Bounds::from)
+			.filter(|b| !b.is_empty())
+			.collect();
+		rects.sort_unstable_by_key(|r| r.y1);
+		let mut ys: Vec<i32> = rects.iter().flat_map(|r| [r.y1, r.y2]).collect();
+		ys.sort_unstable();
+		ys.dedup();
+
+		let mut res = Self::default();
+		// The rectangles covering the current band
+		let mut active: Vec<Bounds> = vec![];
+		let mut next = 0;
+		for w in ys.windows(2) {
+			let (y1, y2) = (w[0], w[1]);
+			active.retain(|r| r.y2 > y1);
+			while next < rects.len() && rects[next].y1 <= y1 {
+				active.push(rects[next]);
+				next += 1;
+			}
+
+			active.sort_unstable_by_key(|r| r.x1);
+			let mut spans: Vec<(i32, i32)> = vec![];
+			for r in &active {
+				match spans.last_mut() {
+					Some(last) if r.x1 <= last.1 => last.1 = max(last.1, r.x2),
+					_ => spans.push((r.x1, r.x2)),
+				}
+			}
+			res.push_band(y1, y2, &spans);
+		}
+
+		res.update_extents();
+		res
+	}
+
+	/// Tells whether the region contains no pixel.
+	pub fn is_empty(&self) -> bool {
+		self.rects.is_empty()
+	}
+
+	/// Returns the smallest bounds containing the region.
+	pub fn get_extents(&self) -> Bounds {
+		self.extents
+	}
+
+	/// Returns the rectangles of the region, in y-x banded order.
+	pub fn iter(&self) -> impl Iterator<Item = &Bounds> {
+		self.rects.iter()
+	}
+
+	/// Returns the bands of the region, from top to bottom.
+	pub fn iter_bands(&self) -> impl Iterator<Item = &[Bounds]> {
+		self.rects.chunk_by(|a, b| a.y1 == b.y1)
+	}
+
+	/// Returns the rectangles of the region, bands sorted from top to bottom if `down` is true,
+	/// and rectangles of each band sorted from left to right if `right` is true.
+	///
+	/// This allows copying regions onto themselves without overwriting pixels before they are
+	/// read.
+	pub fn get_sorted(&self, down: bool, right: bool) -> Vec<Bounds> {
+		let mut bands: Vec<&[Bounds]> = self.iter_bands().collect();
+		if !down {
+			bands.reverse();
+		}
+
+		bands
+			.into_iter()
+			.flat_map(|band| {
+				let mut band = band.to_vec();
+				if !right {
+					band.reverse();
+				}
+				band
+			})
+			.collect()
+	}
+
+	/// Returns the rectangles of the region as protocol rectangles, in y-x banded order.
+	pub fn to_rectangles(&self) -> Vec<Rectangle> {
+		self.rects.iter().map(Bounds::to_rectangle).collect()
+	}
+
+	/// Tells whether the given pixel is inside of the region.
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		if !self.extents.contains(x, y) {
+			return false;
+		}
+
+		let mut start = self.rects.partition_point(|r| r.y2 <= y);
+		let band = get_band(&self.rects, &mut start, y);
+		let i = band.partition_point(|r| r.x2 <= x);
+		band.get(i).is_some_and(|r| r.x1 <= x)
+	}
+
+	/// Appends a band with the given spans at the bottom of the region, merging it with the last
+	/// band if they are adjacent and have the same spans.
+	fn push_band(&mut self, y1: i32, y2: i32, spans: &[(i32, i32)]) {
+		if spans.is_empty() {
+			return;
+		}
+
+		let last_y1 = self.rects.last().map(|r| r.y1);
+		let last_len = self
+			.rects
+			.iter()
+			.rev()
+			.take_while(|r| Some(r.y1) == last_y1)
+			.count();
+		let len = self.rects.len();
+		let last = &mut self.rects[(len - last_len)..];
+		let same = last.len() == spans.len()
+			&& last[0].y2 == y1
+			&& last.iter().zip(spans).all(|(r, s)| (r.x1, r.x2) == *s);
+		if same {
+			last.iter_mut().for_each(|r| r.y2 = y2);
+		} else {
+			self.rects
+				.extend(spans.iter().map(|(x1, x2)| Bounds::new(*x1, y1, *x2, y2)));
+		}
+	}
+
+	/// Computes the extents of the region from its rectangles.
+	fn update_extents(&mut self) {
+		self.extents =
+			self.rects
+				.iter()
+				.fold(self.rects.first().copied().unwrap_or_default(), |e, r| {
+					Bounds::new(
+						min(e.x1, r.x1),
+						min(e.y1, r.y1),
+						max(e.x2, r.x2),
+						max(e.y2, r.y2),
+					)
+				});
+	}
+
+	/// Combines the region with another, with the given operation.
+	fn combine(&self, other: &Self, op: Op) -> Self {
+		let mut ys: Vec<i32> = self
+			.rects
+			.iter()
+			.chain(&other.rects)
+			.flat_map(|r| [r.y1, r.y2])
+			.collect();
+		ys.sort_unstable();
+		ys.dedup();
+
+		let mut res = Self::default();
+		let (mut ia, mut ib) = (0, 0);
+		for w in ys.windows(2) {
+			let (y1, y2) = (w[0], w[1]);
+			let a: Vec<(i32, i32)> = get_band(&self.rects, &mut ia, y1)
+				.iter()
+				.map(|r| (r.x1, r.x2))
+				.collect();
+			let b: Vec<(i32, i32)> = get_band(&other.rects, &mut ib, y1)
+				.iter()
+				.map(|r| (r.x1, r.x2))
+				.collect();
+			res.push_band(y1, y2, &combine_spans(&a, &b, op));
+		}
+
+		res.update_extents();
+		res
+	}
+
+	/// Returns the union of the region with another.
+	pub fn union(&self, other: &Self) -> Self {
+		if self.is_empty() {
+			return other.clone();
+		}
+		if other.is_empty() {
+			return self.clone();
+		}
+
+		self.combine(other, Op::Union)
+	}
+
+	/// Returns the intersection of the region with another.
+	pub fn intersect(&self, other: &Self) -> Self {
+		if self.extents.intersect(&other.extents).is_none() {
+			return Self::default();
+		}
+
+		self.combine(other, Op::Intersect)
+	}
+
+	/// Returns the pixels of the region that are not in `other`.
+	pub fn subtract(&self, other: &Self) -> Self {
+		if self.extents.intersect(&other.extents).is_none() {
+			return self.clone();
+		}
+
+		self.combine(other, Op::Subtract)
+	}
+
+	/// Returns the pixels of the given bounds that are not in the region.
+	pub fn inverse(&self, bounds: Bounds) -> Self {
+		Self::from_bounds(bounds).subtract(self)
+	}
+
+	/// Moves the region by the given offset.
+	pub fn translate(&mut self, dx: i32, dy: i32) {
+		for r in &mut self.rects {
+			*r = r.translate(dx, dy);
+		}
+		if !self.is_empty() {
+			self.extents = self.extents.translate(dx, dy);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashSet;
+
+	/// The number of random cases checked by each test.
+	const CASES: usize = 500;
+	/// Random rectangles are drawn in the square from `-AREA` to `AREA`.
+	const AREA: i32 = 8;
+
+	/// A set of pixels, used as a reference.
+	type Pixels = HashSet<(i32, i32)>;
+
+	/// A xorshift generator, seeded so that failures can be reproduced.
+	struct Rng(u64);
+
+	impl Rng {
+		fn next(&mut self) -> u64 {
+			self.0 ^= self.0 << 13;
+			self.0 ^= self.0 >> 7;
+			self.0 ^= self.0 << 17;
+			self.0
+		}
+
+		/// Returns a number in the given range, end excluded.
+		fn range(&mut self, begin: i32, end: i32) -> i32 {
+			begin + (self.next() % (end - begin) as u64) as i32
+		}
+
+		/// Returns random bounds, possibly empty.
+		fn bounds(&mut self) -> Bounds {
+			let x1 = self.range(-AREA, AREA);
+			let y1 = self.range(-AREA, AREA);
+			Bounds::new(x1, y1, x1 + self.range(0, 8), y1 + self.range(0, 8))
+		}
+
+		/// Returns a random region, made of the union of a few rectangles, along with its pixels.
+		fn region(&mut self) -> (Region, Pixels) {
+			let rects: Vec<Bounds> = (0..self.range(0, 6)).map(|_| self.bounds()).collect();
+			let region = Region::from_rectangles(
+				&rects.iter().map(Bounds::to_rectangle).collect::<Vec<_>>(),
+			);
+			let pixels = rects.iter().flat_map(|r| bounds_pixels(*r)).collect();
+			(region, pixels)
+		}
+	}
+
+	/// Returns the pixels of the given bounds.
+	fn bounds_pixels(b: Bounds) -> impl Iterator<Item = (i32, i32)> {
+		(b.y1..b.y2).flat_map(move |y| (b.x1..b.x2).map(move |x| (x, y)))
+	}
+
+	/// Returns the pixels of the given region, checking that its rectangles do not overlap.
+	fn pixels(region: &Region) -> Pixels {
+		let mut pixels = Pixels::new();
+		for p in region.iter().flat_map(|r| bounds_pixels(*r)) {
+			assert!(pixels.insert(p), "{p:?} is covered twice in {region:?}");
+		}
+		pixels
+	}
+
+	/// Checks that the region is y-x banded, as described in the module documentation.
+	fn check_invariants(region: &Region) {
+		let rects: Vec<Bounds> = region.iter().copied().collect();
+		assert!(rects.iter().all(|r| !r.is_empty()), "{region:?}");
+
+		let bands: Vec<&[Bounds]> = region.iter_bands().collect();
+		for band in &bands {
+			// Rectangles of a band have the same top and bottom, sorted without touching
+			assert!(
+				band.iter()
+					.all(|r| (r.y1, r.y2) == (band[0].y1, band[0].y2)),
+				"{region:?}"
+			);
+			assert!(band.windows(2).all(|w| w[0].x2 < w[1].x1), "{region:?}");
+		}
+		for w in bands.windows(2) {
+			let (a, b) = (w[0], w[1]);
+			assert!(a[0].y2 <= b[0].y1, "{region:?}");
+			// Adjacent bands with the same spans must have been merged
+			let same =
+				a.len() == b.len() && a.iter().zip(b).all(|(a, b)| (a.x1, a.x2) == (b.x1, b.x2));
+			assert!(a[0].y2 < b[0].y1 || !same, "{region:?}");
+		}
+
+		let extents = rects
+			.iter()
+			.fold(rects.first().copied().unwrap_or_default(), |e, r| {
+				Bounds::new(
+					min(e.x1, r.x1),
+					min(e.y1, r.y1),
+					max(e.x2, r.x2),
+					max(e.y2, r.y2),
+				)
+			});
+		assert_eq!(region.get_extents(), extents, "{region:?}");
+	}
+
+	/// Checks that the region is valid and made of the given pixels.
+	fn check(region: &Region, expected: &Pixels) {
+		check_invariants(region);
+		assert_eq!(&pixels(region), expected, "{region:?}");
+	}
+
+	#[test]
+	fn from_rectangles() {
+		let mut rng = Rng(0x9e3779b97f4a7c15);
+		for _ in 0..CASES {
+			let (region, expected) = rng.region();
+			check(&region, &expected);
+		}
+	}
+
+	#[test]
+	fn union() {
+		let mut rng = Rng(0x2545f4914f6cdd1d);
+		for _ in 0..CASES {
+			let (a, pa) = rng.region();
+			let (b, pb) = rng.region();
+			let res = a.union(&b);
+			check(&res, &pa.union(&pb).copied().collect());
+			// A region has a single representation
+			assert_eq!(res, b.union(&a));
+		}
+	}
+
+	#[test]
+	fn intersect() {
+		let mut rng = Rng(0x6a09e667f3bcc908);
+		for _ in 0..CASES {
+			let (a, pa) = rng.region();
+			let (b, pb) = rng.region();
+			let res = a.intersect(&b);
+			check(&res, &pa.intersection(&pb).copied().collect());
+			assert_eq!(res, b.intersect(&a));
+		}
+	}
+
+	#[test]
+	fn subtract() {
+		let mut rng = Rng(0xbb67ae8584caa73b);
+		for _ in 0..CASES {
+			let (a, pa) = rng.region();
+			let (b, pb) = rng.region();
+			check(&a.subtract(&b), &pa.difference(&pb).copied().collect());
+		}
+	}
+
+	#[test]
+	fn inverse() {
+		let mut rng = Rng(0x3c6ef372fe94f82b);
+		for _ in 0..CASES {
+			let (region, pixels) = rng.region();
+			let bounds = rng.bounds();
+			let expected = bounds_pixels(bounds)
+				.filter(|p| !pixels.contains(p))
+				.collect();
+			check(&region.inverse(bounds), &expected);
+		}
+	}
+
+	#[test]
+	fn translate() {
+		let mut rng = Rng(0xa54ff53a5f1d36f1);
+		for _ in 0..CASES {
+			let (mut region, pixels) = rng.region();
+			let (dx, dy) = (rng.range(-AREA, AREA), rng.range(-AREA, AREA));
+			region.translate(dx, dy);
+			let expected = pixels.iter().map(|(x, y)| (x + dx, y + dy)).collect();
+			check(&region, &expected);
+		}
+	}
+
+	#[test]
+	fn contains() {
+		let mut rng = Rng(0x510e527fade682d1);
+		for _ in 0..CASES {
+			let (region, pixels) = rng.region();
+			for y in -AREA - 1..2 * AREA + 1 {
+				for x in -AREA - 1..2 * AREA + 1 {
+					assert_eq!(
+						region.contains(x, y),
+						pixels.contains(&(x, y)),
+						"({x}, {y}) in {region:?}"
+					);
+				}
+			}
+		}
+	}
+}